@@ -0,0 +1,127 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Procedural macros for the Exonum blockchain framework.
+//!
+//! Currently, the crate provides the `ServiceSchema` derive macro generating
+//! the database schema of a service from a declaration of its indexes.
+
+#![recursion_limit = "128"]
+#![deny(missing_debug_implementations, missing_docs)]
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+mod service_schema;
+
+use proc_macro::TokenStream;
+
+/// Derives the database schema of a service from the declaration of its indexes.
+///
+/// The macro is applied to a struct with a single type parameter, which corresponds to
+/// the database view (`&Snapshot` or `&mut Fork`). The struct must contain exactly one field
+/// with the type of the parameter to store the view. Every other field declares an index
+/// of the schema and must have the type `PhantomData<Index<T, ...>>`, where `Index` is
+/// one of the index types from `exonum::storage`, and `T` is the type parameter of the struct.
+/// The fields are only markers for the macro, so they must be private.
+///
+/// The macro generates:
+///
+/// - `new` constructor accepting the database view
+/// - for every index, the read-only accessor with the same name as the field, returning
+///   the index over `&Snapshot`
+/// - for every index, the mutable accessor with the `_mut` suffix, returning the index over
+///   `&mut Fork`; mutable accessors are available for schemas created from `&mut Fork`
+/// - `state_hash` method returning the root hashes of the indexes marked with
+///   `#[schema(state_hash = ...)]` in the order of their positions. The result is
+///   intended to be returned from `Service::state_hash`.
+///
+/// The accessors are private unless their visibility is set with `#[schema(vis = "...")]`;
+/// the documentation of a field is used as the documentation of its read-only accessor.
+///
+/// # Attributes
+///
+/// The struct must be annotated with `#[schema(service_name = "...")]`. The service name
+/// is used as a prefix of the index names: the index declared by the `wallets` field
+/// of the `cryptocurrency` service is named `cryptocurrency.wallets`.
+///
+/// The fields can be annotated with the following attributes:
+///
+/// - `#[schema(state_hash = 0)]` includes the root hash of the index into the state hash
+///   of the service at the specified position. The positions of the indexes must be
+///   `0, 1, 2` and so on. They are specified explicitly rather than derived from the order
+///   of the fields, as the state hash is a part of the consensus, and reordering the fields
+///   must not change it. The index must be Merkelized, for example, `ProofMapIndex`,
+///   `ProofListIndex` or `ProofEntry`.
+/// - `#[schema(no_state_hash)]` excludes a Merkelized index from the state hash. Every
+///   Merkelized index, except for index families, must be marked either with `state_hash`
+///   or with `no_state_hash`, so that an index cannot be left out of the state hash
+///   by mistake.
+/// - `#[schema(vis = "pub")]` sets the visibility of the accessors of the index, for example,
+///   `"pub"` or `"pub(crate)"`.
+/// - `#[schema(family = "Key")]` declares a family of indexes; the accessors take a key
+///   of the specified type and create the index with `new_in_family`. Index families cannot
+///   be included into the state hash.
+/// - `#[schema(name = "...")]` sets the full name of the index instead of the name derived
+///   from the service name and the name of the field. The attribute allows to keep the names
+///   of the indexes in existing databases.
+///
+/// # Examples
+///
+/// ```
+/// extern crate exonum;
+/// #[macro_use]
+/// extern crate exonum_derive;
+///
+/// use exonum::crypto::{Hash, PublicKey};
+/// use exonum::storage::{Database, MemoryDB, ProofListIndex, ProofMapIndex};
+///
+/// use std::marker::PhantomData;
+///
+/// /// Database schema of the cryptocurrency service.
+/// #[derive(Debug, ServiceSchema)]
+/// #[schema(service_name = "cryptocurrency")]
+/// pub struct CurrencySchema<T> {
+///     view: T,
+///     /// Balances of the wallets.
+///     #[schema(state_hash = 0, vis = "pub")]
+///     balances: PhantomData<ProofMapIndex<T, PublicKey, u64>>,
+///     /// History of the wallet with the given public key.
+///     #[schema(family = "PublicKey", vis = "pub")]
+///     history: PhantomData<ProofListIndex<T, Hash>>,
+/// }
+///
+/// # fn main() {
+/// let db = MemoryDB::new();
+/// let mut fork = db.fork();
+/// let key = PublicKey::zero();
+/// {
+///     let mut schema = CurrencySchema::new(&mut fork);
+///     schema.balances_mut().put(&key, 100);
+///     schema.history_mut(&key).push(Hash::zero());
+/// }
+///
+/// let schema = CurrencySchema::new(&fork);
+/// assert_eq!(schema.balances().get(&key), Some(100));
+/// assert_eq!(schema.history(&key).len(), 1);
+/// assert_eq!(schema.state_hash(), vec![schema.balances().merkle_root()]);
+/// # }
+/// ```
+#[proc_macro_derive(ServiceSchema, attributes(schema))]
+pub fn service_schema(input: TokenStream) -> TokenStream {
+    service_schema::implement(input)
+}
@@ -0,0 +1,435 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementation of the `ServiceSchema` derive macro.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::{self, Attribute, Data, DeriveInput, Field, Fields, GenericArgument, Ident, Lit, Meta,
+          NestedMeta, Path, PathArguments, Type, Visibility};
+
+use std::mem;
+
+const ATTRIBUTE: &str = "schema";
+/// Merkelized index types, which should be either included into the state hash
+/// or explicitly excluded from it.
+const MERKELIZED_INDEXES: &[&str] = &[
+    "IndexedProofMapIndex",
+    "ProofEntry",
+    "ProofKeySetIndex",
+    "ProofListIndex",
+    "ProofMapIndex",
+];
+
+/// Index declared by a field of the schema.
+struct SchemaIndex {
+    ident: Ident,
+    vis: Visibility,
+    docs: Vec<Attribute>,
+    name: String,
+    index: Path,
+    params: Vec<Type>,
+    family: Option<Type>,
+    /// Position of the index root hash in the state hash of the service.
+    state_hash: Option<u64>,
+}
+
+impl SchemaIndex {
+    fn from_field(field: &Field, view_param: &Ident, service_name: &str) -> Self {
+        let ident = field.ident.clone().expect("Schema fields should be named");
+        let mut name = format!("{}.{}", service_name, ident);
+        let mut family = None;
+        let mut state_hash = None;
+        let mut no_state_hash = false;
+        let mut vis = Visibility::Inherited;
+
+        match field.vis {
+            Visibility::Inherited => {}
+            _ => panic!(
+                "Schema field `{}` should be private; the visibility of its accessors \
+                 is set with `#[schema(vis = \"...\")]`",
+                ident
+            ),
+        }
+
+        for meta in schema_attributes(&field.attrs) {
+            match meta {
+                Meta::Word(ref word) if word == "state_hash" => panic!(
+                    "Position of index `{}` in the state hash should be specified explicitly, \
+                     e.g., `#[schema(state_hash = 0)]`",
+                    ident
+                ),
+                Meta::NameValue(ref value) if value.ident == "state_hash" => match value.lit {
+                    Lit::Int(ref position) => state_hash = Some(position.value()),
+                    _ => panic!(
+                        "Position of index `{}` in the state hash should be an integer",
+                        ident
+                    ),
+                },
+                Meta::Word(ref word) if word == "no_state_hash" => no_state_hash = true,
+                Meta::NameValue(ref value) if value.ident == "vis" => {
+                    let value = string_value(&value.lit, "vis");
+                    vis = syn::parse_str::<Visibility>(&value).unwrap_or_else(|_| {
+                        panic!("Invalid visibility `{}` of index `{}`", value, ident)
+                    });
+                }
+                Meta::NameValue(ref value) if value.ident == "name" => {
+                    name = string_value(&value.lit, "name");
+                }
+                Meta::NameValue(ref value) if value.ident == "family" => {
+                    let key = string_value(&value.lit, "family");
+                    let key = syn::parse_str::<Type>(&key).unwrap_or_else(|_| {
+                        panic!("Invalid key type `{}` of index family `{}`", key, ident)
+                    });
+                    family = Some(key);
+                }
+                other => panic!(
+                    "Unknown attribute `{}` of schema field `{}`",
+                    other.name(),
+                    ident
+                ),
+            }
+        }
+        if state_hash.is_some() && family.is_some() {
+            panic!(
+                "Index family `{}` cannot be included into the state hash of the service",
+                ident
+            );
+        }
+        if state_hash.is_some() && no_state_hash {
+            panic!(
+                "Index `{}` cannot be both included into and excluded from the state hash",
+                ident
+            );
+        }
+
+        let (index, params) = index_type(&field.ty, view_param).unwrap_or_else(|| {
+            panic!(
+                "Schema field `{}` should have type `PhantomData<Index<{}, ...>>`",
+                ident, view_param
+            )
+        });
+        let is_merkelized = index
+            .segments
+            .iter()
+            .last()
+            .map_or(false, |segment| {
+                MERKELIZED_INDEXES.contains(&segment.ident.to_string().as_str())
+            });
+        if is_merkelized && family.is_none() && state_hash.is_none() && !no_state_hash {
+            panic!(
+                "Merkelized index `{}` should be either included into the state hash \
+                 with `#[schema(state_hash = ...)]` or excluded from it \
+                 with `#[schema(no_state_hash)]`",
+                ident
+            );
+        }
+
+        let docs = field
+            .attrs
+            .iter()
+            .filter(|attr| is_ident(&attr.path, "doc"))
+            .cloned()
+            .collect();
+
+        SchemaIndex {
+            ident,
+            vis,
+            docs,
+            name,
+            index,
+            params,
+            family,
+            state_hash,
+        }
+    }
+
+    fn accessor(&self, view_field: &Ident) -> TokenStream2 {
+        let SchemaIndex {
+            ref ident,
+            ref vis,
+            ref docs,
+            ref name,
+            ref index,
+            ref params,
+            ..
+        } = *self;
+
+        if let Some(ref key) = self.family {
+            quote! {
+                #(#docs)*
+                #vis fn #ident(&self, key: &#key)
+                    -> #index<&::exonum::storage::Snapshot, #(#params),*>
+                {
+                    #index::new_in_family(#name, key, self.#view_field.as_ref())
+                }
+            }
+        } else {
+            quote! {
+                #(#docs)*
+                #vis fn #ident(&self) -> #index<&::exonum::storage::Snapshot, #(#params),*> {
+                    #index::new(#name, self.#view_field.as_ref())
+                }
+            }
+        }
+    }
+
+    fn mut_accessor(&self, view_field: &Ident) -> TokenStream2 {
+        let SchemaIndex {
+            ref ident,
+            ref vis,
+            ref name,
+            ref index,
+            ref params,
+            ..
+        } = *self;
+        let mut_ident = Ident::new(&format!("{}_mut", ident), Span::call_site());
+        let doc = format!(
+            "Mutable version of the [`{0}`](#method.{0}) index.",
+            ident
+        );
+
+        if let Some(ref key) = self.family {
+            quote! {
+                #[doc = #doc]
+                #vis fn #mut_ident(&mut self, key: &#key)
+                    -> #index<&mut ::exonum::storage::Fork, #(#params),*>
+                {
+                    #index::new_in_family(#name, key, &mut *self.#view_field)
+                }
+            }
+        } else {
+            quote! {
+                #[doc = #doc]
+                #vis fn #mut_ident(&mut self)
+                    -> #index<&mut ::exonum::storage::Fork, #(#params),*>
+                {
+                    #index::new(#name, &mut *self.#view_field)
+                }
+            }
+        }
+    }
+}
+
+pub fn implement(input: TokenStream) -> TokenStream {
+    let input: DeriveInput = syn::parse(input).expect("Cannot parse the schema declaration");
+    let schema_name = &input.ident;
+    let view_param = view_param(&input);
+    let service_name = service_name(&input);
+
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => panic!("ServiceSchema can be derived only for structs with named fields"),
+        },
+        _ => panic!("ServiceSchema can be derived only for structs"),
+    };
+
+    let mut view_field = None;
+    let mut indexes = Vec::new();
+    for field in fields {
+        if is_param(&field.ty, &view_param) {
+            if view_field.is_some() {
+                panic!("Schema `{}` should have a single view field", schema_name);
+            }
+            view_field = field.ident.clone();
+        } else {
+            indexes.push(SchemaIndex::from_field(field, &view_param, &service_name));
+        }
+    }
+    let view_field = view_field.unwrap_or_else(|| {
+        panic!(
+            "Schema `{}` should have a field of type `{}` storing the database view",
+            schema_name, view_param
+        )
+    });
+
+    let markers = indexes.iter().map(|index| &index.ident);
+    let accessors = indexes.iter().map(|index| index.accessor(&view_field));
+    let mut_accessors = indexes.iter().map(|index| index.mut_accessor(&view_field));
+    let hashed = state_hash_indexes(&indexes);
+
+    let expanded = quote! {
+        impl<#view_param> #schema_name<#view_param>
+        where
+            #view_param: AsRef<::exonum::storage::Snapshot>,
+        {
+            /// Constructs the schema from the database view.
+            pub fn new(view: #view_param) -> Self {
+                #schema_name {
+                    #view_field: view,
+                    #(#markers: ::std::marker::PhantomData,)*
+                }
+            }
+
+            #(#accessors)*
+
+            /// Returns the root hashes of the indexes included into the state hash
+            /// of the service.
+            pub fn state_hash(&self) -> Vec<::exonum::crypto::Hash> {
+                vec![#(self.#hashed().merkle_root()),*]
+            }
+        }
+
+        impl<'a> #schema_name<&'a mut ::exonum::storage::Fork> {
+            #(#mut_accessors)*
+        }
+    };
+    expanded.into()
+}
+
+/// Returns the identifiers of the indexes included into the state hash, ordered by their
+/// positions in the state hash. The positions must form the range `0..n`.
+fn state_hash_indexes(indexes: &[SchemaIndex]) -> Vec<&Ident> {
+    let mut hashed = indexes
+        .iter()
+        .filter_map(|index| index.state_hash.map(|position| (position, &index.ident)))
+        .collect::<Vec<_>>();
+    hashed.sort_by_key(|&(position, _)| position);
+    for (expected, &(position, ident)) in hashed.iter().enumerate() {
+        if position != expected as u64 {
+            panic!(
+                "Position {} of index `{}` in the state hash is duplicate or out of order; \
+                 the positions should be 0, 1, 2 and so on",
+                position, ident
+            );
+        }
+    }
+    hashed.into_iter().map(|(_, ident)| ident).collect()
+}
+
+/// Returns the type parameter of the schema corresponding to the database view.
+fn view_param(input: &DeriveInput) -> Ident {
+    let mut params = input.generics.type_params();
+    match (params.next(), params.next()) {
+        (Some(param), None) => param.ident.clone(),
+        _ => panic!(
+            "Schema `{}` should have a single type parameter for the database view",
+            input.ident
+        ),
+    }
+}
+
+fn service_name(input: &DeriveInput) -> String {
+    let mut service_name = None;
+    for meta in schema_attributes(&input.attrs) {
+        match meta {
+            Meta::NameValue(ref value) if value.ident == "service_name" => {
+                service_name = Some(string_value(&value.lit, "service_name"));
+            }
+            other => panic!(
+                "Unknown attribute `{}` of schema `{}`",
+                other.name(),
+                input.ident
+            ),
+        }
+    }
+    service_name.unwrap_or_else(|| {
+        panic!(
+            "Schema `{}` should be annotated with `#[schema(service_name = \"...\")]`",
+            input.ident
+        )
+    })
+}
+
+/// Returns the contents of the `#[schema(...)]` attributes.
+fn schema_attributes(attrs: &[Attribute]) -> Vec<Meta> {
+    let mut metas = Vec::new();
+    for attr in attrs {
+        if !is_ident(&attr.path, ATTRIBUTE) {
+            continue;
+        }
+        match attr.interpret_meta() {
+            Some(Meta::List(list)) => for nested in list.nested {
+                match nested {
+                    NestedMeta::Meta(meta) => metas.push(meta),
+                    NestedMeta::Literal(_) => panic!("Literals are not allowed in `#[schema]`"),
+                }
+            },
+            _ => panic!("`#[schema]` attribute should contain a list of parameters"),
+        }
+    }
+    metas
+}
+
+fn string_value(lit: &Lit, attribute: &str) -> String {
+    match *lit {
+        Lit::Str(ref value) => value.value(),
+        _ => panic!("Value of `{}` attribute should be a string", attribute),
+    }
+}
+
+fn is_ident(path: &Path, ident: &str) -> bool {
+    path.leading_colon.is_none() && path.segments.len() == 1 && path.segments[0].ident == ident
+}
+
+/// Checks that the type is the type parameter `param`.
+fn is_param(ty: &Type, param: &Ident) -> bool {
+    match *ty {
+        Type::Path(ref ty) if ty.qself.is_none() => {
+            let path = &ty.path;
+            is_ident(path, &param.to_string()) && match path.segments[0].arguments {
+                PathArguments::None => true,
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Splits the type into the path without generic arguments and the arguments.
+fn type_arguments(ty: &Type) -> Option<(Path, Vec<Type>)> {
+    let mut path = match *ty {
+        Type::Path(ref ty) if ty.qself.is_none() => ty.path.clone(),
+        _ => return None,
+    };
+    let arguments = {
+        let last = path.segments.last_mut()?.into_value();
+        mem::replace(&mut last.arguments, PathArguments::None)
+    };
+    let arguments = match arguments {
+        PathArguments::None => Vec::new(),
+        PathArguments::AngleBracketed(arguments) => arguments
+            .args
+            .into_iter()
+            .map(|argument| match argument {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect::<Option<_>>()?,
+        PathArguments::Parenthesized(_) => return None,
+    };
+    Some((path, arguments))
+}
+
+/// Extracts the index type and its parameters except the view from
+/// the `PhantomData<Index<T, ...>>` type of a schema field.
+fn index_type(ty: &Type, view_param: &Ident) -> Option<(Path, Vec<Type>)> {
+    let (marker, arguments) = type_arguments(ty)?;
+    let is_marker = marker
+        .segments
+        .iter()
+        .last()
+        .map_or(false, |segment| segment.ident == "PhantomData");
+    if !is_marker || arguments.len() != 1 {
+        return None;
+    }
+
+    let (index, mut params) = type_arguments(&arguments[0])?;
+    if params.is_empty() || !is_param(&params[0], view_param) {
+        return None;
+    }
+    params.remove(0);
+    Some((index, params))
+}
@@ -0,0 +1,128 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+extern crate exonum;
+#[macro_use]
+extern crate exonum_derive;
+
+use exonum::{crypto::{Hash, PublicKey},
+             storage::{Database, Entry, MapIndex, MemoryDB, ProofEntry, ProofListIndex,
+                       ProofMapIndex, Snapshot}};
+
+use std::marker::PhantomData;
+
+#[derive(Debug, ServiceSchema)]
+#[schema(service_name = "test_service")]
+struct TestSchema<T> {
+    view: T,
+    #[schema(state_hash = 1)]
+    balances: PhantomData<ProofMapIndex<T, PublicKey, u64>>,
+    #[schema(family = "PublicKey")]
+    history: PhantomData<ProofListIndex<T, Hash>>,
+    #[schema(name = "legacy.counter")]
+    counter: PhantomData<Entry<T, u64>>,
+    #[schema(state_hash = 0)]
+    last_hash: PhantomData<ProofEntry<T, Hash>>,
+    names: PhantomData<MapIndex<T, PublicKey, String>>,
+    #[schema(no_state_hash)]
+    receipts: PhantomData<ProofListIndex<T, Hash>>,
+}
+
+#[test]
+fn schema_accessors() {
+    let db = MemoryDB::new();
+    let key = PublicKey::zero();
+    let mut fork = db.fork();
+    {
+        let mut schema = TestSchema::new(&mut fork);
+        schema.balances_mut().put(&key, 10);
+        schema.history_mut(&key).push(Hash::zero());
+        schema.counter_mut().set(1);
+        schema.last_hash_mut().set(Hash::zero());
+        schema.names_mut().put(&key, "Alice".to_owned());
+    }
+    db.merge(fork.into_patch()).unwrap();
+
+    let snapshot = db.snapshot();
+    let schema = TestSchema::new(&snapshot);
+    assert_eq!(schema.balances().get(&key), Some(10));
+    assert_eq!(schema.history(&key).len(), 1);
+    assert_eq!(schema.counter().get(), Some(1));
+    assert_eq!(schema.last_hash().get(), Some(Hash::zero()));
+    assert_eq!(schema.names().get(&key), Some("Alice".to_owned()));
+}
+
+#[test]
+fn schema_index_names() {
+    let db = MemoryDB::new();
+    let key = PublicKey::zero();
+    let mut fork = db.fork();
+    TestSchema::new(&mut fork).balances_mut().put(&key, 10);
+    TestSchema::new(&mut fork).history_mut(&key).push(Hash::zero());
+    TestSchema::new(&mut fork).counter_mut().set(1);
+
+    let balances: ProofMapIndex<_, PublicKey, u64> =
+        ProofMapIndex::new("test_service.balances", &fork);
+    assert_eq!(balances.get(&key), Some(10));
+    let history: ProofListIndex<_, Hash> =
+        ProofListIndex::new_in_family("test_service.history", &key, &fork);
+    assert_eq!(history.len(), 1);
+    let counter: Entry<_, u64> = Entry::new("legacy.counter", &fork);
+    assert_eq!(counter.get(), Some(1));
+}
+
+#[test]
+fn schema_state_hash() {
+    let db = MemoryDB::new();
+    let mut fork = db.fork();
+    {
+        let mut schema = TestSchema::new(&mut fork);
+        schema.balances_mut().put(&PublicKey::zero(), 10);
+        schema.last_hash_mut().set(Hash::zero());
+        // Indexes not marked with `#[schema(state_hash = ...)]` do not affect the state hash.
+        schema.names_mut().put(&PublicKey::zero(), "Bob".to_owned());
+        schema.receipts_mut().push(Hash::zero());
+    }
+
+    let schema = TestSchema::new(&fork as &Snapshot);
+    assert_eq!(
+        schema.state_hash(),
+        vec![
+            schema.last_hash().merkle_root(),
+            schema.balances().merkle_root(),
+        ]
+    );
+}
+
+mod visibility {
+    use exonum::storage::ListIndex;
+
+    use std::marker::PhantomData;
+
+    #[derive(Debug, ServiceSchema)]
+    #[schema(service_name = "visibility")]
+    pub struct VisibilitySchema<T> {
+        view: T,
+        #[schema(vis = "pub")]
+        items: PhantomData<ListIndex<T, u64>>,
+    }
+}
+
+#[test]
+fn schema_accessor_visibility() {
+    let db = MemoryDB::new();
+    let mut fork = db.fork();
+    visibility::VisibilitySchema::new(&mut fork).items_mut().push(1);
+    assert_eq!(visibility::VisibilitySchema::new(&fork).items().len(), 1);
+}
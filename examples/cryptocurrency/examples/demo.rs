@@ -54,6 +54,9 @@ fn node_config() -> NodeConfig {
         mempool: Default::default(),
         services_configs: Default::default(),
         database: Default::default(),
+        pruning: Default::default(),
+        execution: Default::default(),
+        migrate_on_start: false,
     }
 }
 
@@ -20,7 +20,7 @@ extern crate rand;
 extern crate tempdir;
 extern crate test;
 
-#[cfg(all(test, feature = "long_benchmarks"))]
+#[cfg(all(test, feature = "long_benchmarks", feature = "rocksdb"))]
 mod tests {
     use test::Bencher;
     use tempdir::TempDir;
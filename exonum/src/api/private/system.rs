@@ -16,13 +16,17 @@ use iron::prelude::*;
 use router::Router;
 use serde_json;
 
-use std::{collections::HashMap, net::SocketAddr};
+use std::{collections::HashMap,
+          net::SocketAddr,
+          path::{Component, Path, PathBuf}};
 
 use api::{Api, ApiError};
-use blockchain::{Blockchain, Service, SharedNodeState};
-use crypto::PublicKey;
+use blockchain::{Blockchain, Schema, Service, SharedNodeState};
+use crypto::{Hash, PublicKey};
+use helpers::Height;
 use messages::PROTOCOL_MAJOR_VERSION;
 use node::{ApiSender, ExternalMessage};
+use storage::{index_metrics, list_indexes, Patch, PatchDiff};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct ServiceInfo {
@@ -97,21 +101,43 @@ pub struct SystemApi {
     info: NodeInfo,
     shared_api_state: SharedNodeState,
     node_channel: ApiSender,
+    backup_directory: Option<PathBuf>,
 }
 
 impl SystemApi {
     /// Creates a new `public::SystemApi` instance.
+    ///
+    /// Database checkpoints are created in the `backup_directory`; if it is `None`,
+    /// the checkpoint endpoint is disabled.
     pub fn new(
         info: NodeInfo,
         blockchain: Blockchain,
         shared_api_state: SharedNodeState,
         node_channel: ApiSender,
+        backup_directory: Option<PathBuf>,
     ) -> SystemApi {
         SystemApi {
             info,
             blockchain,
             node_channel,
             shared_api_state,
+            backup_directory,
+        }
+    }
+
+    /// Returns the path of the checkpoint with the given name in the backup directory.
+    fn checkpoint_path(&self, name: &str) -> Result<PathBuf, ApiError> {
+        let backup_directory = self.backup_directory.as_ref().ok_or_else(|| {
+            ApiError::BadRequest("Backup directory is not configured".to_owned())
+        })?;
+        // The name should not allow to escape the backup directory.
+        let mut components = Path::new(name).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(_)), None) => Ok(backup_directory.join(name)),
+            _ => Err(ApiError::BadRequest(format!(
+                "Invalid checkpoint name: {}",
+                name
+            ))),
         }
     }
 
@@ -210,6 +236,95 @@ impl SystemApi {
         );
     }
 
+    fn handle_checkpoint(self, router: &mut Router) {
+        let checkpoint = move |request: &mut Request| -> IronResult<Response> {
+            #[derive(Serialize, Deserialize, Clone, Debug)]
+            struct CheckpointInfo {
+                name: String,
+            }
+
+            let CheckpointInfo { name } = self.parse_body(request)?;
+            let path = self.checkpoint_path(&name)?;
+            self.blockchain
+                .create_checkpoint(&path)
+                .map_err(ApiError::from)?;
+            self.ok_response(&serde_json::to_value("Ok").unwrap())
+        };
+
+        router.post("/v1/checkpoint", checkpoint, "checkpoint");
+    }
+
+    /// Serves `GET /v1/indexes`, which lists the indexes stored in the database.
+    ///
+    /// The entry counts and sizes of the indexes are returned only if the `with_sizes`
+    /// parameter is `true`, since their calculation scans the whole database.
+    fn handle_indexes(self, router: &mut Router) {
+        let indexes = move |request: &mut Request| -> IronResult<Response> {
+            let with_sizes: bool = self.optional_param(request, "with_sizes")?
+                .unwrap_or(false);
+            let indexes = list_indexes(&self.blockchain.snapshot(), with_sizes);
+            self.ok_response(&serde_json::to_value(indexes).unwrap())
+        };
+
+        router.get("/v1/indexes", indexes, "indexes");
+    }
+
+    /// Serves `GET /v1/indexes/metrics`. The metrics are empty unless Exonum is built
+    /// with the `index-metrics` feature.
+    fn handle_index_metrics(self, router: &mut Router) {
+        let metrics = move |_: &mut Request| -> IronResult<Response> {
+            self.ok_response(&serde_json::to_value(index_metrics()).unwrap())
+        };
+
+        router.get("/v1/indexes/metrics", metrics, "index_metrics");
+    }
+
+    /// Serves `GET /v1/blocks/:height/patch`, which re-executes the block at the given height
+    /// with `Blockchain::replay_block` and returns the resulting patch.
+    ///
+    /// The request is expensive: all transactions of the block are executed synchronously
+    /// in the API thread, on top of the state restored by rolling back the history of all
+    /// subsequent blocks. The endpoint is intended for debugging of the nondeterministic
+    /// execution and should not be exposed to untrusted clients. It responds with
+    /// `404 Not Found` unless the state history is saved by the node.
+    fn handle_block_patch(self, router: &mut Router) {
+        let block_patch = move |request: &mut Request| -> IronResult<Response> {
+            #[derive(Serialize)]
+            struct BlockPatchInfo {
+                block_hash: Hash,
+                replayed_block_hash: Hash,
+                patch: Patch,
+                diff: PatchDiff,
+            }
+
+            let height: Height = self.url_fragment(request, "height")?;
+            let block_hash = {
+                let snapshot = self.blockchain.snapshot();
+                Schema::new(&snapshot).block_hash_by_height(height)
+            };
+            let replayed = block_hash.and_then(|hash| {
+                let (replayed_hash, patch) = self.blockchain.replay_block(height)?;
+                let old_snapshot = self.blockchain.snapshot_at(height.previous())?;
+                Some(BlockPatchInfo {
+                    block_hash: hash,
+                    replayed_block_hash: replayed_hash,
+                    diff: PatchDiff::new(&patch, &old_snapshot),
+                    patch,
+                })
+            });
+
+            match replayed {
+                Some(info) => self.ok_response(&serde_json::to_value(info).unwrap()),
+                None => Err(ApiError::NotFound(format!(
+                    "Unable to replay the block at height {}",
+                    height
+                )).into()),
+            }
+        };
+
+        router.get("/v1/blocks/:height/patch", block_patch, "block_patch");
+    }
+
     fn handle_shutdown(self, router: &mut Router) {
         let shutdown = move |_: &mut Request| -> IronResult<Response> {
             self.node_channel
@@ -229,6 +344,10 @@ impl Api for SystemApi {
         self.clone().handle_network(router);
         self.clone().handle_is_consensus_enabled(router);
         self.clone().handle_set_consensus_enabled(router);
+        self.clone().handle_checkpoint(router);
+        self.clone().handle_indexes(router);
+        self.clone().handle_index_metrics(router);
+        self.clone().handle_block_patch(router);
         self.clone().handle_shutdown(router);
     }
 }
@@ -121,7 +121,12 @@ impl ExplorerApi {
             let hash: Hash = self.url_fragment(req, "hash")?;
 
             match self.transaction_info(&hash) {
-                None => self.not_found_response(&json!({ "type": "unknown" })),
+                None => match self.explorer().pruned_transaction(&hash) {
+                    Some(location) => {
+                        self.ok_response(&json!({ "type": "pruned", "location": location }))
+                    }
+                    None => self.not_found_response(&json!({ "type": "unknown" })),
+                },
                 Some(info) => self.ok_response(&::serde_json::to_value(info).unwrap()),
             }
         };
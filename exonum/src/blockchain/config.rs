@@ -28,7 +28,7 @@ use std::collections::{BTreeMap, HashSet};
 
 use crypto::{hash, CryptoHash, Hash, PublicKey};
 use helpers::{Height, Milliseconds};
-use storage::StorageValue;
+use storage::{StorageUsage, StorageValue};
 
 /// Public keys of a validator. Each validator has two public keys: the
 /// `consensus_key` is used for internal operations in the consensus process,
@@ -122,6 +122,18 @@ pub struct ConsensusConfig {
     /// in a block if the transaction pool is almost empty, and create blocks faster when there are
     /// enough transactions in the pool.
     pub propose_timeout_threshold: u32,
+    /// Prices of the storage operations used to compute the cost of transaction execution.
+    ///
+    /// If the prices are set, the storage operations performed by every transaction are
+    /// metered, the cost of the transaction is stored together with its result, and
+    /// the service of the transaction is asked to charge the fee with
+    /// [`Service::charge_fee`] or, if the fee cannot be paid, with
+    /// `Service::charge_unpaid_fee`. Defaults to `None`, which means that transactions are
+    /// not metered.
+    ///
+    /// [`Service::charge_fee`]: ../trait.Service.html#method.charge_fee
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub execution_prices: Option<ExecutionPrices>,
 }
 
 impl ConsensusConfig {
@@ -166,10 +178,39 @@ impl Default for ConsensusConfig {
             min_propose_timeout: 10,
             max_propose_timeout: 200,
             propose_timeout_threshold: 500,
+            execution_prices: None,
         }
     }
 }
 
+/// Prices of the storage operations performed by transactions, in the units of the fee
+/// charged by services.
+///
+/// A value is counted as read when it is read by key or while iterating over an index,
+/// and as written when it is put into or removed from the storage. Both the keys
+/// and the values count towards the number of bytes.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct ExecutionPrices {
+    /// Price of a value read from the storage.
+    pub read: u64,
+    /// Price of a value written into the storage.
+    pub write: u64,
+    /// Price of a byte read from or written into the storage.
+    pub byte: u64,
+}
+
+impl ExecutionPrices {
+    /// Returns the total price of the storage operations. The price saturates at
+    /// `u64::max_value()` instead of overflowing.
+    pub fn price(&self, usage: &StorageUsage) -> u64 {
+        usage
+            .reads
+            .saturating_mul(self.read)
+            .saturating_add(usage.writes.saturating_mul(self.write))
+            .saturating_add(usage.bytes.saturating_mul(self.byte))
+    }
+}
+
 impl StoredConfiguration {
     /// Tries to serialize the given configuration into a UTF-8 encoded JSON.
     /// The method returns either the result of execution or an error.
@@ -336,6 +377,48 @@ mod tests {
         serialize_deserialize(&configuration);
     }
 
+    #[test]
+    fn execution_prices_serialization() {
+        let mut configuration = create_test_configuration();
+        // Configurations without the prices are serialized the same way as before
+        // the prices have been introduced, so that their hashes do not change.
+        let serialized = String::from_utf8(configuration.try_serialize().unwrap()).unwrap();
+        assert!(!serialized.contains("execution_prices"));
+
+        configuration.consensus.execution_prices = Some(ExecutionPrices {
+            read: 1,
+            write: 10,
+            byte: 2,
+        });
+        assert_eq!(configuration, serialize_deserialize(&configuration));
+        let toml = toml::to_string(&configuration).unwrap();
+        assert_eq!(
+            configuration,
+            toml::from_str::<StoredConfiguration>(&toml).unwrap()
+        );
+    }
+
+    #[test]
+    fn execution_price() {
+        let prices = ExecutionPrices {
+            read: 1,
+            write: 10,
+            byte: 2,
+        };
+        let usage = StorageUsage {
+            reads: 2,
+            writes: 3,
+            bytes: 100,
+        };
+        assert_eq!(prices.price(&usage), 2 + 30 + 200);
+
+        let usage = StorageUsage {
+            bytes: u64::max_value(),
+            ..usage
+        };
+        assert_eq!(prices.price(&usage), u64::max_value());
+    }
+
     fn create_test_configuration() -> StoredConfiguration {
         let validator_keys = (1..4)
             .map(|i| ValidatorKeys {
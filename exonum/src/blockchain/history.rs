@@ -0,0 +1,145 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Read-only view of the blockchain state as of an earlier block.
+//!
+//! The view reads the current storage state and looks up the values changed by
+//! the subsequent blocks in the state history, so the history is read only for the keys
+//! and indexes that are actually accessed.
+
+use std::{cmp::Ordering,
+          collections::{btree_map, BTreeMap},
+          iter::Peekable,
+          ops::Range};
+
+use super::Schema;
+use helpers::Height;
+use storage::{Iter, Iterator, Snapshot};
+
+/// A snapshot of the blockchain state as of the given block.
+pub(super) struct HistoricalSnapshot {
+    snapshot: Box<Snapshot>,
+    /// Heights of the state history tables to look through, in ascending order.
+    heights: Range<u64>,
+}
+
+impl HistoricalSnapshot {
+    /// Creates a view of the state right after the block at `height` on top of `snapshot`
+    /// containing the blocks up to `next_height` exclusively.
+    ///
+    /// The state history should be retained for all blocks after `height`.
+    pub(super) fn new(snapshot: Box<Snapshot>, height: Height, next_height: Height) -> Self {
+        // Changes merged after the latest block are saved into the history
+        // of the next block.
+        HistoricalSnapshot {
+            snapshot,
+            heights: height.0 + 1..next_height.0 + 1,
+        }
+    }
+}
+
+impl Snapshot for HistoricalSnapshot {
+    fn get(&self, name: &str, key: &[u8]) -> Option<Vec<u8>> {
+        let schema = Schema::new(&self.snapshot);
+        // The earliest block which has changed the key keeps its value as of `height`.
+        for height in self.heights.clone().map(Height) {
+            if let Some(value) = schema.state_before(height, name, key) {
+                return value;
+            }
+        }
+        self.snapshot.get(name, key)
+    }
+
+    fn iter<'a>(&'a self, name: &str, from: &[u8]) -> Iter<'a> {
+        let mut changes = BTreeMap::new();
+        {
+            let schema = Schema::new(&self.snapshot);
+            for height in self.heights.clone().map(Height) {
+                for (key, value) in schema.index_state_before(height, name, from) {
+                    changes.entry(key).or_insert(value);
+                }
+            }
+        }
+        Box::new(HistoricalIter {
+            snapshot: self.snapshot.iter(name, from),
+            changes: changes.into_iter().peekable(),
+            current: None,
+        })
+    }
+}
+
+/// An iterator over the entries of an index of the current state with the changes
+/// rolled back.
+struct HistoricalIter<'a> {
+    snapshot: Iter<'a>,
+    changes: Peekable<btree_map::IntoIter<Vec<u8>, Option<Vec<u8>>>>,
+    current: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl<'a> HistoricalIter<'a> {
+    /// Skips the keys absent in the historical state and returns the ordering of the next key
+    /// of the current state relative to the next rolled back key, or `None` if both
+    /// iterators are finished.
+    fn step(&mut self) -> Option<Ordering> {
+        loop {
+            let ordering = match (self.snapshot.peek(), self.changes.peek()) {
+                (None, None) => return None,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some((key, _)), Some(&(ref changed_key, _))) => key.cmp(changed_key.as_slice()),
+            };
+            let is_absent = ordering != Ordering::Less
+                && self.changes.peek().map_or(false, |&(_, ref value)| value.is_none());
+            if !is_absent {
+                return Some(ordering);
+            }
+            if ordering == Ordering::Equal {
+                self.snapshot.next();
+            }
+            self.changes.next();
+        }
+    }
+}
+
+impl<'a> Iterator for HistoricalIter<'a> {
+    fn next(&mut self) -> Option<(&[u8], &[u8])> {
+        match self.step() {
+            None => None,
+            Some(Ordering::Less) => self.snapshot.next(),
+            Some(ordering) => {
+                if ordering == Ordering::Equal {
+                    self.snapshot.next();
+                }
+                self.current = self.changes
+                    .next()
+                    .and_then(|(key, value)| value.map(|value| (key, value)));
+                self.current
+                    .as_ref()
+                    .map(|&(ref key, ref value)| (key.as_slice(), value.as_slice()))
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Option<(&[u8], &[u8])> {
+        match self.step() {
+            None => None,
+            Some(Ordering::Less) => self.snapshot.peek(),
+            Some(_) => self.changes.peek().and_then(|&(ref key, ref value)| {
+                value
+                    .as_ref()
+                    .map(|value| (key.as_slice(), value.as_slice()))
+            }),
+        }
+    }
+}
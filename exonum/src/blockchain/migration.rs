@@ -0,0 +1,176 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Versioning of the service schemas and migration of the stored data between
+//! the schema versions.
+
+use failure;
+
+use std::fmt;
+
+use storage::{self, Fork};
+
+/// A step of the migration of a service schema to a newer version.
+///
+/// Services declare their migrations in [`Service::migrations`]. A migration transforms
+/// the indexes of the service in a `Fork`, for example, converts the stored values
+/// to a new `encoding_struct!` layout or moves them to an index with a different name.
+/// Migrations should be deterministic, as every node of the network performs them
+/// independently.
+///
+/// # Examples
+///
+/// ```
+/// use exonum::blockchain::Migration;
+/// use exonum::storage::{Fork, MapIndex};
+///
+/// let migration = Migration::new(1, "Rename `wallets` to `accounts`", |fork: &mut Fork| {
+///     let wallets: Vec<(u64, u64)> = MapIndex::new("my_service.wallets", &*fork)
+///         .iter()
+///         .collect();
+///     MapIndex::<_, u64, u64>::new("my_service.wallets", &mut *fork).clear();
+///     let mut accounts = MapIndex::new("my_service.accounts", fork);
+///     for (key, value) in wallets {
+///         accounts.put(&key, value);
+///     }
+///     Ok(())
+/// });
+/// assert_eq!(migration.version(), 1);
+/// ```
+///
+/// [`Service::migrations`]: trait.Service.html#method.migrations
+pub struct Migration {
+    version: u16,
+    description: String,
+    handler: Box<Fn(&mut Fork) -> Result<(), failure::Error> + Send + Sync>,
+}
+
+impl Migration {
+    /// Creates a migration of the schema to the given `version` performed by `handler`.
+    pub fn new<F>(version: u16, description: &str, handler: F) -> Self
+    where
+        F: Fn(&mut Fork) -> Result<(), failure::Error> + Send + Sync + 'static,
+    {
+        Migration {
+            version,
+            description: description.to_owned(),
+            handler: Box::new(handler),
+        }
+    }
+
+    /// Returns the schema version produced by the migration.
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// Returns the human-readable description of the migration.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub(crate) fn apply(&self, fork: &mut Fork) -> Result<(), failure::Error> {
+        (self.handler)(fork)
+    }
+}
+
+impl fmt::Debug for Migration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Migration")
+            .field("version", &self.version)
+            .field("description", &self.description)
+            .finish()
+    }
+}
+
+/// Result of the migration of a service schema performed by [`Blockchain::migrate`].
+///
+/// [`Blockchain::migrate`]: struct.Blockchain.html#method.migrate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigrationReport {
+    /// Name of the service.
+    pub service_name: String,
+    /// Schema version recorded in the database before the migration.
+    pub from_version: u16,
+    /// Schema version recorded in the database after the migration.
+    pub to_version: u16,
+    /// Versions of the applied migration steps.
+    pub applied: Vec<u16>,
+}
+
+/// Errors that can occur while checking or migrating the service schemas.
+#[derive(Debug, Fail)]
+pub enum MigrationError {
+    /// The database has been created by a newer version of the service.
+    #[fail(
+        display = "schema of service `{}` has version {}, which is newer than the supported \
+                   version {}",
+        service_name, stored, supported
+    )]
+    UnsupportedVersion {
+        /// Name of the service.
+        service_name: String,
+        /// Schema version recorded in the database.
+        stored: u16,
+        /// Schema version declared by the service.
+        supported: u16,
+    },
+
+    /// The service schema in the database should be migrated before the node is started.
+    #[fail(
+        display = "schema of service `{}` has version {} while version {} is required; run \
+                   the `migrate` maintenance action or enable `migrate_on_start` option",
+        service_name, stored, required
+    )]
+    MigrationRequired {
+        /// Name of the service.
+        service_name: String,
+        /// Schema version recorded in the database.
+        stored: u16,
+        /// Schema version declared by the service.
+        required: u16,
+    },
+
+    /// The service has declared several migrations to the same version.
+    #[fail(display = "service `{}` has several migrations to version {}", service_name, version)]
+    DuplicateMigration {
+        /// Name of the service.
+        service_name: String,
+        /// Version of the duplicated migrations.
+        version: u16,
+    },
+
+    /// A migration step has failed; none of the changes made by the migration are saved.
+    #[fail(
+        display = "migration of service `{}` to version {} has failed: {}",
+        service_name, version, message
+    )]
+    MigrationFailed {
+        /// Name of the service.
+        service_name: String,
+        /// Version of the failed migration.
+        version: u16,
+        /// Description of the error returned by the migration.
+        message: String,
+    },
+
+    /// The migrated data could not be saved.
+    #[fail(display = "{}", _0)]
+    Storage(#[cause] storage::Error),
+}
+
+impl From<storage::Error> for MigrationError {
+    fn from(error: storage::Error) -> Self {
+        MigrationError::Storage(error)
+    }
+}
@@ -33,12 +33,14 @@
 //! [doc:create-service]: https://exonum.com/doc/get-started/create-service
 
 pub use self::{block::{Block, BlockProof, SCHEMA_MAJOR_VERSION},
-               config::{ConsensusConfig, StoredConfiguration, ValidatorKeys},
+               config::{ConsensusConfig, ExecutionPrices, StoredConfiguration, ValidatorKeys},
                genesis::GenesisConfig,
+               migration::{Migration, MigrationError, MigrationReport},
                schema::{Schema, TxLocation},
                service::{ApiContext, Service, ServiceContext, SharedNodeState},
-               transaction::{ExecutionError, ExecutionResult, Transaction, TransactionError,
-                             TransactionErrorType, TransactionResult, TransactionSet}};
+               transaction::{ExecutionCost, ExecutionError, ExecutionResult, Transaction,
+                             TransactionError, TransactionErrorType, TransactionOutcome,
+                             TransactionResult, TransactionSet}};
 
 pub mod config;
 
@@ -47,24 +49,29 @@ use failure;
 use mount::Mount;
 use vec_map::VecMap;
 
-use std::{collections::{BTreeMap, HashMap},
+use std::{cmp,
+          collections::{BTreeMap, HashMap},
           error::Error as StdError,
           fmt,
           iter,
           mem,
           net::SocketAddr,
           panic,
+          path::Path,
           sync::Arc};
 
 use crypto::{self, CryptoHash, Hash, PublicKey, SecretKey};
 use encoding::Error as MessageError;
 use helpers::{Height, Round, ValidatorId};
 use messages::{Connect, Precommit, RawMessage, CONSENSUS as CORE_SERVICE};
-use node::ApiSender;
-use storage::{Database, Error, Fork, Patch, Snapshot};
+use node::{ApiSender, ExecutionConfig, PruningConfig};
+use storage::{metered, Database, Error, Fork, Patch, Snapshot};
 
 mod block;
 mod genesis;
+mod history;
+mod migration;
+mod parallel;
 mod schema;
 mod service;
 #[macro_use]
@@ -81,6 +88,9 @@ pub struct Blockchain {
     service_map: Arc<VecMap<Box<Service>>>,
     service_keypair: (PublicKey, SecretKey),
     api_sender: ApiSender,
+    pruning: PruningConfig,
+    /// Worker threads executing the transactions in parallel, if enabled.
+    workers: Option<Arc<parallel::WorkerPool>>,
 }
 
 impl Blockchain {
@@ -109,6 +119,8 @@ impl Blockchain {
             service_map: Arc::new(service_map),
             service_keypair: (service_public_key, service_secret_key),
             api_sender,
+            pruning: PruningConfig::default(),
+            workers: None,
         }
     }
 
@@ -121,6 +133,24 @@ impl Blockchain {
         }
     }
 
+    /// Sets the blockchain data pruning configuration, which is applied to every
+    /// subsequently committed block.
+    pub fn set_pruning_config(&mut self, config: PruningConfig) {
+        self.pruning = config;
+    }
+
+    /// Sets the block execution configuration, which is applied to every subsequently
+    /// created block. See [`create_patch`](#method.create_patch) for details.
+    ///
+    /// If parallel execution is enabled, the worker threads are spawned by this method
+    /// and shared by the clones of the blockchain.
+    pub fn set_execution_config(&mut self, config: ExecutionConfig) {
+        self.workers = match config.parallel_workers {
+            Some(workers) if workers > 1 => Some(Arc::new(parallel::WorkerPool::new(workers))),
+            _ => None,
+        };
+    }
+
     /// Returns the `VecMap` for all services. This is a map which
     /// contains service identifiers and service interfaces. The VecMap
     /// allows proceeding from the service identifier to the service itself.
@@ -133,6 +163,48 @@ impl Blockchain {
         self.db.snapshot()
     }
 
+    /// Creates a read-only snapshot of the storage state as of the block at the given `height`,
+    /// that is, right after this block has been committed.
+    ///
+    /// The snapshot reads the current storage state and looks up the values changed by
+    /// the subsequent blocks in the state history, so every index (including Merkelized ones
+    /// together with their proofs) can be read in the same way as with [`snapshot`].
+    /// The history is read lazily: every read checks the history of the subsequent blocks
+    /// for the accessed key, and iteration over an index collects the changes of this
+    /// index only.
+    /// The changes merged into the storage with [`merge`] after the block, for example,
+    /// the transactions received by the node, are rolled back as well. Note that
+    /// the node-local caches, such as the peers cache, are not versioned.
+    ///
+    /// Returns `None` if there is no block at the given height or the state history is
+    /// not retained for some of the subsequent blocks. The state history is saved only
+    /// if it is enabled in the [`PruningConfig`], which also specifies its retention.
+    ///
+    /// [`snapshot`]: #method.snapshot
+    /// [`merge`]: #method.merge
+    /// [`PruningConfig`]: ../node/struct.PruningConfig.html
+    pub fn snapshot_at(&self, height: Height) -> Option<Box<Snapshot>> {
+        if !self.pruning.is_state_history_enabled() {
+            return None;
+        }
+
+        let snapshot = self.snapshot();
+        let next_height = {
+            let schema = Schema::new(&snapshot);
+            let next_height = Height(schema.block_hashes_by_height().len());
+            if height >= next_height {
+                return None;
+            }
+            for block_height in (height.next().0..next_height.0).map(Height) {
+                if !schema.is_state_history_retained(block_height) {
+                    return None;
+                }
+            }
+            next_height
+        };
+        Some(Box::new(history::HistoricalSnapshot::new(snapshot, height, next_height)))
+    }
+
     /// Creates a snapshot of the current storage state that can be later committed into the storage
     /// via the `merge` method.
     pub fn fork(&self) -> Fork {
@@ -155,10 +227,51 @@ impl Blockchain {
 
     /// Commits changes from the patch to the blockchain storage.
     /// See [`Fork`](../storage/struct.Fork.html) for details.
+    ///
+    /// The values that the changed keys had before the merge are saved into the state
+    /// history, so that the changes can be rolled back by [`snapshot_at`]. Patches which
+    /// change only the node-local caches, such as the consensus messages and the peers
+    /// caches, are merged as is.
+    ///
+    /// [`snapshot_at`]: #method.snapshot_at
     pub fn merge(&mut self, patch: Patch) -> Result<(), Error> {
+        let patch = self.with_state_history(patch);
         self.db.merge(patch)
     }
 
+    /// Adds to the patch the values that the keys changed by it have in the current
+    /// storage state, saving them into the state history of the next block.
+    fn with_state_history(&self, patch: Patch) -> Patch {
+        if !self.pruning.is_state_history_enabled() {
+            return patch;
+        }
+        if !patch.iter().any(|(name, _)| schema::is_versioned(name)) {
+            return patch;
+        }
+        let snapshot = self.snapshot();
+        let next_height = Height(Schema::new(&snapshot).block_hashes_by_height().len());
+        // There is nothing to roll back to before the genesis block.
+        if next_height == Height::zero() {
+            return patch;
+        }
+
+        let mut fork = self.fork();
+        fork.merge(patch);
+        Schema::new(&mut fork).save_state_history(next_height, &*snapshot);
+        fork.into_patch()
+    }
+
+    /// Creates a consistent copy of the blockchain database at the given path.
+    ///
+    /// The copy reflects the state of the database at the moment of the call; it can be
+    /// created while the node keeps running and later used as a database of another node.
+    /// See [`Database::create_checkpoint`] for details.
+    ///
+    /// [`Database::create_checkpoint`]: ../storage/trait.Database.html#method.create_checkpoint
+    pub fn create_checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        self.db.create_checkpoint(path.as_ref())
+    }
+
     /// Returns the hash of the latest committed block.
     ///
     /// # Panics
@@ -192,6 +305,116 @@ impl Blockchain {
         Ok(())
     }
 
+    /// Checks that the schema versions of the services recorded in the database match
+    /// the versions declared by the services.
+    ///
+    /// See [`Service::schema_version`] for details.
+    ///
+    /// [`Service::schema_version`]: trait.Service.html#method.schema_version
+    pub fn check_schema_versions(&self) -> Result<(), MigrationError> {
+        let snapshot = self.snapshot();
+        let schema = Schema::new(&snapshot);
+        for service in self.service_map.values() {
+            let service_name = service.service_name().to_owned();
+            let stored = schema.service_schema_version(&service_name);
+            let required = service.schema_version();
+            if stored > required {
+                return Err(MigrationError::UnsupportedVersion {
+                    service_name,
+                    stored,
+                    supported: required,
+                });
+            }
+            if stored < required {
+                return Err(MigrationError::MigrationRequired {
+                    service_name,
+                    stored,
+                    required,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Migrates the schemas of the services to the versions declared by the services
+    /// and records the new versions in the database.
+    ///
+    /// For each service, the migrations with the versions newer than the recorded one
+    /// and not newer than [`Service::schema_version`] are applied in the ascending order
+    /// of versions. All migrations are performed in a single fork, so the database
+    /// is left intact if any of them fails. Returns the reports of the migrated services.
+    ///
+    /// Migrations change the current state of the services without committing a block,
+    /// so they should be performed by all nodes of the network with the same set
+    /// of migrations before the next block is committed. The state history of the
+    /// blocks committed before the migration does not reflect the migrated data.
+    ///
+    /// [`Service::schema_version`]: trait.Service.html#method.schema_version
+    pub fn migrate(&mut self) -> Result<Vec<MigrationReport>, MigrationError> {
+        let mut reports = Vec::new();
+        let mut fork = self.fork();
+        for service in self.service_map.values() {
+            let service_name = service.service_name().to_owned();
+            let stored = Schema::new(&fork).service_schema_version(&service_name);
+            let target = service.schema_version();
+            if stored > target {
+                return Err(MigrationError::UnsupportedVersion {
+                    service_name,
+                    stored,
+                    supported: target,
+                });
+            }
+            if stored == target {
+                continue;
+            }
+
+            let mut migrations = service.migrations();
+            migrations.sort_by_key(Migration::version);
+            if let Some(pair) = migrations
+                .windows(2)
+                .find(|pair| pair[0].version() == pair[1].version())
+            {
+                return Err(MigrationError::DuplicateMigration {
+                    service_name,
+                    version: pair[0].version(),
+                });
+            }
+
+            let mut applied = Vec::new();
+            for migration in migrations
+                .iter()
+                .filter(|migration| migration.version() > stored && migration.version() <= target)
+            {
+                info!(
+                    "Migrating schema of service `{}` to version {}: {}",
+                    service_name,
+                    migration.version(),
+                    migration.description()
+                );
+                if let Err(e) = migration.apply(&mut fork) {
+                    return Err(MigrationError::MigrationFailed {
+                        service_name,
+                        version: migration.version(),
+                        message: e.to_string(),
+                    });
+                }
+                applied.push(migration.version());
+            }
+
+            Schema::new(&mut fork)
+                .service_schema_versions_mut()
+                .put(&service_name, target);
+            reports.push(MigrationReport {
+                service_name,
+                from_version: stored,
+                to_version: target,
+                applied,
+            });
+        }
+        self.merge(fork.into_patch())?;
+        Ok(reports)
+    }
+
     /// Creates and commits the genesis block with the given genesis configuration.
     fn create_genesis_block(&mut self, cfg: GenesisConfig) -> Result<(), Error> {
         let mut config_propose = StoredConfiguration {
@@ -225,6 +448,10 @@ impl Blockchain {
                     return Ok(());
                 }
                 schema.commit_configuration(config_propose);
+                let mut versions = schema.service_schema_versions_mut();
+                for service in self.service_map.values() {
+                    versions.put(&service.service_name().to_owned(), service.schema_version());
+                }
             };
             self.merge(fork.into_patch())?;
             self.create_patch(ValidatorId::zero(), Height::zero(), &[])
@@ -257,25 +484,140 @@ impl Blockchain {
     /// Executes the given transactions from the pool.
     /// Then collects the resulting changes from the current storage state and returns them
     /// with the hash of the resulting block.
+    ///
+    /// If parallel execution is enabled with [`set_execution_config`], the transactions
+    /// are first executed speculatively by the worker threads, each on a separate fork
+    /// of the same storage snapshot, which the block is then built upon. The results are
+    /// then applied in the order of the transactions; a transaction which has read a value
+    /// changed by the preceding transactions of the block, or has iterated over an index
+    /// changed by them, is executed again on top of their changes. Hence the resulting block
+    /// and patch do not depend on whether the transactions have been executed in parallel.
+    ///
+    /// [`set_execution_config`]: #method.set_execution_config
     pub fn create_patch(
         &self,
         proposer_id: ValidatorId,
         height: Height,
         tx_hashes: &[Hash],
     ) -> (Hash, Patch) {
-        // Create fork
-        let mut fork = self.fork();
+        // The speculative executions are validated against the changes made on top of
+        // the same snapshot, so the changes merged into the storage in the meantime
+        // do not affect the block.
+        let snapshot = Arc::new(self.snapshot());
+        let executions = match self.workers {
+            Some(ref workers) if tx_hashes.len() > 1 => {
+                let prices = execution_prices(&**snapshot);
+                workers.execute_speculatively(&snapshot, &self.service_map, tx_hashes, prices)
+            }
+            _ => Vec::new(),
+        };
+        let fork = Fork::from(parallel::into_snapshot(snapshot));
+        self.execute_block(fork, proposer_id, height, tx_hashes, executions)
+    }
+
+    /// Re-executes the committed block at the given `height` on top of the storage state
+    /// preceding it, as returned by [`snapshot_at`]. Returns the hash of the resulting block
+    /// together with the patch produced by its execution, the same way as [`create_patch`].
+    ///
+    /// If the returned hash differs from the hash of the stored block, the execution of
+    /// the block is not deterministic. The patch can be exported and compared with the one
+    /// produced by another node, e.g., as a [`PatchDiff`] against the preceding state.
+    ///
+    /// Returns `None` for the genesis block, if there is no block at the given height,
+    /// the state history preceding the block is not retained or the block
+    /// transactions have been pruned.
+    ///
+    /// Replaying is expensive: the changes made by all blocks after the replayed one are
+    /// rolled back, and the transactions of the block are executed one after another
+    /// in the calling thread.
+    ///
+    /// [`snapshot_at`]: #method.snapshot_at
+    /// [`create_patch`]: #method.create_patch
+    /// [`PatchDiff`]: ../storage/struct.PatchDiff.html
+    pub fn replay_block(&self, height: Height) -> Option<(Hash, Patch)> {
+        if height == Height::zero() {
+            return None;
+        }
+
+        let (proposer_id, transactions) = {
+            let snapshot = self.snapshot();
+            let schema = Schema::new(&snapshot);
+            let block_hash = schema.block_hash_by_height(height)?;
+            let block = schema.blocks().get(&block_hash)?;
+            let mut transactions = Vec::new();
+            for tx_hash in schema.block_transactions(height).iter() {
+                transactions.push((tx_hash, schema.transactions().get(&tx_hash)?));
+            }
+            (block.proposer_id(), transactions)
+        };
+
+        // Transactions that were not yet received by the node at the preceding height
+        // are put into the pool, so that the changes made by the block are not mixed with
+        // the changes made by the transactions receipt.
+        let mut pool = Fork::from(self.snapshot_at(height.previous())?);
+        for &(ref tx_hash, ref tx) in &transactions {
+            let mut schema = Schema::new(&mut pool);
+            if !schema.transactions().contains(tx_hash) {
+                schema.add_transaction_into_pool(tx.clone());
+            }
+        }
+
+        let tx_hashes: Vec<Hash> = transactions.into_iter().map(|(hash, _)| hash).collect();
+        let fork = Fork::from(Box::new(pool) as Box<Snapshot>);
+        Some(self.execute_block(fork, proposer_id, height, &tx_hashes, Vec::new()))
+    }
 
+    /// Executes the given transactions on top of the fork and returns the hash
+    /// of the resulting block together with the produced changes.
+    ///
+    /// The results of the speculative execution of the transactions are used for
+    /// the transactions which do not conflict with the preceding ones; the rest
+    /// of the transactions are executed on the fork.
+    fn execute_block(
+        &self,
+        mut fork: Fork,
+        proposer_id: ValidatorId,
+        height: Height,
+        tx_hashes: &[Hash],
+        executions: Vec<Option<parallel::SpeculativeExecution>>,
+    ) -> (Hash, Patch) {
         let block_hash = {
             // Get last hash.
-            let last_hash = self.last_hash();
+            let last_hash = Schema::new(&fork)
+                .block_hashes_by_height()
+                .last()
+                .unwrap_or_else(Hash::default);
             // Save & execute transactions.
+            let prices = if tx_hashes.is_empty() {
+                None
+            } else {
+                execution_prices(&fork)
+            };
+            let speculative = !executions.is_empty();
+            let mut executions = executions.into_iter();
+            let mut reexecuted = 0;
             for (index, hash) in tx_hashes.iter().enumerate() {
-                self.execute_transaction(*hash, height, index, &mut fork)
+                let outcome = executions
+                    .next()
+                    .and_then(|execution| execution?.apply(&mut fork));
+                if let Some(outcome) = outcome {
+                    save_transaction_result(*hash, outcome, height, index, &mut fork);
+                    continue;
+                }
+
+                reexecuted += 1;
+                self.execute_transaction(*hash, height, index, prices, &mut fork)
                     // Execution could fail if the transaction
                     // cannot be deserialized or it isn't in the pool.
                     .expect("Transaction not found in the database.");
             }
+            if speculative {
+                trace!(
+                    "{} of {} transactions have been re-executed after speculative execution",
+                    reexecuted,
+                    tx_hashes.len()
+                );
+            }
 
             // Invoke execute method for all services.
             for service in self.service_map.values() {
@@ -355,75 +697,11 @@ impl Blockchain {
         tx_hash: Hash,
         height: Height,
         index: usize,
+        prices: Option<ExecutionPrices>,
         fork: &mut Fork,
     ) -> Result<(), failure::Error> {
-        let (tx, service_name) = {
-            let schema = Schema::new(&fork);
-
-            let tx = schema
-                .transactions()
-                .get(&tx_hash)
-                .ok_or_else(|| failure::err_msg("BUG: Cannot find transaction in database."))?;
-
-            let service_name = self.service_map
-                .get(tx.service_id() as usize)
-                .ok_or_else(|| failure::err_msg("Service not found."))?
-                .service_name();
-
-            let tx = self.tx_from_raw(tx).or_else(|error| {
-                Err(failure::err_msg(format!(
-                    "Service <{}>: {}, tx: {:?}",
-                    service_name,
-                    error.description(),
-                    tx_hash
-                )))
-            })?;
-
-            (tx, service_name)
-        };
-
-        fork.checkpoint();
-
-        let catch_result = panic::catch_unwind(panic::AssertUnwindSafe(|| tx.execute(fork)));
-
-        let tx_result = match catch_result {
-            Ok(execution_result) => {
-                match execution_result {
-                    Ok(()) => {
-                        fork.commit();
-                    }
-                    Err(ref e) => {
-                        // Unlike panic, transaction failure isn't that rare, so logging the
-                        // whole transaction body is an overkill: it can be relatively big.
-                        info!(
-                            "Service <{}>: {:?} transaction execution failed: {:?}",
-                            service_name, tx_hash, e
-                        );
-                        fork.rollback();
-                    }
-                }
-                execution_result.map_err(TransactionError::from)
-            }
-            Err(err) => {
-                if err.is::<Error>() {
-                    // Continue panic unwind if the reason is StorageError.
-                    panic::resume_unwind(err);
-                }
-                fork.rollback();
-                error!(
-                    "Service <{}>: {:?} transaction execution panicked: {:?}",
-                    service_name, tx, err
-                );
-                Err(TransactionError::from_panic(&err))
-            }
-        };
-
-        let mut schema = Schema::new(fork);
-        schema.transaction_results_mut().put(&tx_hash, tx_result);
-        schema.commit_transaction(&tx_hash);
-        schema.block_transactions_mut(height).push(tx_hash);
-        let location = TxLocation::new(height, index as u64);
-        schema.transactions_locations_mut().put(&tx_hash, location);
+        let outcome = run_transaction(&self.service_map, tx_hash, prices, fork)?;
+        save_transaction_result(tx_hash, outcome, height, index, fork);
         Ok(())
     }
 
@@ -456,10 +734,33 @@ impl Blockchain {
                 // Consensus messages cache is useful only during one height, so it should be
                 // cleared when a new height is achieved.
                 schema.consensus_messages_cache_mut().clear();
+
+                let height = schema
+                    .blocks()
+                    .get(&block_hash)
+                    .expect("Committed block is absent in the patch")
+                    .height();
+                if self.pruning.is_state_history_enabled() {
+                    schema.save_state_history(height, &*self.snapshot());
+                }
+
+                if let Some(retained_blocks) = self.pruning.retained_blocks {
+                    let retained_blocks = cmp::max(retained_blocks, 1);
+                    if height.0 >= retained_blocks {
+                        schema.prune_blocks_before(Height(height.0 + 1 - retained_blocks));
+                    }
+                }
+                if let Some(retained_history) = self.pruning.retained_state_history {
+                    if height.0 + 1 >= retained_history {
+                        let first_retained = Height(height.0 + 1 - retained_history);
+                        schema.prune_state_history_before(first_retained);
+                    }
+                }
             }
             fork.into_patch()
         };
-        self.merge(patch)?;
+        // The state history of the block has already been saved.
+        self.db.merge(patch)?;
         // Initializes the context after merge.
         let context = ServiceContext::new(
             self.service_keypair.0,
@@ -569,6 +870,173 @@ impl Blockchain {
     }
 }
 
+/// Returns the prices of the storage operations from the actual consensus configuration.
+fn execution_prices(snapshot: &Snapshot) -> Option<ExecutionPrices> {
+    let schema = Schema::new(snapshot);
+    // There is no configuration before the genesis block is created.
+    if schema.configs_actual_from().is_empty() {
+        return None;
+    }
+    schema.actual_configuration().consensus.execution_prices
+}
+
+/// Executes the transaction with the given hash from the pool on top of the fork.
+/// The changes made by the transaction are rolled back if it returns an error or panics.
+///
+/// If the prices of the storage operations are given, the transaction is metered,
+/// and its service is asked to charge the fee for the execution.
+fn run_transaction(
+    service_map: &VecMap<Box<Service>>,
+    tx_hash: Hash,
+    prices: Option<ExecutionPrices>,
+    fork: &mut Fork,
+) -> Result<TransactionOutcome, failure::Error> {
+    let (tx, service) = {
+        let schema = Schema::new(&fork);
+
+        let tx = schema
+            .transactions()
+            .get(&tx_hash)
+            .ok_or_else(|| failure::err_msg("BUG: Cannot find transaction in database."))?;
+
+        let service = service_map
+            .get(tx.service_id() as usize)
+            .ok_or_else(|| failure::err_msg("Service not found."))?;
+
+        let tx = service.tx_from_raw(tx).or_else(|error| {
+            Err(failure::err_msg(format!(
+                "Service <{}>: {}, tx: {:?}",
+                service.service_name(),
+                error.description(),
+                tx_hash
+            )))
+        })?;
+
+        (tx, service)
+    };
+    let service_name = service.service_name();
+
+    fork.checkpoint();
+
+    let (catch_result, usage) =
+        metered(|| panic::catch_unwind(panic::AssertUnwindSafe(|| tx.execute(fork))));
+
+    let tx_result = match catch_result {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => {
+            // Unlike panic, transaction failure isn't that rare, so logging the
+            // whole transaction body is an overkill: it can be relatively big.
+            info!(
+                "Service <{}>: {:?} transaction execution failed: {:?}",
+                service_name, tx_hash, e
+            );
+            Err(TransactionError::from(e))
+        }
+        Err(err) => {
+            if err.is::<Error>() {
+                // Continue panic unwind if the reason is StorageError.
+                panic::resume_unwind(err);
+            }
+            error!(
+                "Service <{}>: {:?} transaction execution panicked: {:?}",
+                service_name, tx, err
+            );
+            Err(TransactionError::from_panic(&err))
+        }
+    };
+    if tx_result.is_err() {
+        // The fee is charged within a new checkpoint.
+        fork.rollback();
+        fork.checkpoint();
+    }
+
+    let cost = prices.map(|prices| ExecutionCost {
+        usage,
+        price: prices.price(&usage),
+    });
+    let tx_result = match cost {
+        Some(ref cost) => match charge_fee(&**service, &*tx, cost, false, fork) {
+            Ok(()) => tx_result,
+            Err(e) => {
+                // The changes made by the transaction are discarded, but the fee is charged
+                // anyway, so that the transactions with unpayable fees are not executed
+                // for free.
+                fork.rollback();
+                fork.checkpoint();
+                if charge_fee(&**service, &*tx, cost, true, fork).is_err() {
+                    fork.rollback();
+                    fork.checkpoint();
+                }
+                Err(e)
+            }
+        },
+        None => tx_result,
+    };
+    fork.commit();
+    Ok(TransactionOutcome::new(tx_result, cost))
+}
+
+/// Asks the service to charge the fee for the execution of the transaction with
+/// the given cost. If `unpaid` is `true`, the fee is charged with `Service::charge_unpaid_fee`.
+fn charge_fee(
+    service: &Service,
+    tx: &Transaction,
+    cost: &ExecutionCost,
+    unpaid: bool,
+    fork: &mut Fork,
+) -> TransactionResult {
+    let catch_result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        if unpaid {
+            service.charge_unpaid_fee(fork, tx, cost)
+        } else {
+            service.charge_fee(fork, tx, cost)
+        }
+    }));
+
+    match catch_result {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => {
+            info!(
+                "Service <{}>: {:?} transaction fee charging failed: {:?}",
+                service.service_name(),
+                tx.hash(),
+                e
+            );
+            Err(TransactionError::from(e))
+        }
+        Err(err) => {
+            if err.is::<Error>() {
+                // Continue panic unwind if the reason is StorageError.
+                panic::resume_unwind(err);
+            }
+            error!(
+                "Service <{}>: {:?} transaction fee charging panicked: {:?}",
+                service.service_name(),
+                tx,
+                err
+            );
+            Err(TransactionError::from_panic(&err))
+        }
+    }
+}
+
+/// Saves the outcome of the transaction execution and moves the transaction from the pool
+/// to the block at the given height.
+fn save_transaction_result(
+    tx_hash: Hash,
+    outcome: TransactionOutcome,
+    height: Height,
+    index: usize,
+    fork: &mut Fork,
+) {
+    let mut schema = Schema::new(fork);
+    schema.transaction_results_mut().put(&tx_hash, outcome);
+    schema.commit_transaction(&tx_hash);
+    schema.block_transactions_mut(height).push(tx_hash);
+    let location = TxLocation::new(height, index as u64);
+    schema.transactions_locations_mut().put(&tx_hash, location);
+}
+
 fn before_commit(service: &Service, fork: &mut Fork) {
     fork.checkpoint();
     match panic::catch_unwind(panic::AssertUnwindSafe(|| service.before_commit(fork))) {
@@ -601,6 +1069,8 @@ impl Clone for Blockchain {
             service_map: Arc::clone(&self.service_map),
             api_sender: self.api_sender.clone(),
             service_keypair: self.service_keypair.clone(),
+            pruning: self.pruning,
+            workers: self.workers.clone(),
         }
     }
 }
@@ -0,0 +1,305 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Optimistic parallel execution of the transactions of a block.
+//!
+//! The transactions are executed speculatively by a pool of worker threads, each transaction
+//! on a separate fork of the same storage snapshot. The values read by a transaction and
+//! the indexes it has iterated over are recorded, so that before the changes made by
+//! the transaction are applied to a fork of the same snapshot, they can be checked against
+//! the changes made by the preceding transactions of the block.
+
+use vec_map::VecMap;
+
+use std::{cmp,
+          collections::{Bound, HashSet},
+          mem,
+          panic,
+          sync::{atomic::{AtomicUsize, Ordering},
+                 mpsc,
+                 Arc,
+                 Mutex},
+          thread};
+
+use super::{run_transaction, ExecutionPrices, Service, TransactionOutcome};
+use crypto::Hash;
+use storage::{Fork, Iter, Patch, Snapshot};
+
+/// A value read by a transaction from the storage state it has been executed on.
+#[derive(Debug)]
+enum Read {
+    /// The value has been read with `Snapshot::get`.
+    Value(Option<Vec<u8>>),
+    /// The presence of the value has been checked with `Snapshot::contains`.
+    Presence(bool),
+}
+
+/// Data read by a transaction from the storage state it has been executed on.
+#[derive(Debug, Default)]
+struct ReadSet {
+    reads: Vec<(String, Vec<u8>, Read)>,
+    iterated: HashSet<String>,
+}
+
+impl ReadSet {
+    /// Returns `true` if the fork contains the same data as the transaction has read,
+    /// that is, the transaction would behave the same way if executed on the fork.
+    ///
+    /// The fork should be created from the snapshot the transaction has been executed on,
+    /// so that all changes since that state are contained in the patch of the fork.
+    fn is_valid(&self, fork: &Fork) -> bool {
+        let patch = fork.patch();
+        let iterated_unchanged = self.iterated
+            .iter()
+            .all(|name| patch.changes(name).is_none());
+
+        iterated_unchanged && self.reads.iter().all(|&(ref name, ref key, ref read)| {
+            match *read {
+                Read::Value(ref value) => fork.get(name, key) == *value,
+                Read::Presence(is_present) => fork.contains(name, key) == is_present,
+            }
+        })
+    }
+}
+
+/// A snapshot shared by the worker threads and the thread applying the results
+/// of the speculative execution.
+struct SharedSnapshot(Arc<Box<Snapshot>>);
+
+impl Snapshot for SharedSnapshot {
+    fn get(&self, name: &str, key: &[u8]) -> Option<Vec<u8>> {
+        self.0.get(name, key)
+    }
+
+    fn contains(&self, name: &str, key: &[u8]) -> bool {
+        self.0.contains(name, key)
+    }
+
+    fn iter<'a>(&'a self, name: &str, from: &[u8]) -> Iter<'a> {
+        self.0.iter(name, from)
+    }
+
+    fn iter_rev<'a>(&'a self, name: &str, to: Bound<&[u8]>) -> Iter<'a> {
+        self.0.iter_rev(name, to)
+    }
+}
+
+/// Converts the shared snapshot into a snapshot owned by the caller.
+pub(super) fn into_snapshot(snapshot: Arc<Box<Snapshot>>) -> Box<Snapshot> {
+    match Arc::try_unwrap(snapshot) {
+        Ok(snapshot) => snapshot,
+        // A worker thread may still hold the snapshot for a moment after sending the results.
+        Err(snapshot) => Box::new(SharedSnapshot(snapshot)),
+    }
+}
+
+/// A snapshot recording the data read from it.
+struct RecordingSnapshot {
+    snapshot: Arc<Box<Snapshot>>,
+    reads: Arc<Mutex<ReadSet>>,
+}
+
+impl Snapshot for RecordingSnapshot {
+    fn get(&self, name: &str, key: &[u8]) -> Option<Vec<u8>> {
+        let value = self.snapshot.get(name, key);
+        self.reads.lock().unwrap().reads.push((
+            name.to_owned(),
+            key.to_vec(),
+            Read::Value(value.clone()),
+        ));
+        value
+    }
+
+    fn contains(&self, name: &str, key: &[u8]) -> bool {
+        let is_present = self.snapshot.contains(name, key);
+        self.reads.lock().unwrap().reads.push((
+            name.to_owned(),
+            key.to_vec(),
+            Read::Presence(is_present),
+        ));
+        is_present
+    }
+
+    fn iter<'a>(&'a self, name: &str, from: &[u8]) -> Iter<'a> {
+        self.reads.lock().unwrap().iterated.insert(name.to_owned());
+        self.snapshot.iter(name, from)
+    }
+
+    fn iter_rev<'a>(&'a self, name: &str, to: Bound<&[u8]>) -> Iter<'a> {
+        self.reads.lock().unwrap().iterated.insert(name.to_owned());
+        self.snapshot.iter_rev(name, to)
+    }
+}
+
+/// Result of the speculative execution of a transaction.
+#[derive(Debug)]
+pub(super) struct SpeculativeExecution {
+    reads: ReadSet,
+    patch: Patch,
+    outcome: TransactionOutcome,
+}
+
+impl SpeculativeExecution {
+    /// Applies the changes made by the transaction to the fork and returns the outcome
+    /// of the transaction.
+    ///
+    /// Returns `None` if the transaction has read the data changed in the fork since
+    /// the state the transaction has been executed on; such a transaction should be
+    /// executed again on top of the fork.
+    pub(super) fn apply(self, fork: &mut Fork) -> Option<TransactionOutcome> {
+        if !self.reads.is_valid(fork) {
+            return None;
+        }
+        fork.merge(self.patch);
+        Some(self.outcome)
+    }
+}
+
+/// Transactions of a block executed by the worker threads.
+struct Batch {
+    snapshot: Arc<Box<Snapshot>>,
+    service_map: Arc<VecMap<Box<Service>>>,
+    tx_hashes: Vec<Hash>,
+    prices: Option<ExecutionPrices>,
+    next_index: AtomicUsize,
+}
+
+/// A batch to execute together with the channel for the results of the execution.
+type Job = (Arc<Batch>, mpsc::Sender<(usize, SpeculativeExecution)>);
+
+/// Pool of the worker threads executing the transactions of blocks.
+///
+/// The threads are spawned once and exit when the pool is dropped.
+pub(super) struct WorkerPool {
+    jobs: Mutex<mpsc::Sender<Job>>,
+    size: usize,
+}
+
+impl WorkerPool {
+    /// Spawns the given number of worker threads.
+    pub(super) fn new(workers: usize) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let mut size = 0;
+        for worker in 0..workers {
+            let receiver = Arc::clone(&receiver);
+            let spawn_result = thread::Builder::new()
+                .name(format!("tx-executor-{}", worker))
+                .spawn(move || run_worker(&receiver));
+            match spawn_result {
+                Ok(_) => size += 1,
+                Err(e) => warn!("Unable to spawn transaction executor thread: {}", e),
+            }
+        }
+        WorkerPool {
+            jobs: Mutex::new(sender),
+            size,
+        }
+    }
+
+    /// Executes the transactions from the pool with the given hashes by the worker threads,
+    /// each transaction on a separate fork of the given snapshot.
+    ///
+    /// Returns the results of the execution in the order of the transactions. The result is
+    /// `None` if the transaction has not been executed, for example, because it is absent
+    /// in the pool.
+    pub(super) fn execute_speculatively(
+        &self,
+        snapshot: &Arc<Box<Snapshot>>,
+        service_map: &Arc<VecMap<Box<Service>>>,
+        tx_hashes: &[Hash],
+        prices: Option<ExecutionPrices>,
+    ) -> Vec<Option<SpeculativeExecution>> {
+        let batch = Arc::new(Batch {
+            snapshot: Arc::clone(snapshot),
+            service_map: Arc::clone(service_map),
+            tx_hashes: tx_hashes.to_vec(),
+            prices,
+            next_index: AtomicUsize::new(0),
+        });
+        let (sender, receiver) = mpsc::channel();
+        {
+            let jobs = self.jobs.lock().unwrap();
+            for _ in 0..cmp::min(self.size, tx_hashes.len()) {
+                if jobs.send((Arc::clone(&batch), sender.clone())).is_err() {
+                    break;
+                }
+            }
+        }
+        drop(batch);
+        drop(sender);
+
+        let mut executions = tx_hashes.iter().map(|_| None).collect::<Vec<_>>();
+        for (index, execution) in receiver {
+            executions[index] = Some(execution);
+        }
+        executions
+    }
+}
+
+/// Executes the batches received from the pool until the pool is dropped.
+fn run_worker(jobs: &Mutex<mpsc::Receiver<Job>>) {
+    loop {
+        let job = jobs.lock().unwrap().recv();
+        let (batch, results) = match job {
+            Ok(job) => job,
+            Err(_) => break,
+        };
+        // A transaction panics only if the storage fails. The transaction is executed again
+        // sequentially, so the panic is propagated from the sequential execution.
+        let _ = panic::catch_unwind(panic::AssertUnwindSafe(|| loop {
+            let index = batch.next_index.fetch_add(1, Ordering::SeqCst);
+            if index >= batch.tx_hashes.len() {
+                break;
+            }
+            let tx_hash = batch.tx_hashes[index];
+            if let Some(execution) =
+                execute(&batch.snapshot, &batch.service_map, tx_hash, batch.prices)
+            {
+                if results.send((index, execution)).is_err() {
+                    break;
+                }
+            }
+        }));
+        // The snapshot is released before the results channel is closed, so that
+        // the caller can take the snapshot back.
+        drop(batch);
+        drop(results);
+    }
+}
+
+/// Executes the transaction on a separate fork of the snapshot, recording
+/// the data read by the transaction.
+fn execute(
+    snapshot: &Arc<Box<Snapshot>>,
+    service_map: &VecMap<Box<Service>>,
+    tx_hash: Hash,
+    prices: Option<ExecutionPrices>,
+) -> Option<SpeculativeExecution> {
+    let reads = Arc::new(Mutex::new(ReadSet::default()));
+    let mut fork = Fork::from(Box::new(RecordingSnapshot {
+        snapshot: Arc::clone(snapshot),
+        reads: Arc::clone(&reads),
+    }) as Box<Snapshot>);
+
+    // Errors are reported when the transaction is executed again sequentially.
+    let outcome = run_transaction(service_map, tx_hash, prices, &mut fork).ok()?;
+    let patch = fork.into_patch();
+    let reads = mem::replace(&mut *reads.lock().unwrap(), ReadSet::default());
+    Some(SpeculativeExecution {
+        reads,
+        patch,
+        outcome,
+    })
+}
@@ -12,12 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::{config::StoredConfiguration, Block, BlockProof, Blockchain, TransactionResult};
+use super::{config::StoredConfiguration, Block, BlockProof, Blockchain, TransactionOutcome};
 use crypto::{CryptoHash, Hash, PublicKey};
 use helpers::{Height, Round};
 use messages::{Connect, Precommit, RawMessage};
-use storage::{Entry, Fork, KeySetIndex, ListIndex, MapIndex, MapProof, ProofListIndex,
-              ProofMapIndex, Snapshot};
+use storage::{Entry, Fork, IndexFamily, KeySetIndex, ListIndex, MapIndex, MapProof,
+              ProofListIndex, ProofMapIndex, Snapshot};
 
 /// Defines `&str` constants with given name and value.
 macro_rules! define_names {
@@ -45,6 +45,9 @@ define_names!(
     PEERS_CACHE => "peers_cache";
     CONSENSUS_MESSAGES_CACHE => "consensus_messages_cache";
     CONSENSUS_ROUND => "consensus_round";
+    STATE_HISTORY => "state_history";
+    FIRST_UNPRUNED_HEIGHT => "first_unpruned_height";
+    SERVICE_SCHEMA_VERSIONS => "service_schema_versions";
 );
 
 encoding_struct! {
@@ -95,11 +98,11 @@ where
     }
 
     /// Returns a table that represents a map with a key-value pair of a transaction
-    /// hash and execution result.
+    /// hash and execution result, together with the metered cost of the execution.
     ///
     /// This method can be used to retrieve a proof that a certain transaction
     /// result is present in the blockchain.
-    pub fn transaction_results(&self) -> ProofMapIndex<&T, Hash, TransactionResult> {
+    pub fn transaction_results(&self) -> ProofMapIndex<&T, Hash, TransactionOutcome> {
         ProofMapIndex::new(TRANSACTION_RESULTS, &self.view)
     }
 
@@ -196,6 +199,78 @@ where
             .unwrap_or_else(Round::first)
     }
 
+    /// Returns a table that keeps the changes needed to roll the blockchain state back
+    /// from the block at the given height to the previous block.
+    ///
+    /// Keys are full storage keys, that is, an index name and a key within the index
+    /// separated by a zero byte. Values are the values that these keys had before the block
+    /// at `height` was committed (see [`state_before`](#method.state_before)).
+    pub(crate) fn state_history(&self, height: Height) -> MapIndex<&T, Vec<u8>, Vec<u8>> {
+        let height: u64 = height.into();
+        MapIndex::new_in_family(STATE_HISTORY, &height, &self.view)
+    }
+
+    /// Returns `true` if the changes made by the block at the given height are
+    /// retained in the state history.
+    pub(crate) fn is_state_history_retained(&self, height: Height) -> bool {
+        self.state_history(height).iter().next().is_some()
+    }
+
+    /// Returns the value that the key of the index with the given name had before
+    /// the block at the given height was committed (`Some(None)` if the key was absent),
+    /// or `None` if the key has not been changed by the block.
+    pub(crate) fn state_before(
+        &self,
+        height: Height,
+        name: &str,
+        key: &[u8],
+    ) -> Option<Option<Vec<u8>>> {
+        self.state_history(height)
+            .get(&history_key(name, key))
+            .map(decode_history_value)
+    }
+
+    /// Returns the keys of the index with the given name starting from `from`, which have
+    /// been changed by the block at the given height, in ascending order together with
+    /// the values they had before the block was committed (`None` if the key was absent).
+    pub(crate) fn index_state_before(
+        &self,
+        height: Height,
+        name: &str,
+        from: &[u8],
+    ) -> Vec<(Vec<u8>, Option<Vec<u8>>)> {
+        let prefix = history_key(name, &[]);
+        self.state_history(height)
+            .iter_from(&history_key(name, from))
+            .take_while(|&(ref key, _)| key.starts_with(&prefix))
+            .map(|(key, value)| (key[prefix.len()..].to_vec(), decode_history_value(value)))
+            .collect()
+    }
+
+    /// Returns the height of the earliest block which body has not been pruned.
+    ///
+    /// For the blocks below this height, the lists of transactions, raw transactions
+    /// and the state history are no longer available; block headers and precommits
+    /// are retained for all blocks.
+    pub fn first_unpruned_height(&self) -> Height {
+        let height: Option<u64> = Entry::new(FIRST_UNPRUNED_HEIGHT, &self.view).get();
+        Height(height.unwrap_or(0))
+    }
+
+    /// Returns a table that keeps the schema versions of the services recorded in the database,
+    /// keyed by the service name.
+    pub fn service_schema_versions(&self) -> MapIndex<&T, String, u16> {
+        MapIndex::new(SERVICE_SCHEMA_VERSIONS, &self.view)
+    }
+
+    /// Returns the schema version of the service with the given name recorded in the database.
+    ///
+    /// Databases created before the schema versions were recorded are considered
+    /// to have version `0` of all service schemas.
+    pub fn service_schema_version(&self, service_name: &str) -> u16 {
+        self.service_schema_versions().get(service_name).unwrap_or(0)
+    }
+
     /// Returns the block hash for the given height.
     pub fn block_hash_by_height(&self, height: Height) -> Option<Hash> {
         self.block_hashes_by_height().get(height.into())
@@ -382,7 +457,7 @@ impl<'a> Schema<&'a mut Fork> {
     /// [1]: struct.Schema.html#method.transaction_results
     pub(crate) fn transaction_results_mut(
         &mut self,
-    ) -> ProofMapIndex<&mut Fork, Hash, TransactionResult> {
+    ) -> ProofMapIndex<&mut Fork, Hash, TransactionOutcome> {
         ProofMapIndex::new(TRANSACTION_RESULTS, self.view)
     }
 
@@ -467,6 +542,87 @@ impl<'a> Schema<&'a mut Fork> {
         ListIndex::new(CONSENSUS_MESSAGES_CACHE, self.view)
     }
 
+    /// Mutable reference to the [`state_history`][1] index.
+    ///
+    /// [1]: struct.Schema.html#method.state_history
+    pub(crate) fn state_history_mut(
+        &mut self,
+        height: Height,
+    ) -> MapIndex<&mut Fork, Vec<u8>, Vec<u8>> {
+        let height: u64 = height.into();
+        MapIndex::new_in_family(STATE_HISTORY, &height, self.view)
+    }
+
+    /// Saves the values that the keys modified in the underlying fork have in the given
+    /// `snapshot` into the state history of the block at `height`.
+    ///
+    /// The `snapshot` should represent the state on top of which the changes are made.
+    /// The changes merged into the storage after the previous block are saved into
+    /// the history of the next block as well, so the keys which already have a value
+    /// in the history keep it. The node-local caches are not versioned.
+    pub(crate) fn save_state_history(&mut self, height: Height, snapshot: &Snapshot) {
+        let keys = self.view
+            .patch()
+            .iter()
+            .filter(|&(name, _)| is_versioned(name))
+            .flat_map(|(name, changes)| {
+                changes
+                    .iter()
+                    .map(move |(key, _)| (name.to_owned(), key.to_vec()))
+            })
+            .collect::<Vec<_>>();
+
+        let mut index = self.state_history_mut(height);
+        for (name, key) in keys {
+            let history_key = history_key(&name, &key);
+            if !index.contains(&history_key) {
+                index.put(&history_key, encode_history_value(snapshot.get(&name, &key)));
+            }
+        }
+    }
+
+    /// Removes the state history of all blocks below the given height.
+    pub(crate) fn prune_state_history_before(&mut self, height: Height) {
+        let heights = IndexFamily::<_, u64>::new(STATE_HISTORY, &*self.view)
+            .keys()
+            .take_while(|&block_height| block_height < height.0)
+            .collect::<Vec<_>>();
+        for block_height in heights {
+            self.state_history_mut(Height(block_height)).clear();
+        }
+    }
+
+    /// Removes the bodies of all blocks below the given height, that is, the lists
+    /// of transactions in these blocks, the corresponding raw transactions and
+    /// the state history.
+    pub(crate) fn prune_blocks_before(&mut self, height: Height) {
+        let first_unpruned_height = self.first_unpruned_height();
+        if height <= first_unpruned_height {
+            return;
+        }
+
+        for block_height in (first_unpruned_height.0..height.0).map(Height) {
+            let tx_hashes = self.block_transactions(block_height)
+                .iter()
+                .collect::<Vec<_>>();
+            for tx_hash in &tx_hashes {
+                self.transactions_mut().remove(tx_hash);
+            }
+            self.block_transactions_mut(block_height).clear();
+            self.state_history_mut(block_height).clear();
+        }
+
+        let mut entry: Entry<&mut Fork, u64> = Entry::new(FIRST_UNPRUNED_HEIGHT, self.view);
+        entry.set(height.0);
+    }
+
+    /// Mutable reference to the [`service_schema_versions`][1] index.
+    ///
+    /// [1]: struct.Schema.html#method.service_schema_versions
+    pub(crate) fn service_schema_versions_mut(&mut self) -> MapIndex<&mut Fork, String, u16> {
+        MapIndex::new(SERVICE_SCHEMA_VERSIONS, self.view)
+    }
+
     /// Saves the given consensus round value into the storage.
     pub(crate) fn set_consensus_round(&mut self, round: Round) {
         let mut entry: Entry<&mut Fork, _> = Entry::new(CONSENSUS_ROUND, self.view);
@@ -535,3 +691,44 @@ impl<'a> Schema<&'a mut Fork> {
         }
     }
 }
+
+/// Returns `true` if the changes of the index with the given name are saved into
+/// the state history.
+pub(crate) fn is_versioned(name: &str) -> bool {
+    match name {
+        STATE_HISTORY | PEERS_CACHE | CONSENSUS_MESSAGES_CACHE | CONSENSUS_ROUND => false,
+        _ => true,
+    }
+}
+
+/// Builds a key of the state history table from the name of an index and a key within it.
+///
+/// Index names cannot contain zero bytes, so the keys of an index share a unique prefix.
+fn history_key(name: &str, key: &[u8]) -> Vec<u8> {
+    let mut buffer = Vec::with_capacity(name.len() + 1 + key.len());
+    buffer.extend_from_slice(name.as_bytes());
+    buffer.push(0);
+    buffer.extend_from_slice(key);
+    buffer
+}
+
+fn encode_history_value(value: Option<Vec<u8>>) -> Vec<u8> {
+    match value {
+        Some(mut value) => {
+            value.insert(0, 1);
+            value
+        }
+        None => vec![0],
+    }
+}
+
+fn decode_history_value(mut buffer: Vec<u8>) -> Option<Vec<u8>> {
+    match buffer[0] {
+        0 => None,
+        1 => {
+            buffer.remove(0);
+            Some(buffer)
+        }
+        tag => panic!("Invalid state history value tag: {}", tag),
+    }
+}
@@ -24,7 +24,8 @@ use std::{collections::{HashMap, HashSet},
           sync::{Arc, RwLock}};
 
 use super::transaction::Transaction;
-use blockchain::{Blockchain, ConsensusConfig, Schema, StoredConfiguration, ValidatorKeys};
+use blockchain::{Blockchain, ConsensusConfig, ExecutionCost, ExecutionResult, Migration, Schema,
+                 StoredConfiguration, ValidatorKeys};
 use crypto::{Hash, PublicKey, SecretKey};
 use encoding::Error as MessageError;
 use helpers::{Height, Milliseconds, ValidatorId};
@@ -170,6 +171,50 @@ pub trait Service: Send + Sync + 'static {
     /// [the `Service` example above](#examples).
     fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<Transaction>, MessageError>;
 
+    /// Charges the fee for the execution of a transaction of the service. The method
+    /// is invoked after the execution of every transaction if the [`ExecutionPrices`]
+    /// are set in the consensus configuration, regardless of whether the transaction
+    /// has been executed successfully. `cost` contains the storage operations performed
+    /// by the transaction and their total price.
+    ///
+    /// The fee is charged by changing the service data, for example, by decreasing
+    /// the balance of the transaction author. If an error is returned or panic occurs,
+    /// the changes made both by the transaction and by this method are discarded,
+    /// the error becomes the result of the transaction, and the fee is charged with
+    /// [`charge_unpaid_fee`](#method.charge_unpaid_fee) instead.
+    ///
+    /// The default implementation does not charge any fee.
+    ///
+    /// [`ExecutionPrices`]: config/struct.ExecutionPrices.html
+    fn charge_fee(
+        &self,
+        fork: &mut Fork,
+        transaction: &Transaction,
+        cost: &ExecutionCost,
+    ) -> ExecutionResult {
+        Ok(())
+    }
+
+    /// Charges the fee for the execution of a transaction which could not be paid
+    /// with [`charge_fee`](#method.charge_fee). The method is invoked after the changes
+    /// made by the transaction are discarded, so the transaction cannot avoid the fee
+    /// by spending the funds needed to pay it.
+    ///
+    /// Unlike `charge_fee`, the method should not fail if the transaction author cannot pay
+    /// the whole fee, but take as much of it as possible, for example, the whole balance
+    /// of the author. Otherwise, the transaction is executed for free. The changes made
+    /// by the method are discarded only if an error is returned or panic occurs.
+    ///
+    /// The default implementation calls `charge_fee`.
+    fn charge_unpaid_fee(
+        &self,
+        fork: &mut Fork,
+        transaction: &Transaction,
+        cost: &ExecutionCost,
+    ) -> ExecutionResult {
+        self.charge_fee(fork, transaction, cost)
+    }
+
     /// Initializes the information schema of the service
     /// and generates an initial service configuration.
     /// This method is called on genesis block creation.
@@ -177,6 +222,32 @@ pub trait Service: Send + Sync + 'static {
         Value::Null
     }
 
+    /// Returns the version of the service schema, that is, of the layout of the service
+    /// data in the storage. The version should be increased whenever the layout changes,
+    /// and the migration to the new version should be added to
+    /// [`migrations`](#method.migrations).
+    ///
+    /// The version is recorded in the database on genesis block creation and by
+    /// [`Blockchain::migrate`]. A node does not start if the recorded version differs from
+    /// the declared one.
+    ///
+    /// Defaults to `0`.
+    ///
+    /// [`Blockchain::migrate`]: struct.Blockchain.html#method.migrate
+    fn schema_version(&self) -> u16 {
+        0
+    }
+
+    /// Returns the migrations of the service schema. [`Blockchain::migrate`] applies
+    /// the migrations with the versions newer than the one recorded in the database
+    /// and not newer than [`schema_version`](#method.schema_version) in the ascending
+    /// order of versions.
+    ///
+    /// [`Blockchain::migrate`]: struct.Blockchain.html#method.migrate
+    fn migrations(&self) -> Vec<Migration> {
+        Vec::new()
+    }
+
     /// A service execution. This method is invoked for each service after execution
     /// of all transactions in the block but before `after_commit` handler.
     ///
@@ -15,15 +15,23 @@
 #![allow(dead_code, unsafe_code)]
 
 use chrono::{DateTime, TimeZone, Utc};
+use futures::sync::mpsc;
 use rand::{thread_rng, Rng};
-use serde_json;
+use serde_json::{self, Value};
 
-use blockchain::{Blockchain, ExecutionResult, Schema, Service, Transaction};
+use std::sync::Arc;
+
+use blockchain::{Blockchain, ConsensusConfig, ExecutionCost, ExecutionError, ExecutionPrices,
+                 ExecutionResult, GenesisConfig, Migration, MigrationError, MigrationReport,
+                 Schema, Service, Transaction, TransactionErrorType, TransactionSet,
+                 ValidatorKeys};
 use crypto::{gen_keypair, CryptoHash, Hash};
 use encoding::Error as MessageError;
-use helpers::{Height, ValidatorId};
+use helpers::{Height, Round, ValidatorId};
 use messages::{Message, RawTransaction};
-use storage::{Database, Error, Fork, ListIndex, Snapshot};
+use node::{ApiSender, ExecutionConfig, PruningConfig};
+use storage::{Database, Entry, Error, Fork, IndexFamily, ListIndex, MapIndex, Patch, PatchDiff,
+              ProofMapIndex, Snapshot};
 
 const IDX_NAME: &'static str = "idx_name";
 const TEST_SERVICE_ID: u16 = 255;
@@ -267,6 +275,334 @@ fn handling_tx_panic_storage_error(blockchain: &mut Blockchain) {
     );
 }
 
+fn commit_block(blockchain: &mut Blockchain, height: Height, tx: &Tx) -> Hash {
+    let patch = {
+        let mut fork = blockchain.fork();
+        Schema::new(&mut fork).add_transaction_into_pool(tx.raw().clone());
+        fork.into_patch()
+    };
+    blockchain.merge(patch).unwrap();
+
+    let (block_hash, patch) = blockchain.create_patch(ValidatorId::zero(), height, &[tx.hash()]);
+    blockchain
+        .commit(&patch, block_hash, ::std::iter::empty())
+        .unwrap();
+    block_hash
+}
+
+fn snapshot_at_height(blockchain: &mut Blockchain) {
+    let (_, sec_key) = gen_keypair();
+    blockchain.set_pruning_config(PruningConfig {
+        save_state_history: true,
+        ..PruningConfig::default()
+    });
+    let txs = [Tx::new(3, &sec_key), Tx::new(4, &sec_key)];
+
+    let block_hashes = txs.iter()
+        .enumerate()
+        .map(|(height, tx)| commit_block(blockchain, Height(height as u64), tx))
+        .collect::<Vec<_>>();
+
+    assert!(blockchain.snapshot_at(Height(2)).is_none());
+
+    let snapshot = blockchain.snapshot();
+    let schema = Schema::new(&snapshot);
+    for (height, block_hash) in block_hashes.iter().enumerate() {
+        let block = schema.blocks().get(block_hash).unwrap();
+        let old_snapshot = blockchain.snapshot_at(Height(height as u64)).unwrap();
+        let old_schema = Schema::new(&old_snapshot);
+
+        assert_eq!(old_schema.height(), Height(height as u64));
+        assert_eq!(
+            old_schema.state_hash_aggregator().merkle_root(),
+            *block.state_hash()
+        );
+
+        let index: ListIndex<_, u64> = ListIndex::new(IDX_NAME, &old_snapshot);
+        assert_eq!(index.len(), 2 * (height as u64 + 1));
+        assert_eq!(index.iter().count(), 2 * (height + 1));
+    }
+
+    let old_snapshot = blockchain.snapshot_at(Height(0)).unwrap();
+    let old_schema = Schema::new(&old_snapshot);
+    assert!(old_schema.transactions().contains(&txs[0].hash()));
+    assert!(!old_schema.transactions().contains(&txs[1].hash()));
+    assert!(old_schema.transaction_results().get(&txs[1].hash()).is_none());
+}
+
+fn state_history_disabled_by_default(blockchain: &mut Blockchain) {
+    let (_, sec_key) = gen_keypair();
+    for height in 0..2 {
+        commit_block(blockchain, Height(height), &Tx::new(height + 1, &sec_key));
+    }
+
+    assert!(blockchain.snapshot_at(Height(0)).is_none());
+    let snapshot = blockchain.snapshot();
+    let family: IndexFamily<_, u64> = IndexFamily::new("core.state_history", &snapshot);
+    assert!(family.is_empty());
+}
+
+fn state_history_skips_caches(blockchain: &mut Blockchain) {
+    let (_, sec_key) = gen_keypair();
+    blockchain.set_pruning_config(PruningConfig {
+        save_state_history: true,
+        ..PruningConfig::default()
+    });
+    commit_block(blockchain, Height::zero(), &Tx::new(1, &sec_key));
+
+    // Node-local caches are not versioned, so merging them does not touch the history.
+    blockchain.save_messages(Round::first(), ::std::iter::empty());
+    let snapshot = blockchain.snapshot();
+    assert!(!Schema::new(&snapshot).is_state_history_retained(Height(1)));
+
+    let patch = {
+        let mut fork = blockchain.fork();
+        Schema::new(&mut fork).add_transaction_into_pool(Tx::new(2, &sec_key).raw().clone());
+        fork.into_patch()
+    };
+    blockchain.merge(patch).unwrap();
+    let snapshot = blockchain.snapshot();
+    assert!(Schema::new(&snapshot).is_state_history_retained(Height(1)));
+}
+
+fn replay_block(blockchain: &mut Blockchain) {
+    let (_, sec_key) = gen_keypair();
+    blockchain.set_pruning_config(PruningConfig {
+        save_state_history: true,
+        ..PruningConfig::default()
+    });
+    let txs = (3..6).map(|i| Tx::new(i, &sec_key)).collect::<Vec<_>>();
+
+    let block_hashes = txs.iter()
+        .enumerate()
+        .map(|(height, tx)| commit_block(blockchain, Height(height as u64), tx))
+        .collect::<Vec<_>>();
+
+    assert!(blockchain.replay_block(Height(0)).is_none());
+    assert!(blockchain.replay_block(Height(3)).is_none());
+
+    for height in 1..3 {
+        let (block_hash, patch) = blockchain.replay_block(Height(height)).unwrap();
+        assert_eq!(block_hash, block_hashes[height as usize]);
+
+        let old_snapshot = blockchain.snapshot_at(Height(height - 1)).unwrap();
+        // The transaction of the block has been received after the preceding block.
+        let tx_hash = txs[height as usize].hash();
+        assert!(!Schema::new(&old_snapshot).transactions().contains(&tx_hash));
+        let diff = PatchDiff::new(&patch, &old_snapshot);
+        let index = diff.indexes().iter().find(|index| index.name == IDX_NAME).unwrap();
+        assert_eq!(index.entries.iter().filter(|entry| entry.is_added()).count(), 2);
+    }
+
+    // Replaying does not change the storage.
+    let snapshot = blockchain.snapshot();
+    assert_eq!(Schema::new(&snapshot).last_block().hash(), block_hashes[2]);
+}
+
+fn pruning(blockchain: &mut Blockchain) {
+    let (_, sec_key) = gen_keypair();
+    let txs = (1..5).map(|i| Tx::new(i, &sec_key)).collect::<Vec<_>>();
+
+    blockchain.set_pruning_config(PruningConfig {
+        save_state_history: true,
+        retained_blocks: Some(2),
+        ..PruningConfig::default()
+    });
+    for (height, tx) in txs.iter().enumerate() {
+        commit_block(blockchain, Height(height as u64), tx);
+    }
+
+    let snapshot = blockchain.snapshot();
+    let schema = Schema::new(&snapshot);
+    assert_eq!(schema.first_unpruned_height(), Height(2));
+
+    // Bodies of the first blocks are pruned, but their headers, precommits
+    // and the transaction results are retained.
+    for height in 0..2 {
+        let tx_hash = txs[height].hash();
+        assert!(schema.transactions().get(&tx_hash).is_none());
+        assert!(schema.transaction_results().get(&tx_hash).is_some());
+        assert!(schema.transactions_locations().get(&tx_hash).is_some());
+        assert!(schema.block_transactions(Height(height as u64)).is_empty());
+        assert!(schema.block_and_precommits(Height(height as u64)).is_some());
+    }
+
+    // The latest blocks are retained completely.
+    for height in 2..4 {
+        assert!(schema.transactions().get(&txs[height].hash()).is_some());
+        assert_eq!(schema.block_transactions(Height(height as u64)).len(), 1);
+    }
+
+    // The state after the block 1 can be restored using the retained history.
+    assert!(blockchain.snapshot_at(Height(0)).is_none());
+    assert!(blockchain.snapshot_at(Height(1)).is_some());
+}
+
+fn state_history_retention(blockchain: &mut Blockchain) {
+    let (_, sec_key) = gen_keypair();
+    let txs = (1..5).map(|i| Tx::new(i, &sec_key)).collect::<Vec<_>>();
+
+    blockchain.set_pruning_config(PruningConfig {
+        save_state_history: true,
+        retained_state_history: Some(1),
+        ..PruningConfig::default()
+    });
+    for (height, tx) in txs.iter().take(3).enumerate() {
+        commit_block(blockchain, Height(height as u64), tx);
+    }
+    // Only the changes made by the latest block can be rolled back.
+    assert!(blockchain.snapshot_at(Height(2)).is_some());
+    assert!(blockchain.snapshot_at(Height(1)).is_some());
+    assert!(blockchain.snapshot_at(Height(0)).is_none());
+    // Block bodies are not pruned.
+    let snapshot = blockchain.snapshot();
+    assert!(Schema::new(&snapshot).transactions().contains(&txs[0].hash()));
+
+    blockchain.set_pruning_config(PruningConfig {
+        save_state_history: true,
+        retained_state_history: Some(0),
+        ..PruningConfig::default()
+    });
+    commit_block(blockchain, Height(3), &txs[3]);
+    assert!(blockchain.snapshot_at(Height(3)).is_none());
+    let snapshot = blockchain.snapshot();
+    let family: IndexFamily<_, u64> = IndexFamily::new("core.state_history", &snapshot);
+    assert!(family.is_empty());
+}
+
+const MIGRATED_IDX_NAME: &str = "migrated_service.values";
+
+struct MigratedService {
+    schema_version: u16,
+}
+
+impl Service for MigratedService {
+    fn service_id(&self) -> u16 {
+        2
+    }
+
+    fn service_name(&self) -> &'static str {
+        "migrated_service"
+    }
+
+    fn state_hash(&self, _snapshot: &Snapshot) -> Vec<Hash> {
+        vec![]
+    }
+
+    fn tx_from_raw(&self, _raw: RawTransaction) -> Result<Box<Transaction>, MessageError> {
+        unimplemented!()
+    }
+
+    fn initialize(&self, fork: &mut Fork) -> Value {
+        ListIndex::new(MIGRATED_IDX_NAME, fork).push(10_u64);
+        Value::Null
+    }
+
+    fn schema_version(&self) -> u16 {
+        self.schema_version
+    }
+
+    fn migrations(&self) -> Vec<Migration> {
+        // Migrations are deliberately declared out of order.
+        vec![
+            Migration::new(2, "Double the values", |fork: &mut Fork| {
+                let values: Vec<u64> = ListIndex::new(MIGRATED_IDX_NAME, &*fork).iter().collect();
+                let mut index = ListIndex::new(MIGRATED_IDX_NAME, fork);
+                index.clear();
+                index.extend(values.into_iter().map(|value| value * 2));
+                Ok(())
+            }),
+            Migration::new(1, "Append a value", |fork: &mut Fork| {
+                ListIndex::new(MIGRATED_IDX_NAME, fork).push(1_u64);
+                Ok(())
+            }),
+            Migration::new(3, "Fail after a change", |fork: &mut Fork| {
+                ListIndex::new(MIGRATED_IDX_NAME, fork).push(3_u64);
+                Err(format_err!("Unsupported layout"))
+            }),
+        ]
+    }
+}
+
+fn create_migrated_blockchain(db: &Arc<Database>, schema_version: u16) -> Blockchain {
+    let service_keypair = gen_keypair();
+    let api_channel = mpsc::channel(1);
+    Blockchain::new(
+        Arc::clone(db),
+        vec![Box::new(MigratedService { schema_version }) as Box<Service>],
+        service_keypair.0,
+        service_keypair.1,
+        ApiSender::new(api_channel.0),
+    )
+}
+
+fn migrated_values(blockchain: &Blockchain) -> Vec<u64> {
+    ListIndex::new(MIGRATED_IDX_NAME, &blockchain.snapshot())
+        .iter()
+        .collect()
+}
+
+fn schema_migration(db: Arc<Database>) {
+    let validator_keys = ValidatorKeys {
+        consensus_key: gen_keypair().0,
+        service_key: gen_keypair().0,
+    };
+    let mut blockchain = create_migrated_blockchain(&db, 0);
+    blockchain
+        .initialize(GenesisConfig::new(vec![validator_keys].into_iter()))
+        .unwrap();
+    blockchain.check_schema_versions().unwrap();
+    assert_eq!(migrated_values(&blockchain), vec![10]);
+
+    // The service has been upgraded.
+    let mut blockchain = create_migrated_blockchain(&db, 2);
+    match blockchain.check_schema_versions() {
+        Err(MigrationError::MigrationRequired {
+            stored: 0,
+            required: 2,
+            ..
+        }) => {}
+        other => panic!("Unexpected result: {:?}", other),
+    }
+    let reports = blockchain.migrate().unwrap();
+    assert_eq!(
+        reports,
+        vec![MigrationReport {
+            service_name: "migrated_service".to_owned(),
+            from_version: 0,
+            to_version: 2,
+            applied: vec![1, 2],
+        }]
+    );
+    assert_eq!(migrated_values(&blockchain), vec![20, 2]);
+    blockchain.check_schema_versions().unwrap();
+    assert!(blockchain.migrate().unwrap().is_empty());
+
+    // A failed migration leaves the database intact.
+    let mut blockchain = create_migrated_blockchain(&db, 3);
+    match blockchain.migrate() {
+        Err(MigrationError::MigrationFailed { version: 3, .. }) => {}
+        other => panic!("Unexpected result: {:?}", other),
+    }
+    assert_eq!(migrated_values(&blockchain), vec![20, 2]);
+    assert_eq!(
+        Schema::new(&blockchain.snapshot()).service_schema_version("migrated_service"),
+        2
+    );
+
+    // The service has been downgraded.
+    let mut blockchain = create_migrated_blockchain(&db, 1);
+    match blockchain.check_schema_versions() {
+        Err(MigrationError::UnsupportedVersion {
+            stored: 2,
+            supported: 1,
+            ..
+        }) => {}
+        other => panic!("Unexpected result: {:?}", other),
+    }
+    assert!(blockchain.migrate().is_err());
+}
+
 mod transactions_tests {
     use blockchain::{ExecutionResult, Transaction, TransactionSet};
     use crypto::gen_keypair;
@@ -361,6 +697,298 @@ mod transactions_tests {
     }
 }
 
+const COUNTERS_IDX_NAME: &'static str = "parallel_service.counters";
+const TOTAL_IDX_NAME: &'static str = "parallel_service.total";
+const PARALLEL_SERVICE_ID: u16 = 3;
+
+struct ParallelService;
+
+impl Service for ParallelService {
+    fn service_id(&self) -> u16 {
+        PARALLEL_SERVICE_ID
+    }
+
+    fn service_name(&self) -> &'static str {
+        "parallel_service"
+    }
+
+    fn state_hash(&self, snapshot: &Snapshot) -> Vec<Hash> {
+        let counters: ProofMapIndex<_, u64, u64> = ProofMapIndex::new(COUNTERS_IDX_NAME, snapshot);
+        vec![counters.merkle_root()]
+    }
+
+    fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<Transaction>, MessageError> {
+        ParallelServiceTxs::tx_from_raw(raw).map(Into::into)
+    }
+}
+
+transactions! {
+    ParallelServiceTxs {
+        const SERVICE_ID = PARALLEL_SERVICE_ID;
+
+        struct TxAdd {
+            key: u64,
+            summand: u64,
+        }
+
+        struct TxSum {
+            seed: u64,
+        }
+    }
+}
+
+impl Transaction for TxAdd {
+    fn verify(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut counters = ProofMapIndex::new(COUNTERS_IDX_NAME, view);
+        let value = counters.get(&self.key()).unwrap_or(0);
+        counters.put(&self.key(), value + self.summand());
+        match self.summand() {
+            0 => Err(ExecutionError::new(0)),
+            13 => panic!("Unlucky summand"),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Transaction for TxSum {
+    fn verify(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let total = {
+            let counters: ProofMapIndex<_, u64, u64> =
+                ProofMapIndex::new(COUNTERS_IDX_NAME, &*view);
+            counters.values().sum::<u64>()
+        };
+        Entry::new(TOTAL_IDX_NAME, view).set(total);
+        Ok(())
+    }
+}
+
+fn commit_raw_block(blockchain: &mut Blockchain, height: Height, txs: &[RawTransaction]) -> Patch {
+    let patch = {
+        let mut fork = blockchain.fork();
+        for tx in txs {
+            Schema::new(&mut fork).add_transaction_into_pool(tx.clone());
+        }
+        fork.into_patch()
+    };
+    blockchain.merge(patch).unwrap();
+
+    let tx_hashes = txs.iter().map(|tx| tx.hash()).collect::<Vec<_>>();
+    let (block_hash, patch) = blockchain.create_patch(ValidatorId::zero(), height, &tx_hashes);
+    blockchain
+        .commit(&patch, block_hash, ::std::iter::empty())
+        .unwrap();
+    patch
+}
+
+fn parallel_execution(sequential: &mut Blockchain, parallel: &mut Blockchain) {
+    let (_, sec_key) = gen_keypair();
+    let add = |key, summand| TxAdd::new(key, summand, &sec_key).raw().clone();
+    let sum = |seed| TxSum::new(seed, &sec_key).raw().clone();
+    let blocks = vec![
+        // Independent transactions.
+        (0..8).map(|key| add(key, key + 1)).collect::<Vec<_>>(),
+        // Conflicting, failing and panicking transactions.
+        vec![add(1, 5), add(1, 0), add(2, 13), add(1, 7), add(3, 1), add(2, 1)],
+        // Transactions iterating over an index changed by the preceding transactions.
+        vec![add(4, 1), sum(0), add(5, 1), add(6, 1), sum(1)],
+    ];
+
+    parallel.set_execution_config(ExecutionConfig {
+        parallel_workers: Some(4),
+    });
+    for (height, txs) in blocks.iter().enumerate() {
+        let height = Height(height as u64);
+        let sequential_patch = commit_raw_block(sequential, height, txs);
+        let parallel_patch = commit_raw_block(parallel, height, txs);
+        assert_eq!(sequential_patch.to_bytes(), parallel_patch.to_bytes());
+        assert_eq!(
+            Schema::new(&sequential.snapshot()).last_block().hash(),
+            Schema::new(&parallel.snapshot()).last_block().hash()
+        );
+    }
+
+    let snapshot = parallel.snapshot();
+    let counters: ProofMapIndex<_, u64, u64> = ProofMapIndex::new(COUNTERS_IDX_NAME, &snapshot);
+    assert_eq!(counters.get(&1), Some(14));
+    assert_eq!(counters.get(&2), Some(4));
+    let total: Entry<_, u64> = Entry::new(TOTAL_IDX_NAME, &snapshot);
+    assert_eq!(total.get(), Some(counters.values().sum::<u64>()));
+}
+
+const BALANCES_IDX_NAME: &'static str = "fee_service.balances";
+const RECORDS_IDX_NAME: &'static str = "fee_service.records";
+const FEE_SERVICE_ID: u16 = 4;
+const RICH_PAYER: u64 = 1;
+const POOR_PAYER: u64 = 2;
+const INITIAL_BALANCE: u64 = 1_000_000;
+const POOR_BALANCE: u64 = 5;
+const MAX_RECORDS: u64 = 3;
+const INSUFFICIENT_FUNDS: u8 = 1;
+
+struct FeeService;
+
+impl Service for FeeService {
+    fn service_id(&self) -> u16 {
+        FEE_SERVICE_ID
+    }
+
+    fn service_name(&self) -> &'static str {
+        "fee_service"
+    }
+
+    fn state_hash(&self, _: &Snapshot) -> Vec<Hash> {
+        vec![]
+    }
+
+    fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<Transaction>, MessageError> {
+        Ok(Box::new(TxRecord::from_raw(raw)?))
+    }
+
+    fn charge_fee(
+        &self,
+        fork: &mut Fork,
+        transaction: &Transaction,
+        cost: &ExecutionCost,
+    ) -> ExecutionResult {
+        let payer = TxRecord::from_raw(transaction.raw().clone()).unwrap().payer();
+        let mut balances = MapIndex::new(BALANCES_IDX_NAME, fork);
+        let balance = balances.get(&payer).unwrap_or(0);
+        if balance < cost.price {
+            return Err(ExecutionError::new(INSUFFICIENT_FUNDS));
+        }
+        balances.put(&payer, balance - cost.price);
+        Ok(())
+    }
+
+    fn charge_unpaid_fee(
+        &self,
+        fork: &mut Fork,
+        transaction: &Transaction,
+        cost: &ExecutionCost,
+    ) -> ExecutionResult {
+        let payer = TxRecord::from_raw(transaction.raw().clone()).unwrap().payer();
+        let mut balances = MapIndex::new(BALANCES_IDX_NAME, fork);
+        let balance = balances.get(&payer).unwrap_or(0);
+        balances.put(&payer, balance.saturating_sub(cost.price));
+        Ok(())
+    }
+
+    fn initialize(&self, fork: &mut Fork) -> Value {
+        let mut balances = MapIndex::new(BALANCES_IDX_NAME, fork);
+        balances.put(&RICH_PAYER, INITIAL_BALANCE);
+        balances.put(&POOR_PAYER, POOR_BALANCE);
+        Value::Null
+    }
+}
+
+transactions! {
+    FeeServiceTxs {
+        const SERVICE_ID = FEE_SERVICE_ID;
+
+        struct TxRecord {
+            payer: u64,
+            count: u64,
+        }
+    }
+}
+
+impl Transaction for TxRecord {
+    fn verify(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut records = ListIndex::new(RECORDS_IDX_NAME, view);
+        records.extend(0..self.count());
+        if records.len() > MAX_RECORDS {
+            Err(ExecutionError::new(0))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn execution_metering(sequential: &mut Blockchain, parallel: &mut Blockchain) {
+    let prices = ExecutionPrices {
+        read: 1,
+        write: 10,
+        byte: 1,
+    };
+    let consensus = ConsensusConfig {
+        execution_prices: Some(prices),
+        ..ConsensusConfig::default()
+    };
+    let validator_keys = ValidatorKeys {
+        consensus_key: gen_keypair().0,
+        service_key: gen_keypair().0,
+    };
+    parallel.set_execution_config(ExecutionConfig {
+        parallel_workers: Some(4),
+    });
+
+    let (_, sec_key) = gen_keypair();
+    let txs = vec![
+        TxRecord::new(RICH_PAYER, 2, &sec_key),
+        // The payer cannot pay the fee, so the changes made by the transaction are discarded,
+        // and the whole balance of the payer is taken as the fee.
+        TxRecord::new(POOR_PAYER, 1, &sec_key),
+        // Failed transactions are charged for the performed operations as well.
+        TxRecord::new(RICH_PAYER, 2 * MAX_RECORDS, &sec_key),
+    ];
+    let raw_txs = txs.iter().map(|tx| tx.raw().clone()).collect::<Vec<_>>();
+    for blockchain in vec![&mut *sequential, &mut *parallel] {
+        let genesis = GenesisConfig::new_with_consensus(
+            consensus.clone(),
+            vec![validator_keys].into_iter(),
+        );
+        blockchain.initialize(genesis).unwrap();
+        commit_raw_block(blockchain, Height(1), &raw_txs);
+    }
+    assert_eq!(
+        Schema::new(&sequential.snapshot()).last_block().hash(),
+        Schema::new(&parallel.snapshot()).last_block().hash()
+    );
+
+    let snapshot = parallel.snapshot();
+    let schema = Schema::new(&snapshot);
+    let outcomes = txs.iter()
+        .map(|tx| schema.transaction_results().get(&tx.hash()).unwrap())
+        .collect::<Vec<_>>();
+    let costs = outcomes
+        .iter()
+        .map(|outcome| outcome.cost().unwrap())
+        .collect::<Vec<_>>();
+    for cost in &costs {
+        assert!(cost.usage.reads > 0);
+        assert!(cost.usage.writes > 0);
+        assert_eq!(cost.price, prices.price(&cost.usage));
+    }
+    assert!(costs[2].usage.writes > costs[0].usage.writes);
+    assert!(costs[1].price > POOR_BALANCE);
+
+    assert_eq!(*outcomes[0].result(), Ok(()));
+    let error_type = |index: usize| outcomes[index].result().as_ref().unwrap_err().error_type();
+    assert_eq!(error_type(1), TransactionErrorType::Code(INSUFFICIENT_FUNDS));
+    assert_eq!(error_type(2), TransactionErrorType::Code(0));
+
+    let records: ListIndex<_, u64> = ListIndex::new(RECORDS_IDX_NAME, &snapshot);
+    assert_eq!(records.len(), 2);
+    let balances: MapIndex<_, u64, u64> = MapIndex::new(BALANCES_IDX_NAME, &snapshot);
+    assert_eq!(
+        balances.get(&RICH_PAYER),
+        Some(INITIAL_BALANCE - costs[0].price - costs[2].price)
+    );
+    assert_eq!(balances.get(&POOR_PAYER), Some(0));
+}
+
 struct ServiceGood;
 
 impl Service for ServiceGood {
@@ -456,9 +1084,10 @@ mod memorydb_tests {
     use crypto::gen_keypair;
     use futures::sync::mpsc;
     use node::ApiSender;
+    use std::sync::Arc;
     use storage::{Database, MemoryDB};
 
-    use super::{ServiceGood, ServicePanic, ServicePanicStorageError};
+    use super::{FeeService, ParallelService, ServiceGood, ServicePanic, ServicePanicStorageError};
 
     fn create_database() -> Box<Database> {
         Box::new(MemoryDB::new())
@@ -501,6 +1130,61 @@ mod memorydb_tests {
         super::handling_tx_panic_storage_error(&mut blockchain);
     }
 
+    #[test]
+    fn test_snapshot_at_height() {
+        let mut blockchain = create_blockchain();
+        super::snapshot_at_height(&mut blockchain);
+    }
+
+    #[test]
+    fn test_pruning() {
+        let mut blockchain = create_blockchain();
+        super::pruning(&mut blockchain);
+    }
+
+    #[test]
+    fn test_state_history_retention() {
+        let mut blockchain = create_blockchain();
+        super::state_history_retention(&mut blockchain);
+    }
+
+    #[test]
+    fn test_state_history_disabled_by_default() {
+        let mut blockchain = create_blockchain();
+        super::state_history_disabled_by_default(&mut blockchain);
+    }
+
+    #[test]
+    fn test_state_history_skips_caches() {
+        let mut blockchain = create_blockchain();
+        super::state_history_skips_caches(&mut blockchain);
+    }
+
+    #[test]
+    fn test_replay_block() {
+        let mut blockchain = create_blockchain();
+        super::replay_block(&mut blockchain);
+    }
+
+    #[test]
+    fn test_schema_migration() {
+        super::schema_migration(Arc::new(MemoryDB::new()));
+    }
+
+    #[test]
+    fn test_parallel_execution() {
+        let mut sequential = create_blockchain_with_service(Box::new(ParallelService));
+        let mut parallel = create_blockchain_with_service(Box::new(ParallelService));
+        super::parallel_execution(&mut sequential, &mut parallel);
+    }
+
+    #[test]
+    fn test_execution_metering() {
+        let mut sequential = create_blockchain_with_service(Box::new(FeeService));
+        let mut parallel = create_blockchain_with_service(Box::new(FeeService));
+        super::execution_metering(&mut sequential, &mut parallel);
+    }
+
     #[test]
     fn test_service_execute() {
         let blockchain = create_blockchain_with_service(Box::new(ServiceGood));
@@ -524,6 +1208,7 @@ mod memorydb_tests {
     }
 }
 
+#[cfg(feature = "rocksdb")]
 mod rocksdb_tests {
     use blockchain::{Blockchain, Service};
     use crypto::gen_keypair;
@@ -533,7 +1218,7 @@ mod rocksdb_tests {
     use storage::{Database, DbOptions, RocksDB};
     use tempdir::TempDir;
 
-    use super::{ServiceGood, ServicePanic, ServicePanicStorageError};
+    use super::{FeeService, ParallelService, ServiceGood, ServicePanic, ServicePanicStorageError};
 
     fn create_database(path: &Path) -> Box<Database> {
         let opts = DbOptions::default();
@@ -585,6 +1270,72 @@ mod rocksdb_tests {
         super::handling_tx_panic_storage_error(&mut blockchain);
     }
 
+    #[test]
+    fn test_snapshot_at_height() {
+        let dir = create_temp_dir();
+        let mut blockchain = create_blockchain(dir.path());
+        super::snapshot_at_height(&mut blockchain);
+    }
+
+    #[test]
+    fn test_pruning() {
+        let dir = create_temp_dir();
+        let mut blockchain = create_blockchain(dir.path());
+        super::pruning(&mut blockchain);
+    }
+
+    #[test]
+    fn test_state_history_retention() {
+        let dir = create_temp_dir();
+        let mut blockchain = create_blockchain(dir.path());
+        super::state_history_retention(&mut blockchain);
+    }
+
+    #[test]
+    fn test_state_history_disabled_by_default() {
+        let dir = create_temp_dir();
+        let mut blockchain = create_blockchain(dir.path());
+        super::state_history_disabled_by_default(&mut blockchain);
+    }
+
+    #[test]
+    fn test_state_history_skips_caches() {
+        let dir = create_temp_dir();
+        let mut blockchain = create_blockchain(dir.path());
+        super::state_history_skips_caches(&mut blockchain);
+    }
+
+    #[test]
+    fn test_replay_block() {
+        let dir = create_temp_dir();
+        let mut blockchain = create_blockchain(dir.path());
+        super::replay_block(&mut blockchain);
+    }
+
+    #[test]
+    fn test_schema_migration() {
+        let dir = create_temp_dir();
+        super::schema_migration(create_database(dir.path()).into());
+    }
+
+    #[test]
+    fn test_parallel_execution() {
+        let dir = create_temp_dir();
+        let mut sequential = create_blockchain_with_service(dir.path(), Box::new(ParallelService));
+        let dir = create_temp_dir();
+        let mut parallel = create_blockchain_with_service(dir.path(), Box::new(ParallelService));
+        super::parallel_execution(&mut sequential, &mut parallel);
+    }
+
+    #[test]
+    fn test_execution_metering() {
+        let dir = create_temp_dir();
+        let mut sequential = create_blockchain_with_service(dir.path(), Box::new(FeeService));
+        let dir = create_temp_dir();
+        let mut parallel = create_blockchain_with_service(dir.path(), Box::new(FeeService));
+        super::execution_metering(&mut sequential, &mut parallel);
+    }
+
     #[test]
     fn test_service_execute() {
         let dir = create_temp_dir();
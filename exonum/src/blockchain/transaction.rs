@@ -18,10 +18,10 @@ use serde::{de::DeserializeOwned, Serialize};
 
 use std::{any::Any, borrow::Cow, convert::Into, error::Error, fmt, u8};
 
-use crypto::{CryptoHash, Hash};
+use crypto::{hash, CryptoHash, Hash};
 use encoding::{self, serialize::json::ExonumJson};
 use messages::{Message, RawTransaction};
-use storage::{Fork, StorageValue};
+use storage::{Fork, StorageUsage, StorageValue};
 
 //  User-defined error codes (`TransactionErrorType::Code(u8)`) have a `0...255` range.
 #[cfg_attr(feature = "cargo-clippy", allow(cast_lossless))]
@@ -30,6 +30,11 @@ const MAX_ERROR_CODE: u16 = u8::MAX as u16;
 const TRANSACTION_STATUS_OK: u16 = MAX_ERROR_CODE + 1;
 // `Err(TransactionErrorType::Panic)`.
 const TRANSACTION_STATUS_PANIC: u16 = TRANSACTION_STATUS_OK + 1;
+// Bit of the flags byte of the serialized `TransactionOutcome` indicating that the cost
+// of the transaction is present.
+const OUTCOME_COST_FLAG: u8 = 0b10;
+// Size of the serialized `ExecutionCost`.
+const EXECUTION_COST_SIZE: usize = 4 * 8;
 
 /// Returns a result of the `Transaction` `execute` method. This result may be
 /// either an empty unit type, in case of success, or an `ExecutionError`, if execution has
@@ -104,10 +109,16 @@ pub trait Transaction: Message + ExonumJson + 'static {
     /// - Transaction itself is considered committed regardless whether `Ok` or `Err` has been
     ///   returned or even if panic occurs during execution.
     /// - Changes made by the transaction are discarded if `Err` is returned or panic occurs.
+    /// - To discard only a part of the changes, the transaction may create a savepoint
+    ///   with [`Fork::savepoint`] and then roll back to it with
+    ///   [`Fork::rollback_to_savepoint`].
     /// - A transaction execution status (see `ExecutionResult` and `TransactionResult` for the
     ///   details) is stored in the blockchain and can be accessed through API.
     /// - Blockchain state hash is affected by the transactions execution status.
     ///
+    /// [`Fork::savepoint`]: ../storage/struct.Fork.html#method.savepoint
+    /// [`Fork::rollback_to_savepoint`]: ../storage/struct.Fork.html#method.rollback_to_savepoint
+    ///
     /// # Examples
     ///
     /// ```
@@ -220,10 +231,10 @@ pub enum TransactionErrorType {
 /// # let transaction_hash = Hash::zero();
 /// let schema = Schema::new(&snapshot);
 ///
-/// if let Some(result) = schema.transaction_results().get(&transaction_hash) {
-///     match result {
+/// if let Some(outcome) = schema.transaction_results().get(&transaction_hash) {
+///     match *outcome.result() {
 ///         Ok(()) => println!("Successful transaction execution"),
-///         Err(transaction_error) => {
+///         Err(ref transaction_error) => {
 ///             // Prints user friendly error description.
 ///             println!("Transaction error: {}", transaction_error);
 ///         }
@@ -354,6 +365,115 @@ fn status_as_u16(status: &TransactionResult) -> u16 {
     }
 }
 
+/// Metered cost of the transaction execution.
+///
+/// The cost is computed only if the [`ExecutionPrices`] are set in the consensus
+/// configuration. The storage operations performed by the service while charging the fee
+/// are not included into the cost.
+///
+/// [`ExecutionPrices`]: config/struct.ExecutionPrices.html
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionCost {
+    /// Storage operations performed by the transaction.
+    pub usage: StorageUsage,
+    /// Total price of the storage operations.
+    pub price: u64,
+}
+
+impl ExecutionCost {
+    fn write(&self, buf: &mut Vec<u8>) {
+        let usage = &self.usage;
+        for &value in &[usage.reads, usage.writes, usage.bytes, self.price] {
+            buf.extend(u64::into_bytes(value));
+        }
+    }
+
+    fn read(bytes: &[u8]) -> Self {
+        let value = |i: usize| u64::from_bytes(Cow::Borrowed(&bytes[i * 8..(i + 1) * 8]));
+        ExecutionCost {
+            usage: StorageUsage {
+                reads: value(0),
+                writes: value(1),
+                bytes: value(2),
+            },
+            price: value(3),
+        }
+    }
+}
+
+/// Result of the transaction execution together with its metered cost, as stored
+/// in the [`transaction_results`] index.
+///
+/// [`transaction_results`]: struct.Schema.html#method.transaction_results
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionOutcome {
+    result: TransactionResult,
+    cost: Option<ExecutionCost>,
+}
+
+impl TransactionOutcome {
+    /// Creates a new `TransactionOutcome` from the result and the optional cost.
+    pub(crate) fn new(result: TransactionResult, cost: Option<ExecutionCost>) -> Self {
+        TransactionOutcome { result, cost }
+    }
+
+    /// Returns the result of the transaction execution.
+    pub fn result(&self) -> &TransactionResult {
+        &self.result
+    }
+
+    /// Returns the cost of the transaction execution, or `None` if the transaction
+    /// has not been metered.
+    pub fn cost(&self) -> Option<ExecutionCost> {
+        self.cost
+    }
+}
+
+// `TransactionOutcome` without the cost has the same hash and binary representation as its
+// `TransactionResult`, so the cost does not affect the blockchain state unless
+// the transactions are metered. As for `TransactionResult`, the error description is excluded
+// from the hash calculation.
+impl CryptoHash for TransactionOutcome {
+    fn hash(&self) -> Hash {
+        match self.cost {
+            None => self.result.hash(),
+            Some(ref cost) => {
+                let mut bytes = u16::into_bytes(status_as_u16(&self.result));
+                cost.write(&mut bytes);
+                hash(&bytes)
+            }
+        }
+    }
+}
+
+// If the cost is present, the `OUTCOME_COST_FLAG` bit is set in the flags byte following
+// the status of the `TransactionResult`, and the cost is stored right after the flags byte.
+impl StorageValue for TransactionOutcome {
+    fn into_bytes(self) -> Vec<u8> {
+        let mut bytes = self.result.into_bytes();
+        if let Some(ref cost) = self.cost {
+            bytes[2] |= OUTCOME_COST_FLAG;
+            let description = bytes.split_off(3);
+            cost.write(&mut bytes);
+            bytes.extend(description);
+        }
+        bytes
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        if bytes[2] & OUTCOME_COST_FLAG == 0 {
+            return TransactionOutcome::new(TransactionResult::from_bytes(bytes), None);
+        }
+
+        let cost = ExecutionCost::read(&bytes[3..3 + EXECUTION_COST_SIZE]);
+        let mut result = bytes[..3].to_vec();
+        result[2] &= !OUTCOME_COST_FLAG;
+        result.extend_from_slice(&bytes[3 + EXECUTION_COST_SIZE..]);
+        let result = TransactionResult::from_bytes(Cow::Owned(result));
+        TransactionOutcome::new(result, Some(cost))
+    }
+}
+
 /// `TransactionSet` trait describes a type which is an `enum` of several transactions.
 /// The implementation of this trait is generated automatically by the `transactions!`
 /// macro.
@@ -762,6 +882,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn transaction_outcomes_round_trip() {
+        let cost = ExecutionCost {
+            usage: StorageUsage {
+                reads: 3,
+                writes: 2,
+                bytes: 100,
+            },
+            price: 1_000,
+        };
+        let results = [
+            Ok(()),
+            Err(TransactionError::panic(None)),
+            Err(TransactionError::panic(Some("Panic error description".to_owned()))),
+            Err(TransactionError::code(1, Some("".to_owned()))),
+            Err(TransactionError::code(255, Some("error description".to_owned()))),
+        ];
+
+        for result in &results {
+            let outcome = TransactionOutcome::new(result.clone(), None);
+            let bytes = outcome.clone().into_bytes();
+            assert_eq!(bytes, result.clone().into_bytes());
+            assert_eq!(outcome.hash(), result.hash());
+            assert_eq!(outcome, TransactionOutcome::from_bytes(Cow::Borrowed(&bytes)));
+
+            let metered = TransactionOutcome::new(result.clone(), Some(cost));
+            let bytes = metered.clone().into_bytes();
+            assert_eq!(metered, TransactionOutcome::from_bytes(Cow::Borrowed(&bytes)));
+            assert_ne!(metered.hash(), outcome.hash());
+        }
+    }
+
     #[test]
     fn error_discards_transaction_changes() {
         let statuses = [
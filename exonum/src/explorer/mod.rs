@@ -25,8 +25,8 @@ use std::{cell::{Ref, RefCell},
           ops::{Index, Range, RangeFrom, RangeFull, RangeTo},
           slice};
 
-use blockchain::{Block, Blockchain, Schema, Transaction, TransactionError, TransactionErrorType,
-                 TransactionResult, TxLocation};
+use blockchain::{Block, Blockchain, ExecutionCost, Schema, Transaction, TransactionError,
+                 TransactionErrorType, TransactionResult, TxLocation};
 use crypto::{CryptoHash, Hash};
 use encoding;
 use helpers::Height;
@@ -98,6 +98,9 @@ impl HeightRange {
 /// | `block` | [`Block`] | Block header as recorded in the blockchain |
 /// | `precommits` | `Vec<`[`Precommit`]`>` | Precommits authorizing the block |
 /// | `txs` | `Vec<`[`Hash`]`>` | Hashes of transactions in the block |
+/// | `pruned` | `bool` | Whether the body of the block has been pruned |
+///
+/// The list of transactions is empty for pruned blocks; see [`is_pruned`](#method.is_pruned).
 ///
 /// [`Block`]: ../blockchain/struct.Block.html
 /// [`Precommit`]: ../messages/struct.Precommit.html
@@ -160,6 +163,13 @@ impl<'a> BlockInfo<'a> {
         self.len() == 0
     }
 
+    /// Has the body of this block (i.e., its transactions) been pruned?
+    ///
+    /// The header and precommits are available for pruned blocks, but transactions are not.
+    pub fn is_pruned(&self) -> bool {
+        self.explorer.is_pruned(self.height())
+    }
+
     /// Returns a list of precommits for this block.
     pub fn precommits(&self) -> Ref<[Precommit]> {
         if self.precommits.borrow().is_none() {
@@ -225,10 +235,11 @@ impl<'a> Serialize for BlockInfo<'a> {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         use serde::ser::SerializeStruct;
 
-        let mut s = serializer.serialize_struct("BlockInfo", 3)?;
+        let mut s = serializer.serialize_struct("BlockInfo", 4)?;
         s.serialize_field("block", &self.header)?;
         s.serialize_field("precommits", &*self.precommits())?;
         s.serialize_field("txs", &*self.transaction_hashes())?;
+        s.serialize_field("pruned", &self.is_pruned())?;
         s.end()
     }
 }
@@ -343,6 +354,7 @@ impl<'a, T> IntoIterator for &'a BlockWithTransactions<T> {
 /// | `location` | [`TxLocation`] | Location of the transaction in the block |
 /// | `location_proof` | [`ListProof`]`<`[`Hash`]`>` | Proof of transaction inclusion into a block |
 /// | `status` | (custom; see below) | Execution status |
+/// | `cost` | [`ExecutionCost`] | Metered cost of the execution; omitted if not metered |
 ///
 /// ## `status` field
 ///
@@ -375,6 +387,7 @@ impl<'a, T> IntoIterator for &'a BlockWithTransactions<T> {
 /// [`Hash`]: ../crypto/struct.Hash.html
 /// [`TransactionResult`]: ../blockchain/type.TransactionResult.html
 /// [`ExecutionError`]: ../blockchain/struct.ExecutionError.html
+/// [`ExecutionCost`]: ../blockchain/struct.ExecutionCost.html
 /// [`Flow`]: https://flow.org/
 /// [`TypeScript`]: https://www.typescriptlang.org/
 ///
@@ -441,6 +454,8 @@ pub struct CommittedTransaction<T = Box<Transaction>> {
     location_proof: ListProof<Hash>,
     #[serde(with = "TxStatus")]
     status: TransactionResult,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cost: Option<ExecutionCost>,
 }
 
 /// Transaction execution status. Simplified version of `TransactionResult`.
@@ -527,6 +542,12 @@ impl<T> CommittedTransaction<T> {
     pub fn status(&self) -> Result<(), &TransactionError> {
         self.status.as_ref().map(|_| ())
     }
+
+    /// Returns the metered cost of the transaction execution, or `None` if the transaction
+    /// has not been metered.
+    pub fn cost(&self) -> Option<ExecutionCost> {
+        self.cost
+    }
 }
 
 /// Information about the transaction.
@@ -746,6 +767,18 @@ impl<'a> BlockchainExplorer<'a> {
         Some(TransactionInfo::Committed(tx))
     }
 
+    /// Returns the location of a committed transaction which contents have been pruned.
+    ///
+    /// Returns `None` if the transaction is unknown or its contents are available;
+    /// in the latter case, use [`transaction`](#method.transaction) instead.
+    pub fn pruned_transaction(&self, tx_hash: &Hash) -> Option<TxLocation> {
+        let schema = Schema::new(&self.snapshot);
+        if schema.transactions().contains(tx_hash) {
+            return None;
+        }
+        schema.transactions_locations().get(tx_hash)
+    }
+
     #[cfg_attr(feature = "cargo-clippy", allow(let_and_return))]
     fn precommits(&self, block: &Block) -> Vec<Precommit> {
         let schema = Schema::new(&self.snapshot);
@@ -783,7 +816,7 @@ impl<'a> BlockchainExplorer<'a> {
             .get_proof(location.position_in_block());
 
         // Unwrap is OK here, because we already know that transaction is committed.
-        let status = schema.transaction_results().get(tx_hash).unwrap();
+        let outcome = schema.transaction_results().get(tx_hash).unwrap();
 
         CommittedTransaction {
             content: maybe_content.unwrap_or_else(|| {
@@ -793,7 +826,8 @@ impl<'a> BlockchainExplorer<'a> {
 
             location,
             location_proof,
-            status,
+            status: outcome.result().clone(),
+            cost: outcome.cost(),
         }
     }
 
@@ -803,6 +837,16 @@ impl<'a> BlockchainExplorer<'a> {
         schema.height()
     }
 
+    /// Returns `true` if the body of the block at the specified height has been pruned.
+    ///
+    /// See [`PruningConfig`] for details.
+    ///
+    /// [`PruningConfig`]: ../node/struct.PruningConfig.html
+    pub fn is_pruned(&self, height: Height) -> bool {
+        let schema = Schema::new(&self.snapshot);
+        height < schema.first_unpruned_height()
+    }
+
     /// Returns block information for the specified height or `None` if there is no such block.
     pub fn block(&self, height: Height) -> Option<BlockInfo> {
         if self.height() >= height {
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use futures::sync::mpsc;
+
 use std::{collections::HashMap,
           ffi::OsString,
           fmt,
@@ -25,9 +27,10 @@ use super::{clap_backend::ClapBackend,
             keys,
             maintenance::Maintenance,
             CommandName,
+            Context,
             ServiceFactory};
-use blockchain::Service;
-use node::Node;
+use blockchain::{Blockchain, Service};
+use node::{ApiSender, Node};
 
 /// `NodeBuilder` is a high level object,
 /// usable for fast prototyping and creating app from services list.
@@ -74,17 +77,51 @@ impl NodeBuilder {
                 let config = ctx.get(keys::NODE_CONFIG)
                     .expect("could not find node_config");
                 let db = Run::db_helper(ctx, &config.database);
-                let services: Vec<Box<Service>> = self.service_factories
-                    .into_iter()
-                    .map(|mut factory| factory.make_service(ctx))
-                    .collect();
+                let services = Self::make_services(self.service_factories, ctx);
                 let node = Node::new(db, services, config);
                 Some(node)
             }
+            Feedback::MigrateSchemas(ref ctx) => {
+                let config = ctx.get(keys::NODE_CONFIG)
+                    .expect("could not find node_config");
+                let db = Run::db_helper(ctx, &config.database);
+                let services = Self::make_services(self.service_factories, ctx);
+                let api_channel = mpsc::channel(1);
+                let mut blockchain = Blockchain::new(
+                    db,
+                    services,
+                    config.service_public_key,
+                    config.service_secret_key,
+                    ApiSender::new(api_channel.0),
+                );
+                let reports = blockchain
+                    .migrate()
+                    .expect("Unable to migrate service schemas");
+                if reports.is_empty() {
+                    info!("Service schemas are up to date");
+                }
+                for report in reports {
+                    info!(
+                        "Migrated schema of service `{}` from version {} to {}",
+                        report.service_name, report.from_version, report.to_version
+                    );
+                }
+                None
+            }
             _ => None,
         }
     }
 
+    fn make_services(
+        factories: Vec<Box<ServiceFactory>>,
+        run_context: &Context,
+    ) -> Vec<Box<Service>> {
+        factories
+            .into_iter()
+            .map(|mut factory| factory.make_service(run_context))
+            .collect()
+    }
+
     // handle error, and print it.
     fn panic_hook(info: &PanicInfo) {
         let msg = match info.payload().downcast_ref::<&'static str>() {
@@ -36,7 +36,7 @@ use blockchain::{config::ValidatorKeys, GenesisConfig};
 use crypto;
 use helpers::{config::ConfigFile, generate_testnet_config};
 use node::{AllowOrigin, NodeApiConfig, NodeConfig};
-use storage::{Database, DbOptions, RocksDB};
+use storage::{open_database, Database, DbOptions};
 
 const DATABASE_PATH: &str = "DATABASE_PATH";
 const OUTPUT_DIR: &str = "OUTPUT_DIR";
@@ -55,7 +55,7 @@ impl Run {
     pub fn db_helper(ctx: &Context, options: &DbOptions) -> Box<Database> {
         let path = ctx.arg::<String>(DATABASE_PATH)
             .expect(&format!("{} not found.", DATABASE_PATH));
-        Box::new(RocksDB::open(Path::new(&path), options).expect("Can't load database file"))
+        open_database(Path::new(&path), options).expect("Can't load database file")
     }
 
     fn node_config(ctx: &Context) -> NodeConfig {
@@ -623,6 +623,9 @@ impl Command for Finalize {
                 mempool: Default::default(),
                 services_configs: Default::default(),
                 database: Default::default(),
+                pruning: Default::default(),
+                execution: Default::default(),
+                migrate_on_start: false,
             }
         };
 
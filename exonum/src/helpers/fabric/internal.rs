@@ -23,6 +23,8 @@ use super::{Argument, CommandExtension, CommandName, Context};
 pub enum Feedback {
     /// Run node with current context.
     RunNode(Context),
+    /// Migrate service schemas with current context.
+    MigrateSchemas(Context),
     /// Do nothing
     None,
 }
@@ -14,16 +14,20 @@
 
 //! This module implements node maintenance actions.
 
-use std::{collections::HashMap, path::Path};
+use std::{collections::HashMap, fs, path::{Path, PathBuf}};
 
 use super::{internal::{CollectedCommand, Command, Feedback},
+            keys,
             Argument,
             CommandName,
             Context};
 use blockchain::Schema;
 use helpers::config::ConfigFile;
 use node::NodeConfig;
-use storage::{Database, DbOptions, RocksDB};
+use serde_json;
+use storage::{list_indexes, open_database, Database, DbOptions, Patch, PatchDiff};
+#[cfg(feature = "rocksdb")]
+use storage::RocksDB;
 
 // Context entry for the path to the node config.
 const NODE_CONFIG_PATH: &str = "NODE_CONFIG_PATH";
@@ -31,10 +35,39 @@ const NODE_CONFIG_PATH: &str = "NODE_CONFIG_PATH";
 const DATABASE_PATH: &str = "DATABASE_PATH";
 // Context entry for the type of action to be performed.
 const MAINTENANCE_ACTION_PATH: &str = "MAINTENANCE_ACTION_PATH";
+// Context entry for the path to the backup.
+const BACKUP_PATH: &str = "BACKUP_PATH";
+// Context entry for the path to the exported patch.
+const PATCH_PATH: &str = "PATCH_PATH";
 
 /// Maintenance command. Supported actions:
 ///
 /// - `clear-cache` - clear message cache.
+/// - `checkpoint` - create a copy of the database at the backup path. `RocksDB` checkpoints
+///   hard-link the table files of the database, so they should be created on the same file
+///   system as the database.
+/// - `backup` - create a new incremental backup of the `RocksDB` database in the backup
+///   directory at the backup path.
+/// - `restore` - restore the `RocksDB` database from the latest backup in the backup
+///   directory. The database path must not exist.
+/// - `list-indexes` - print the list of the indexes stored in the database with their
+///   types, entry counts and sizes in the JSON format.
+/// - `show-patch` - print the changes the patch at the patch path would make
+///   to the database as a per-index diff.
+/// - `apply-patch` - merge the patch at the patch path into the database.
+/// - `migrate` - migrate the service schemas to the versions declared by the services
+///   (see `Blockchain::migrate`).
+///
+/// Patches are read in the JSON format if the file has the `.json` extension and in
+/// the binary format produced by `Patch::to_bytes` otherwise. The patch produced by a committed
+/// block is returned in the `patch` field of the `v1/blocks/:height/patch` endpoint
+/// of the private API.
+///
+/// The running node keeps its database locked, so these actions can be performed
+/// only while the node is stopped. Use the `v1/checkpoint` and `v1/indexes` endpoints
+/// of the private API to back up and inspect the database of a running node; checkpoints
+/// are created by the endpoint in the `api.backup_directory` of the node configuration.
+/// A checkpoint can be turned into a backup with the `backup` action later.
 #[derive(Debug)]
 pub struct Maintenance;
 
@@ -48,7 +81,7 @@ impl Maintenance {
     fn database(ctx: &Context, options: &DbOptions) -> Box<Database> {
         let path = ctx.arg::<String>(DATABASE_PATH)
             .expect(&format!("{} not found.", DATABASE_PATH));
-        Box::new(RocksDB::open(Path::new(&path), options).expect("Can't load database file"))
+        open_database(Path::new(&path), options).expect("Can't load database file")
     }
 
     fn clear_cache(context: &Context) {
@@ -65,6 +98,114 @@ impl Maintenance {
 
         info!("Cache cleared successfully");
     }
+
+    fn backup_path(ctx: &Context) -> PathBuf {
+        let path = ctx.arg::<String>(BACKUP_PATH)
+            .expect(&format!("{} not found.", BACKUP_PATH));
+        PathBuf::from(path)
+    }
+
+    fn checkpoint(context: &Context) {
+        let path = Self::backup_path(context);
+        info!("Creating database checkpoint at {}", path.display());
+
+        let config = Self::node_config(context);
+        let db = Self::database(context, &config.database);
+        db.create_checkpoint(&path)
+            .expect("Can't create checkpoint");
+
+        info!("Checkpoint created successfully");
+    }
+
+    #[cfg(feature = "rocksdb")]
+    fn backup(context: &Context) {
+        let backup_path = Self::backup_path(context);
+        let path = context
+            .arg::<String>(DATABASE_PATH)
+            .expect(&format!("{} not found.", DATABASE_PATH));
+        info!("Creating database backup in {}", backup_path.display());
+
+        let config = Self::node_config(context);
+        let options = DbOptions {
+            create_if_missing: false,
+            ..config.database
+        };
+        let db = RocksDB::open(&path, &options).expect("Can't load database file");
+        db.create_backup(&backup_path).expect("Can't create backup");
+
+        info!("Backup created successfully");
+    }
+
+    #[cfg(feature = "rocksdb")]
+    fn restore(context: &Context) {
+        let backup_path = Self::backup_path(context);
+        let path = context
+            .arg::<String>(DATABASE_PATH)
+            .expect(&format!("{} not found.", DATABASE_PATH));
+        info!(
+            "Restoring database at {} from {}",
+            path,
+            backup_path.display()
+        );
+
+        RocksDB::restore_backup(&backup_path, &path).expect("Can't restore database");
+
+        info!("Database restored successfully");
+    }
+
+    #[cfg(not(feature = "rocksdb"))]
+    fn backup(_context: &Context) {
+        panic!("Backups require the RocksDB backend, which is disabled in this build");
+    }
+
+    #[cfg(not(feature = "rocksdb"))]
+    fn restore(_context: &Context) {
+        panic!("Backups require the RocksDB backend, which is disabled in this build");
+    }
+
+    fn list_indexes(context: &Context) {
+        let config = Self::node_config(context);
+        let db = Self::database(context, &config.database);
+        let indexes = list_indexes(&db.snapshot(), true);
+        let indexes = serde_json::to_string_pretty(&indexes).expect("Unable to serialize indexes");
+        println!("{}", indexes);
+    }
+
+    fn patch(ctx: &Context) -> Patch {
+        let path = ctx.arg::<String>(PATCH_PATH)
+            .expect(&format!("{} not found.", PATCH_PATH));
+        let bytes = fs::read(&path).expect("Can't read patch file");
+        if Path::new(&path).extension().map_or(false, |ext| ext == "json") {
+            serde_json::from_slice(&bytes).expect("Can't parse patch")
+        } else {
+            Patch::from_bytes(&bytes).expect("Can't parse patch")
+        }
+    }
+
+    fn show_patch(context: &Context) {
+        let config = Self::node_config(context);
+        let db = Self::database(context, &config.database);
+        let patch = Self::patch(context);
+        print!("{}", PatchDiff::new(&patch, &db.snapshot()));
+    }
+
+    fn apply_patch(context: &Context) {
+        let config = Self::node_config(context);
+        let db = Self::database(context, &config.database);
+        let patch = Self::patch(context);
+        info!("Applying patch with {} changes", patch.len());
+
+        db.merge_sync(patch).expect("Can't apply patch");
+
+        info!("Patch applied successfully");
+    }
+
+    fn migrate(mut context: Context, exts: &Fn(Context) -> Context) -> Feedback {
+        let config = Self::node_config(&context);
+        context.set(keys::NODE_CONFIG, config);
+        // Services are created and the migrations are performed by the `NodeBuilder`.
+        Feedback::MigrateSchemas(exts(context))
+    }
 }
 
 impl Command for Maintenance {
@@ -94,6 +235,22 @@ impl Command for Maintenance {
                 "action",
                 false,
             ),
+            Argument::new_named(
+                BACKUP_PATH,
+                false,
+                "Path to the database backup.",
+                "b",
+                "backup-path",
+                false,
+            ),
+            Argument::new_named(
+                PATCH_PATH,
+                false,
+                "Path to the exported patch.",
+                "p",
+                "patch-path",
+                false,
+            ),
         ]
     }
 
@@ -102,14 +259,15 @@ impl Command for Maintenance {
     }
 
     fn about(&self) -> &str {
-        "Maintenance module. Available actions: clear-cache."
+        "Maintenance module. Available actions: clear-cache, checkpoint, backup, restore, \
+         list-indexes, show-patch, apply-patch, migrate."
     }
 
     fn execute(
         &self,
         _commands: &HashMap<CommandName, CollectedCommand>,
         context: Context,
-        _: &Fn(Context) -> Context,
+        exts: &Fn(Context) -> Context,
     ) -> Feedback {
         let action = context
             .arg::<String>(MAINTENANCE_ACTION_PATH)
@@ -117,6 +275,13 @@ impl Command for Maintenance {
 
         match action.as_ref() {
             "clear-cache" => Self::clear_cache(&context),
+            "checkpoint" => Self::checkpoint(&context),
+            "backup" => Self::backup(&context),
+            "restore" => Self::restore(&context),
+            "list-indexes" => Self::list_indexes(&context),
+            "show-patch" => Self::show_patch(&context),
+            "apply-patch" => Self::apply_patch(&context),
+            "migrate" => return Self::migrate(context, exts),
             _ => println!("Unsupported maintenance action: {}", action),
         }
 
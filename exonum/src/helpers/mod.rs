@@ -87,6 +87,9 @@ pub fn generate_testnet_config(count: u8, start_port: u16) -> Vec<NodeConfig> {
             mempool: Default::default(),
             services_configs: Default::default(),
             database: Default::default(),
+            pruning: Default::default(),
+            execution: Default::default(),
+            migrate_on_start: false,
         })
         .collect::<Vec<_>>()
 }
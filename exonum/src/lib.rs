@@ -35,6 +35,7 @@ extern crate cookie;
 extern crate env_logger;
 #[macro_use]
 extern crate exonum_profiler;
+#[cfg(feature = "rocksdb")]
 extern crate exonum_rocksdb as rocksdb;
 extern crate exonum_sodiumoxide as sodiumoxide;
 #[macro_use]
@@ -45,10 +46,15 @@ extern crate hyper;
 extern crate iron;
 extern crate iron_cors;
 #[macro_use]
+extern crate lazy_static;
+#[macro_use]
 extern crate log;
 extern crate mount;
 extern crate os_info;
 extern crate params;
+#[cfg(feature = "conformance")]
+#[macro_use]
+extern crate proptest;
 extern crate rand;
 extern crate router;
 extern crate rust_decimal;
@@ -70,9 +76,6 @@ extern crate vec_map;
 
 // Test dependencies.
 #[cfg(test)]
-#[macro_use]
-extern crate lazy_static;
-#[cfg(test)]
 extern crate tempdir;
 #[cfg(all(test, feature = "long_benchmarks"))]
 extern crate test;
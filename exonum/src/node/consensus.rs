@@ -129,16 +129,18 @@ impl NodeHandler {
         let snapshot = self.blockchain.snapshot();
         let schema = Schema::new(snapshot);
         //TODO: Remove this match after errors refactor. (ECR-979)
-        let has_unknown_txs =
-            match self.state
-                .add_propose(msg, &schema.transactions(), &schema.transactions_pool())
-            {
-                Ok(state) => state.has_unknown_txs(),
-                Err(err) => {
-                    warn!("{}, msg={:?}", err, msg);
-                    return;
-                }
-            };
+        let has_unknown_txs = match self.state.add_propose(
+            msg,
+            &schema.transactions(),
+            &schema.transactions_pool(),
+            &schema.transactions_locations(),
+        ) {
+            Ok(state) => state.has_unknown_txs(),
+            Err(err) => {
+                warn!("{}, msg={:?}", err, msg);
+                return;
+            }
+        };
 
         let hash = msg.hash();
 
@@ -232,7 +234,12 @@ impl NodeHandler {
             let snapshot = self.blockchain.snapshot();
             let schema = Schema::new(snapshot);
             let has_unknown_txs = self.state
-                .create_incomplete_block(msg, &schema.transactions(), &schema.transactions_pool())
+                .create_incomplete_block(
+                    msg,
+                    &schema.transactions(),
+                    &schema.transactions_pool(),
+                    &schema.transactions_locations(),
+                )
                 .has_unknown_txs();
 
             let known_nodes = self.remove_request(&RequestData::Block(block.height()));
@@ -543,7 +550,12 @@ impl NodeHandler {
 
         profiler_span!("Make sure that it is new transaction", {
             let snapshot = self.blockchain.snapshot();
-            if Schema::new(&snapshot).transactions().contains(&hash) {
+            let schema = Schema::new(&snapshot);
+            // Raw transactions are removed from the pruned blocks, but their locations
+            // are retained, so committed transactions cannot be replayed.
+            if schema.transactions().contains(&hash)
+                || schema.transactions_locations().contains(&hash)
+            {
                 let err = format!("Received already processed transaction, hash {:?}", hash);
                 return Err(err);
             }
@@ -37,6 +37,7 @@ use std::{collections::{BTreeMap, HashSet},
           fmt,
           io,
           net::SocketAddr,
+          path::PathBuf,
           str::FromStr,
           sync::Arc,
           thread,
@@ -171,6 +172,9 @@ pub struct NodeApiConfig {
     ///
     /// [cors]: https://developer.mozilla.org/en-US/docs/Web/HTTP/CORS
     pub private_allow_origin: Option<AllowOrigin>,
+    /// Directory in which the database checkpoints requested via the private API
+    /// are created. Checkpoints cannot be requested if the directory is not specified.
+    pub backup_directory: Option<PathBuf>,
 }
 
 impl Default for NodeApiConfig {
@@ -182,6 +186,7 @@ impl Default for NodeApiConfig {
             private_api_address: None,
             public_allow_origin: None,
             private_allow_origin: None,
+            backup_directory: None,
         }
     }
 }
@@ -325,6 +330,72 @@ impl Default for MemoryPoolConfig {
     }
 }
 
+/// Blockchain data pruning parameters.
+///
+/// By default a node is archival, that is, it keeps every block together with
+/// its transactions forever. A pruned node drops the bodies of blocks older than
+/// the retention window: lists of transactions in such blocks, raw transactions and
+/// the state history. Block headers, precommits and the current state of the blockchain
+/// are retained in any case.
+///
+/// A pruned node cannot help the lagging peers to catch up: it ignores requests
+/// of the pruned blocks, so such peers should be able to request the blocks from
+/// other nodes of the network.
+///
+/// The state history (see [`Blockchain::snapshot_at`]) is not saved unless
+/// `save_state_history` is set. For every block it keeps the previous values of all keys
+/// changed by the block and by the transactions received before it, so the history takes
+/// about as much space as the changes made by the blocks themselves. The history can be
+/// limited separately with `retained_state_history`.
+///
+/// [`Blockchain::snapshot_at`]: ../blockchain/struct.Blockchain.html#method.snapshot_at
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PruningConfig {
+    /// Whether to save the state history of the committed blocks.
+    ///
+    /// Defaults to `false`.
+    #[serde(default)]
+    pub save_state_history: bool,
+    /// Number of the latest blocks which bodies are retained.
+    ///
+    /// Defaults to `None`, which means that nothing is pruned.
+    pub retained_blocks: Option<u64>,
+    /// Number of the latest blocks for which the state history is retained.
+    ///
+    /// Defaults to `None`, which means that the state history is retained for all blocks
+    /// which bodies are retained. `Some(0)` disables the state history. Ignored unless
+    /// `save_state_history` is set.
+    pub retained_state_history: Option<u64>,
+}
+
+impl PruningConfig {
+    /// Returns `true` if the state history is saved for the committed blocks.
+    pub fn is_state_history_enabled(&self) -> bool {
+        self.save_state_history && self.retained_state_history != Some(0)
+    }
+}
+
+/// Block execution parameters.
+///
+/// By default the transactions of a block are executed one after another. If
+/// `parallel_workers` is set, the transactions of the blocks proposed by the node are
+/// executed optimistically in parallel: every transaction is executed on a separate fork
+/// by one of the worker threads, and the results of the transactions which have read
+/// the data changed by preceding transactions of the block are discarded, and such
+/// transactions are executed again one after another. The resulting block is identical
+/// to the one produced by the sequential execution.
+///
+/// Parallel execution is beneficial if the transactions of a block rarely touch the same
+/// data, for example, transfers between different wallets.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionConfig {
+    /// Number of the worker threads executing the transactions of a block.
+    ///
+    /// Defaults to `None`, which means that the transactions are executed sequentially
+    /// in the node thread. The worker threads are spawned once when the node starts.
+    pub parallel_workers: Option<usize>,
+}
+
 /// Configuration for the `Node`.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct NodeConfig {
@@ -359,6 +430,21 @@ pub struct NodeConfig {
     /// Optional database configuration.
     #[serde(default)]
     pub database: DbOptions,
+    /// Optional blockchain data pruning configuration.
+    #[serde(default)]
+    pub pruning: PruningConfig,
+    /// Optional block execution configuration.
+    #[serde(default)]
+    pub execution: ExecutionConfig,
+    /// Whether to migrate the service schemas on node start if the schema versions
+    /// recorded in the database are outdated. If disabled, the node does not start until
+    /// the schemas are migrated with the `migrate` action of the `maintenance` command.
+    ///
+    /// See [`Blockchain::migrate`] for details.
+    ///
+    /// [`Blockchain::migrate`]: ../blockchain/struct.Blockchain.html#method.migrate
+    #[serde(default)]
+    pub migrate_on_start: bool,
 }
 
 /// Configuration for the `NodeHandler`.
@@ -824,7 +910,20 @@ impl Node {
             node_cfg.service_secret_key.clone(),
             ApiSender::new(channel.api_requests.0.clone()),
         );
+        blockchain.set_pruning_config(node_cfg.pruning);
+        blockchain.set_execution_config(node_cfg.execution);
         blockchain.initialize(node_cfg.genesis.clone()).unwrap();
+        if node_cfg.migrate_on_start {
+            for report in blockchain.migrate().expect("Unable to migrate service schemas") {
+                info!(
+                    "Migrated schema of service `{}` from version {} to {}",
+                    report.service_name, report.from_version, report.to_version
+                );
+            }
+        }
+        if let Err(e) = blockchain.check_schema_versions() {
+            panic!("{}", e);
+        }
 
         let config = Configuration {
             listener: ListenerConfig {
@@ -1033,7 +1132,13 @@ pub fn create_private_api_handler(
 
     let mut router = Router::new();
     let node_info = private::NodeInfo::new(blockchain.service_map().iter().map(|(_, s)| s));
-    let system_api = private::SystemApi::new(node_info, blockchain, shared_api_state, api_sender);
+    let system_api = private::SystemApi::new(
+        node_info,
+        blockchain,
+        shared_api_state,
+        api_sender,
+        config.backup_directory.clone(),
+    );
     system_api.wire(&mut router);
     mount.mount("api/system", router);
 
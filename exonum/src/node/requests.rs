@@ -137,6 +137,9 @@ impl NodeHandler {
     }
 
     /// Handles `BlockRequest` message. For details see the message documentation.
+    ///
+    /// A pruned node does not respond to the requests of the blocks which bodies have been
+    /// pruned, so the lagging peers request such blocks from other nodes.
     pub fn handle_request_block(&mut self, msg: &BlockRequest) {
         trace!(
             "Handle block request with height:{}, our height: {}",
@@ -151,6 +154,10 @@ impl NodeHandler {
         let schema = Schema::new(&snapshot);
 
         let height = msg.height();
+        if height < schema.first_unpruned_height() {
+            trace!("Ignore request of the pruned block with height:{}", height);
+            return;
+        }
         let block_hash = schema.block_hash_by_height(height).unwrap();
 
         let block = schema.blocks().get(&block_hash).unwrap();
@@ -22,7 +22,7 @@ use std::{collections::{hash_map::Entry, BTreeMap, HashMap, HashSet},
           net::SocketAddr,
           time::{Duration, SystemTime}};
 
-use blockchain::{ConsensusConfig, StoredConfiguration, ValidatorKeys};
+use blockchain::{ConsensusConfig, StoredConfiguration, TxLocation, ValidatorKeys};
 use crypto::{CryptoHash, Hash, PublicKey, SecretKey};
 use helpers::{Height, Milliseconds, Round, ValidatorId};
 use messages::{BlockResponse, Connect, ConsensusMessage, Message, Precommit, Prevote, Propose,
@@ -846,11 +846,15 @@ impl State {
     }
 
     /// Adds propose from other node. Returns `ProposeState` if it is a new propose.
+    ///
+    /// The committed transactions are detected by their locations, which are retained
+    /// even if the block bodies are pruned.
     pub fn add_propose<S: AsRef<Snapshot>>(
         &mut self,
         msg: &Propose,
         transactions: &MapIndex<S, Hash, RawMessage>,
         transaction_pool: &KeySetIndex<S, Hash>,
+        transaction_locations: &MapIndex<S, Hash, TxLocation>,
     ) -> Result<&ProposeState, failure::Error> {
         let propose_hash = msg.hash();
         match self.proposes.entry(propose_hash) {
@@ -858,6 +862,12 @@ impl State {
             Entry::Vacant(e) => {
                 let mut unknown_txs = HashSet::new();
                 for hash in msg.transactions() {
+                    if transaction_locations.contains(hash) {
+                        bail!(
+                            "Received propose with already \
+                             committed transaction"
+                        )
+                    }
                     if transactions.get(hash).is_some() {
                         if !transaction_pool.contains(hash) {
                             bail!(
@@ -919,11 +929,18 @@ impl State {
         msg: &BlockResponse,
         txs: &MapIndex<S, Hash, RawMessage>,
         txs_pool: &KeySetIndex<S, Hash>,
+        txs_locations: &MapIndex<S, Hash, TxLocation>,
     ) -> &IncompleteBlock {
         assert!(self.incomplete_block().is_none());
 
         let mut unknown_txs = HashSet::new();
         for hash in msg.transactions() {
+            if txs_locations.contains(hash) {
+                panic!(
+                    "Received block with already \
+                     committed transaction"
+                )
+            }
             if txs.get(hash).is_some() {
                 if !txs_pool.contains(hash) {
                     panic!(
@@ -30,6 +30,7 @@ use messages::{BlockRequest, BlockResponse, Connect, Message, PeersRequest, Prec
                PrevotesRequest, Propose, ProposeRequest, RawMessage, Status, TransactionsRequest,
                TransactionsResponse, CONSENSUS};
 use node::{self,
+           PruningConfig,
            state::{BLOCK_REQUEST_TIMEOUT, PREVOTES_REQUEST_TIMEOUT, PROPOSE_REQUEST_TIMEOUT,
                    TRANSACTIONS_REQUEST_TIMEOUT}};
 
@@ -3479,6 +3480,75 @@ fn handle_tx_ignore_existing_tx_in_blockchain() {
     sandbox.add_time(Duration::from_millis(0));
 }
 
+#[test]
+fn handle_tx_ignore_pruned_tx_in_blockchain() {
+    let sandbox = timestamping_sandbox();
+    let sandbox_state = SandboxState::new();
+    sandbox.blockchain_mut().set_pruning_config(PruningConfig {
+        retained_blocks: Some(1),
+        ..PruningConfig::default()
+    });
+
+    let tx = gen_timestamping_tx();
+    add_one_height_with_transactions(&sandbox, &sandbox_state, &[tx.raw().clone()]);
+    add_one_height_with_transactions(&sandbox, &sandbox_state, &[]);
+    sandbox.assert_state(HEIGHT_THREE, ROUND_ONE);
+    {
+        let snapshot = sandbox.blockchain_ref().snapshot();
+        assert!(!Schema::new(&snapshot).transactions().contains(&tx.hash()));
+    }
+
+    // The raw transaction has been pruned, but it is not accepted into the pool again.
+    sandbox.recv(&tx);
+    assert!(!sandbox.transactions_hashes().contains(&tx.hash()));
+}
+
+#[test]
+fn handle_block_request_ignore_pruned_block() {
+    let sandbox = timestamping_sandbox();
+    let sandbox_state = SandboxState::new();
+    sandbox.blockchain_mut().set_pruning_config(PruningConfig {
+        retained_blocks: Some(1),
+        ..PruningConfig::default()
+    });
+
+    add_one_height(&sandbox, &sandbox_state);
+    add_one_height(&sandbox, &sandbox_state);
+    sandbox.assert_state(HEIGHT_THREE, ROUND_ONE);
+
+    // The body of the first block has been pruned, so the request is ignored.
+    sandbox.recv(&BlockRequest::new(
+        &sandbox.p(VALIDATOR_3),
+        &sandbox.p(VALIDATOR_0),
+        HEIGHT_ONE,
+        sandbox.s(VALIDATOR_3),
+    ));
+
+    let (block_proof, tx_hashes) = {
+        let snapshot = sandbox.blockchain_ref().snapshot();
+        let schema = Schema::new(&snapshot);
+        let tx_hashes = schema.block_transactions(HEIGHT_TWO).iter().collect::<Vec<_>>();
+        (schema.block_and_precommits(HEIGHT_TWO).unwrap(), tx_hashes)
+    };
+    sandbox.recv(&BlockRequest::new(
+        &sandbox.p(VALIDATOR_3),
+        &sandbox.p(VALIDATOR_0),
+        HEIGHT_TWO,
+        sandbox.s(VALIDATOR_3),
+    ));
+    sandbox.send(
+        sandbox.a(VALIDATOR_3),
+        &BlockResponse::new(
+            &sandbox.p(VALIDATOR_0),
+            &sandbox.p(VALIDATOR_3),
+            block_proof.block,
+            block_proof.precommits,
+            &tx_hashes,
+            sandbox.s(VALIDATOR_0),
+        ),
+    );
+}
+
 /// HANDLE ROUND TIMEOUT:
 /// - Ignore if height and round are not the same
 /// scenario:
@@ -692,6 +692,7 @@ pub fn sandbox_with_services_uninitialized(services: Vec<Box<Service>>) -> Sandb
         min_propose_timeout: PROPOSE_TIMEOUT,
         max_propose_timeout: PROPOSE_TIMEOUT,
         propose_timeout_threshold: 0,
+        execution_prices: None,
     };
     let genesis = GenesisConfig::new_with_consensus(
         consensus,
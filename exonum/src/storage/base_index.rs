@@ -15,10 +15,15 @@
 //! An implementation of base index with most common features.
 // spell-checker:ignore subprefix
 
-use std::{borrow::Cow, marker::PhantomData};
+use std::{borrow::Cow,
+          cmp,
+          collections::Bound::{self, Excluded, Included, Unbounded},
+          marker::PhantomData};
 
-use super::{Fork, Iter, Snapshot, StorageKey, StorageValue};
-use storage::indexes_metadata::{self, IndexType, INDEXES_METADATA_TABLE_NAME};
+use super::{db::is_within_upper_bound, metering, metrics, Fork, Iter, Snapshot, StorageKey,
+            StorageValue};
+use storage::indexes_metadata::{self, IndexType, INDEXES_METADATA_TABLE_NAME,
+                                INDEX_FAMILY_MEMBERS_TABLE_NAME};
 
 /// Basic struct for all indices that implements common features.
 ///
@@ -43,16 +48,28 @@ pub struct BaseIndex<T> {
 
 /// An iterator over the entries of a `BaseIndex`.
 ///
-/// This struct is created by the [`iter`] or
-/// [`iter_from`] methods on [`BaseIndex`]. See its documentation for more.
+/// This struct is created by the [`iter`], [`iter_from`] or
+/// [`range`] methods on [`BaseIndex`]. See its documentation for more.
+///
+/// The iterator is double-ended: the entries can be taken from both ends
+/// of the iterated range, which allows to iterate over the index in descending
+/// order without scanning it.
 ///
 /// [`iter`]: struct.BaseIndex.html#method.iter
 /// [`iter_from`]: struct.BaseIndex.html#method.iter_from
+/// [`range`]: struct.BaseIndex.html#method.range
 /// [`BaseIndex`]: struct.BaseIndex.html
 pub struct BaseIndexIter<'a, K, V> {
-    base_iter: Iter<'a>,
+    view: &'a Snapshot,
+    name: &'a str,
     base_prefix_len: usize,
     index_id: Vec<u8>,
+    lower: Bound<Vec<u8>>,
+    upper: Bound<Vec<u8>>,
+    front: Option<Iter<'a>>,
+    back: Option<Iter<'a>>,
+    front_key: Option<Vec<u8>>,
+    back_key: Option<Vec<u8>>,
     ended: bool,
     _k: PhantomData<K>,
     _v: PhantomData<V>,
@@ -141,6 +158,29 @@ where
         }
     }
 
+    /// Creates a set of the keys of the indexes in the family with the given name which
+    /// are registered in the storage metadata.
+    pub(crate) fn family_members<S: AsRef<str>>(family_name: S, view: T) -> Self {
+        assert_valid_name(&family_name);
+        let mut index_id = family_name.as_ref().as_bytes().to_vec();
+        // Index names cannot contain zero bytes, so the prefixes of different families
+        // never overlap.
+        index_id.push(0);
+        BaseIndex {
+            name: INDEX_FAMILY_MEMBERS_TABLE_NAME.to_string(),
+            is_family: true,
+            index_id: Some(index_id),
+            is_mutable: true,
+            index_type: IndexType::KeySet,
+            view,
+        }
+    }
+
+    /// Returns the storage view of the index.
+    pub(crate) fn snapshot(&self) -> &Snapshot {
+        self.view.as_ref()
+    }
+
     fn prefixed_key<K: StorageKey + ?Sized>(&self, key: &K) -> Vec<u8> {
         match self.index_id {
             Some(ref prefix) => {
@@ -163,10 +203,11 @@ where
         K: StorageKey + ?Sized,
         V: StorageValue,
     {
-        self.view
-            .as_ref()
-            .get(&self.name, &self.prefixed_key(key))
-            .map(|v| StorageValue::from_bytes(Cow::Owned(v)))
+        let key = self.prefixed_key(key);
+        let value = self.view.as_ref().get(&self.name, &key);
+        metrics::record_read(&self.name, value.as_ref().map(Vec::as_slice));
+        metering::record_read(key.len() + value.as_ref().map_or(0, Vec::len));
+        value.map(|v| StorageValue::from_bytes(Cow::Owned(v)))
     }
 
     /// Returns `true` if the index contains a value of *any* type for the specified key of
@@ -175,9 +216,10 @@ where
     where
         K: StorageKey + ?Sized,
     {
-        self.view
-            .as_ref()
-            .contains(&self.name, &self.prefixed_key(key))
+        let key = self.prefixed_key(key);
+        metrics::record_read(&self.name, None);
+        metering::record_read(key.len());
+        self.view.as_ref().contains(&self.name, &key)
     }
 
     /// Returns an iterator over the entries of the index in ascending order. The iterator element
@@ -189,15 +231,7 @@ where
         K: StorageKey,
         V: StorageValue,
     {
-        let iter_prefix = self.prefixed_key(subprefix);
-        BaseIndexIter {
-            base_iter: self.view.as_ref().iter(&self.name, &iter_prefix),
-            base_prefix_len: self.index_id.as_ref().map_or(0, |p| p.len()),
-            index_id: iter_prefix,
-            ended: false,
-            _k: PhantomData,
-            _v: PhantomData,
-        }
+        self.range::<P, P, K, V>(subprefix, Unbounded, Unbounded)
     }
 
     /// Returns an iterator over the entries of the index in ascending order starting from the
@@ -210,12 +244,50 @@ where
         K: StorageKey,
         V: StorageValue,
     {
-        let iter_prefix = self.prefixed_key(subprefix);
-        let iter_from = self.prefixed_key(from);
+        self.range(subprefix, Included(from), Unbounded)
+    }
+
+    /// Returns an iterator over the entries of the index with the keys within the specified
+    /// bounds, in ascending order. The iterator element type is *any* key-value pair.
+    /// An argument `subprefix` allows to specify a subset of iteration.
+    pub fn range<P, F, K, V>(
+        &self,
+        subprefix: &P,
+        lower: Bound<&F>,
+        upper: Bound<&F>,
+    ) -> BaseIndexIter<K, V>
+    where
+        P: StorageKey,
+        F: StorageKey + ?Sized,
+        K: StorageKey,
+        V: StorageValue,
+    {
+        let prefixed_bound = |bound: Bound<&F>| match bound {
+            Included(key) => Included(self.prefixed_key(key)),
+            Excluded(key) => Excluded(self.prefixed_key(key)),
+            Unbounded => Unbounded,
+        };
+        let index_id = self.prefixed_key(subprefix);
+        // Keys starting with `index_id` are less than its successor, so the successor
+        // may be used as the upper bound for the reverse iteration.
+        let mut upper = prefixed_bound(upper);
+        if let Some(successor) = prefix_successor(&index_id) {
+            let is_narrower = is_within_upper_bound(&successor, as_slice_bound(&upper));
+            if is_narrower {
+                upper = Excluded(successor);
+            }
+        }
         BaseIndexIter {
-            base_iter: self.view.as_ref().iter(&self.name, &iter_from),
+            view: self.view.as_ref(),
+            name: &self.name,
             base_prefix_len: self.index_id.as_ref().map_or(0, |p| p.len()),
-            index_id: iter_prefix,
+            index_id,
+            lower: prefixed_bound(lower),
+            upper,
+            front: None,
+            back: None,
+            front_key: None,
+            back_key: None,
             ended: false,
             _k: PhantomData,
             _v: PhantomData,
@@ -224,6 +296,11 @@ where
 }
 
 impl<'a> BaseIndex<&'a mut Fork> {
+    /// Returns the fork the index is based on.
+    pub(crate) fn fork(&mut self) -> &mut Fork {
+        self.view
+    }
+
     fn set_index_type(&mut self) {
         if !self.is_mutable {
             indexes_metadata::set_index_type(
@@ -232,6 +309,9 @@ impl<'a> BaseIndex<&'a mut Fork> {
                 self.is_family,
                 &mut self.view,
             );
+            if let Some(ref index_id) = self.index_id {
+                indexes_metadata::add_family_member(&self.name, index_id, &mut self.view);
+            }
             self.is_mutable = true;
         }
     }
@@ -269,6 +349,21 @@ impl<'a> BaseIndex<&'a mut Fork> {
         self.set_index_type();
         self.view
             .remove_by_prefix(&self.name, self.index_id.as_ref());
+        if let Some(ref index_id) = self.index_id {
+            indexes_metadata::remove_family_member(&self.name, index_id, &mut self.view);
+            // The index is registered again once it is modified.
+            self.is_mutable = false;
+        }
+    }
+}
+
+impl<'a, K, V> BaseIndexIter<'a, K, V> {
+    /// Returns the key from which the forward iteration starts.
+    fn start_key(&self) -> &[u8] {
+        match self.lower {
+            Included(ref key) | Excluded(ref key) => cmp::max(key, &self.index_id),
+            Unbounded => &self.index_id,
+        }
     }
 }
 
@@ -283,16 +378,80 @@ where
         if self.ended {
             return None;
         }
-        if let Some((k, v)) = self.base_iter.next() {
-            if k.starts_with(&self.index_id) {
-                return Some((
-                    K::read(&k[self.base_prefix_len..]),
-                    V::from_bytes(Cow::Borrowed(v)),
-                ));
+        if self.front.is_none() {
+            let view = self.view;
+            let iter = view.iter(self.name, self.start_key());
+            self.front = Some(iter);
+        }
+        loop {
+            let item = match self.front.as_mut().unwrap().next() {
+                Some((k, v)) => {
+                    let is_within_range = k.starts_with(&self.index_id)
+                        && is_within_upper_bound(k, as_slice_bound(&self.upper))
+                        && self.back_key.as_ref().map_or(true, |back| k < back.as_slice());
+                    if !is_within_range {
+                        None
+                    } else if !is_within_lower_bound(k, &self.lower) {
+                        continue;
+                    } else {
+                        let front_key = self.front_key.get_or_insert_with(Vec::new);
+                        front_key.clear();
+                        front_key.extend_from_slice(k);
+                        metering::record_read(k.len() + v.len());
+                        Some((
+                            K::read(&k[self.base_prefix_len..]),
+                            V::from_bytes(Cow::Borrowed(v)),
+                        ))
+                    }
+                }
+                None => None,
+            };
+            if item.is_none() {
+                self.ended = true;
+            }
+            return item;
+        }
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for BaseIndexIter<'a, K, V>
+where
+    K: StorageKey,
+    V: StorageValue,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.ended {
+            return None;
+        }
+        if self.back.is_none() {
+            let view = self.view;
+            let iter = view.iter_rev(self.name, as_slice_bound(&self.upper));
+            self.back = Some(iter);
+        }
+        let item = match self.back.as_mut().unwrap().next() {
+            Some((k, v)) => {
+                let is_within_range = k.starts_with(&self.index_id)
+                    && is_within_lower_bound(k, &self.lower)
+                    && self.front_key.as_ref().map_or(true, |front| k > front.as_slice());
+                if is_within_range {
+                    let back_key = self.back_key.get_or_insert_with(Vec::new);
+                    back_key.clear();
+                    back_key.extend_from_slice(k);
+                    metering::record_read(k.len() + v.len());
+                    Some((
+                        K::read(&k[self.base_prefix_len..]),
+                        V::from_bytes(Cow::Borrowed(v)),
+                    ))
+                } else {
+                    None
+                }
             }
+            None => None,
+        };
+        if item.is_none() {
+            self.ended = true;
         }
-        self.ended = true;
-        None
+        item
     }
 }
 
@@ -302,6 +461,62 @@ impl<'a, K, V> ::std::fmt::Debug for BaseIndexIter<'a, K, V> {
     }
 }
 
+/// Replaces an unbounded start of a range of list positions with the first position,
+/// so that the list metadata stored before its elements is not iterated.
+pub(crate) fn list_start_bound(bound: Bound<&u64>) -> Bound<&u64> {
+    match bound {
+        Unbounded => Included(&0),
+        bound => bound,
+    }
+}
+
+/// Converts the value of a bound using the specified function.
+pub(crate) fn map_bound<T, U, F: FnOnce(T) -> U>(bound: Bound<T>, f: F) -> Bound<U> {
+    match bound {
+        Included(value) => Included(f(value)),
+        Excluded(value) => Excluded(f(value)),
+        Unbounded => Unbounded,
+    }
+}
+
+/// Converts `&Bound<T>` to `Bound<&T>`.
+pub(crate) fn bound_ref<T>(bound: &Bound<T>) -> Bound<&T> {
+    match *bound {
+        Included(ref value) => Included(value),
+        Excluded(ref value) => Excluded(value),
+        Unbounded => Unbounded,
+    }
+}
+
+fn as_slice_bound(bound: &Bound<Vec<u8>>) -> Bound<&[u8]> {
+    match *bound {
+        Included(ref key) => Included(key.as_slice()),
+        Excluded(ref key) => Excluded(key.as_slice()),
+        Unbounded => Unbounded,
+    }
+}
+
+fn is_within_lower_bound(key: &[u8], bound: &Bound<Vec<u8>>) -> bool {
+    match *bound {
+        Included(ref lower) => key >= lower.as_slice(),
+        Excluded(ref lower) => key > lower.as_slice(),
+        Unbounded => true,
+    }
+}
+
+/// Returns the least key greater than all keys starting with `prefix`, or `None`
+/// if there is no such key.
+fn prefix_successor(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut successor = prefix.to_vec();
+    while let Some(last) = successor.pop() {
+        if last < u8::max_value() {
+            successor.push(last + 1);
+            return Some(successor);
+        }
+    }
+    None
+}
+
 /// A function that validates an index name. Allowable characters in name: ASCII characters, digits
 /// and underscores.
 fn is_valid_name<S: AsRef<str>>(name: S) -> bool {
@@ -0,0 +1,393 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A database wrapper caching the results of point lookups.
+
+use std::{collections::{BTreeMap, Bound, HashMap},
+          fmt,
+          path::Path,
+          sync::{Arc, Mutex}};
+
+use super::{Database, Iter, Patch, Result, Snapshot};
+
+/// Full key of a cached value: column family name and key within the family.
+type CacheKey = (String, Vec<u8>);
+
+/// Statistics of a `CachedDatabase` cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheMetrics {
+    /// Number of lookups served from the cache.
+    pub hits: u64,
+    /// Number of lookups which were forwarded to the underlying database.
+    pub misses: u64,
+    /// Number of entries currently stored in the cache.
+    pub entries: usize,
+    /// Maximum number of entries in the cache.
+    pub capacity: usize,
+}
+
+impl CacheMetrics {
+    /// Returns the share of lookups served from the cache, or `0.0` if there were no lookups.
+    pub fn hit_ratio(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+/// Least recently used cache of the database lookups.
+///
+/// Absent values are cached as well, so repeated lookups of missing keys
+/// do not reach the underlying database either.
+#[derive(Debug)]
+struct LruCache {
+    capacity: usize,
+    /// Cached values together with the tick of their last use.
+    entries: HashMap<CacheKey, (Option<Vec<u8>>, u64)>,
+    /// Keys ordered by the tick of their last use.
+    recency: BTreeMap<u64, CacheKey>,
+    tick: u64,
+    /// Number of merges applied to the database since the cache creation.
+    generation: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl LruCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            generation: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn get(&mut self, key: &CacheKey) -> Option<Option<Vec<u8>>> {
+        let tick = self.next_tick();
+        let value = match self.entries.get_mut(key) {
+            Some(&mut (ref value, ref mut last_used)) => {
+                self.recency.remove(last_used);
+                *last_used = tick;
+                value.clone()
+            }
+            None => return None,
+        };
+        self.recency.insert(tick, key.clone());
+        Some(value)
+    }
+
+    fn insert(&mut self, key: CacheKey, value: Option<Vec<u8>>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let tick = self.next_tick();
+        if let Some((_, last_used)) = self.entries.insert(key.clone(), (value, tick)) {
+            self.recency.remove(&last_used);
+        }
+        self.recency.insert(tick, key);
+
+        while self.entries.len() > self.capacity {
+            let oldest = *self.recency.keys().next().unwrap();
+            let key = self.recency.remove(&oldest).unwrap();
+            self.entries.remove(&key);
+        }
+    }
+
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some((_, last_used)) = self.entries.remove(key) {
+            self.recency.remove(&last_used);
+        }
+    }
+}
+
+/// A wrapper around a database caching the results of [`Snapshot::get`] and
+/// [`Snapshot::contains`] calls.
+///
+/// The cache holds up to `capacity` recently accessed `(index name, key)` pairs and evicts
+/// the least recently used ones. The changes merged into the database invalidate
+/// the corresponding cache entries. Iterators are not cached and are always served
+/// by the underlying database.
+///
+/// The cache always reflects the latest state of the database, so it is only used by
+/// the snapshots created after the last merge. Older snapshots, including the one
+/// underlying a long-living `Fork`, read directly from the underlying database
+/// to preserve read isolation.
+///
+/// The cache is enabled for the databases opened with [`open_database`] if the cache size
+/// is specified in [`DbOptions`].
+///
+/// # Examples
+///
+/// ```
+/// use exonum::storage::{CachedDatabase, Database, MapIndex, MemoryDB};
+///
+/// let db = CachedDatabase::new(MemoryDB::new(), 1_000);
+/// let mut fork = db.fork();
+/// MapIndex::new("wallets", &mut fork).put(&1_u8, 10_u64);
+/// db.merge(fork.into_patch()).unwrap();
+///
+/// let snapshot = db.snapshot();
+/// let wallets: MapIndex<_, u8, u64> = MapIndex::new("wallets", &snapshot);
+/// assert_eq!(wallets.get(&1), Some(10));
+///
+/// // Repeated lookups are served from the cache.
+/// let metrics = db.metrics();
+/// assert_eq!(wallets.get(&1), Some(10));
+/// assert_eq!(db.metrics().hits, metrics.hits + 1);
+/// assert_eq!(db.metrics().misses, metrics.misses);
+/// ```
+///
+/// [`Snapshot::get`]: trait.Snapshot.html#tymethod.get
+/// [`Snapshot::contains`]: trait.Snapshot.html#method.contains
+/// [`open_database`]: fn.open_database.html
+/// [`DbOptions`]: struct.DbOptions.html
+pub struct CachedDatabase<D: Database> {
+    inner: D,
+    cache: Arc<Mutex<LruCache>>,
+}
+
+/// Snapshot of a `CachedDatabase`.
+struct CachedSnapshot {
+    inner: Box<Snapshot>,
+    cache: Arc<Mutex<LruCache>>,
+    /// Generation of the cache at the moment of the snapshot creation.
+    generation: u64,
+}
+
+impl<D: Database> CachedDatabase<D> {
+    /// Wraps the database with a cache holding up to `capacity` entries.
+    pub fn new(inner: D, capacity: usize) -> Self {
+        Self {
+            inner,
+            cache: Arc::new(Mutex::new(LruCache::new(capacity))),
+        }
+    }
+
+    /// Returns a reference to the underlying database.
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    /// Returns the current statistics of the cache.
+    pub fn metrics(&self) -> CacheMetrics {
+        let cache = self.cache.lock().unwrap();
+        CacheMetrics {
+            hits: cache.hits,
+            misses: cache.misses,
+            entries: cache.entries.len(),
+            capacity: cache.capacity,
+        }
+    }
+
+    fn merge_with<F>(&self, patch: Patch, merge: F) -> Result<()>
+    where
+        F: FnOnce(&D, Patch) -> Result<()>,
+    {
+        let keys: Vec<CacheKey> = patch
+            .iter()
+            .flat_map(|(name, changes)| {
+                changes
+                    .iter()
+                    .map(move |(key, _)| (name.clone(), key.clone()))
+            })
+            .collect();
+
+        // The lock is held during the merge so that no snapshot is created
+        // between the moment the changes are applied and the cache invalidation.
+        let mut cache = self.cache.lock().unwrap();
+        merge(&self.inner, patch)?;
+        for key in &keys {
+            cache.remove(key);
+        }
+        cache.generation += 1;
+        Ok(())
+    }
+}
+
+impl<D: Database> Database for CachedDatabase<D> {
+    fn snapshot(&self) -> Box<Snapshot> {
+        let cache = self.cache.lock().unwrap();
+        Box::new(CachedSnapshot {
+            inner: self.inner.snapshot(),
+            cache: Arc::clone(&self.cache),
+            generation: cache.generation,
+        })
+    }
+
+    fn merge(&self, patch: Patch) -> Result<()> {
+        self.merge_with(patch, |db, patch| db.merge(patch))
+    }
+
+    fn merge_sync(&self, patch: Patch) -> Result<()> {
+        self.merge_with(patch, |db, patch| db.merge_sync(patch))
+    }
+
+    fn create_checkpoint(&self, path: &Path) -> Result<()> {
+        self.inner.create_checkpoint(path)
+    }
+}
+
+impl<D: Database> fmt::Debug for CachedDatabase<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CachedDatabase")
+            .field("metrics", &self.metrics())
+            .finish()
+    }
+}
+
+impl Snapshot for CachedSnapshot {
+    fn get(&self, name: &str, key: &[u8]) -> Option<Vec<u8>> {
+        let cache_key = (name.to_owned(), key.to_vec());
+        {
+            let mut cache = self.cache.lock().unwrap();
+            if cache.generation == self.generation {
+                if let Some(value) = cache.get(&cache_key) {
+                    cache.hits += 1;
+                    return value;
+                }
+            }
+            cache.misses += 1;
+        }
+
+        let value = self.inner.get(name, key);
+        let mut cache = self.cache.lock().unwrap();
+        // The database might have been changed while the value was being read.
+        if cache.generation == self.generation {
+            cache.insert(cache_key, value.clone());
+        }
+        value
+    }
+
+    fn iter<'a>(&'a self, name: &str, from: &[u8]) -> Iter<'a> {
+        self.inner.iter(name, from)
+    }
+
+    fn iter_rev<'a>(&'a self, name: &str, to: Bound<&[u8]>) -> Iter<'a> {
+        self.inner.iter_rev(name, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Database, MemoryDB};
+    use super::*;
+
+    const IDX_NAME: &str = "idx_name";
+
+    fn put(db: &CachedDatabase<MemoryDB>, key: u8, value: u8) {
+        let mut fork = db.fork();
+        fork.put(IDX_NAME, vec![key], vec![value]);
+        db.merge(fork.into_patch()).unwrap();
+    }
+
+    #[test]
+    fn reads_are_cached() {
+        let db = CachedDatabase::new(MemoryDB::new(), 16);
+        put(&db, 1, 10);
+
+        let snapshot = db.snapshot();
+        assert_eq!(snapshot.get(IDX_NAME, &[1]), Some(vec![10]));
+        assert_eq!(snapshot.get(IDX_NAME, &[1]), Some(vec![10]));
+        assert!(!snapshot.contains(IDX_NAME, &[2]));
+        assert!(!snapshot.contains(IDX_NAME, &[2]));
+
+        let metrics = db.metrics();
+        assert_eq!(metrics.hits, 2);
+        assert_eq!(metrics.misses, 2);
+        assert_eq!(metrics.entries, 2);
+        assert_eq!(metrics.hit_ratio(), 0.5);
+    }
+
+    #[test]
+    fn merge_invalidates_cache() {
+        let db = CachedDatabase::new(MemoryDB::new(), 16);
+        put(&db, 1, 10);
+        assert_eq!(db.snapshot().get(IDX_NAME, &[1]), Some(vec![10]));
+        assert_eq!(db.snapshot().get(IDX_NAME, &[2]), None);
+
+        put(&db, 1, 11);
+        put(&db, 2, 20);
+        assert_eq!(db.snapshot().get(IDX_NAME, &[1]), Some(vec![11]));
+        assert_eq!(db.snapshot().get(IDX_NAME, &[2]), Some(vec![20]));
+
+        let mut fork = db.fork();
+        fork.remove(IDX_NAME, vec![1]);
+        db.merge(fork.into_patch()).unwrap();
+        assert_eq!(db.snapshot().get(IDX_NAME, &[1]), None);
+        assert_eq!(db.snapshot().get(IDX_NAME, &[2]), Some(vec![20]));
+    }
+
+    #[test]
+    fn old_snapshots_are_isolated() {
+        let db = CachedDatabase::new(MemoryDB::new(), 16);
+        put(&db, 1, 10);
+        let old_snapshot = db.snapshot();
+
+        put(&db, 1, 11);
+        assert_eq!(db.snapshot().get(IDX_NAME, &[1]), Some(vec![11]));
+        assert_eq!(old_snapshot.get(IDX_NAME, &[1]), Some(vec![10]));
+        // The value read by the old snapshot does not get into the cache.
+        assert_eq!(db.snapshot().get(IDX_NAME, &[1]), Some(vec![11]));
+        assert_eq!(db.metrics().hits, 1);
+    }
+
+    #[test]
+    fn least_recently_used_entries_are_evicted() {
+        let db = CachedDatabase::new(MemoryDB::new(), 2);
+        for i in 0..3 {
+            put(&db, i, i);
+        }
+
+        let snapshot = db.snapshot();
+        snapshot.get(IDX_NAME, &[0]);
+        snapshot.get(IDX_NAME, &[1]);
+        snapshot.get(IDX_NAME, &[0]);
+        snapshot.get(IDX_NAME, &[2]);
+        assert_eq!(db.metrics().entries, 2);
+        assert_eq!(db.metrics().hits, 1);
+
+        // Key `1` has been evicted, key `0` is still cached.
+        assert_eq!(snapshot.get(IDX_NAME, &[0]), Some(vec![0]));
+        assert_eq!(db.metrics().hits, 2);
+        assert_eq!(snapshot.get(IDX_NAME, &[1]), Some(vec![1]));
+        assert_eq!(db.metrics().hits, 2);
+    }
+
+    #[test]
+    fn zero_capacity_disables_cache() {
+        let db = CachedDatabase::new(MemoryDB::new(), 0);
+        put(&db, 1, 10);
+
+        let snapshot = db.snapshot();
+        assert_eq!(snapshot.get(IDX_NAME, &[1]), Some(vec![10]));
+        assert_eq!(snapshot.get(IDX_NAME, &[1]), Some(vec![10]));
+
+        let metrics = db.metrics();
+        assert_eq!(metrics.hits, 0);
+        assert_eq!(metrics.misses, 2);
+        assert_eq!(metrics.entries, 0);
+    }
+}
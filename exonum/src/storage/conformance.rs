@@ -0,0 +1,626 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conformance tests for `Database` implementations.
+//!
+//! The module is available with the `conformance` feature. Each public function
+//! checks one aspect of the contract of the [`Database`] and [`Snapshot`] traits
+//! and panics if the database violates it. The functions expect an empty database.
+//!
+//! - Snapshots are isolated from the changes merged after their creation.
+//! - A patch is merged atomically; column families are independent from each other
+//!   and are created implicitly on the first write.
+//! - Iterators return the entries of a column family in the lexicographic order
+//!   of keys, starting from the first key that is not less than `from`. Reverse
+//!   iterators return the same entries in the descending order, starting from
+//!   the upper bound.
+//! - `Fork` iterators, checkpoints and `remove_by_prefix` behave consistently on top
+//!   of the database.
+//!
+//! Besides that, [`differential`] runs random sequences of operations against
+//! the database and [`MemoryDB`], which serves as the reference implementation.
+//!
+//! The easiest way to check a database is the [`database_conformance_tests!`] macro,
+//! which generates a test for every check. It accepts a closure that creates
+//! a new empty database.
+//!
+//! # Examples
+//!
+//! ```
+//! #[macro_use]
+//! extern crate exonum;
+//!
+//! mod memorydb_conformance {
+//!     use exonum::storage::MemoryDB;
+//!
+//!     database_conformance_tests!(|| MemoryDB::new());
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! [`Database`]: ../trait.Database.html
+//! [`Snapshot`]: ../trait.Snapshot.html
+//! [`MemoryDB`]: ../struct.MemoryDB.html
+//! [`differential`]: fn.differential.html
+//! [`database_conformance_tests!`]: ../../macro.database_conformance_tests.html
+
+use proptest::{prelude::*, test_runner::{Config, TestRunner}};
+
+use std::collections::Bound::{self, Excluded, Included, Unbounded};
+
+use super::{Database, Fork, MemoryDB, Snapshot};
+
+/// Names of the column families used by the checks.
+const NAMES: [&str; 3] = ["conformance", "conformance.a", "conformance_b"];
+
+type Entries = Vec<(Vec<u8>, Vec<u8>)>;
+
+/// An operation performed on both databases during differential testing.
+#[derive(Debug, Clone)]
+enum Operation {
+    Put(usize, Vec<u8>, Vec<u8>),
+    Remove(usize, Vec<u8>),
+    RemoveByPrefix(usize, Option<Vec<u8>>),
+    Iter(usize, Vec<u8>),
+    Checkpoint,
+    Rollback,
+    Merge,
+}
+
+/// Collects the entries returned by the iterator over `name` starting from `from`
+/// and checks that `peek` is consistent with `next`.
+fn collect(view: &Snapshot, name: &str, from: &[u8]) -> Entries {
+    let mut entries = Vec::new();
+    let mut iter = view.iter(name, from);
+    loop {
+        let peeked = iter.peek().map(|(k, v)| (k.to_vec(), v.to_vec()));
+        let next = iter.next().map(|(k, v)| (k.to_vec(), v.to_vec()));
+        assert_eq!(peeked, next, "`peek` is inconsistent with `next`");
+        match next {
+            Some(entry) => entries.push(entry),
+            None => break,
+        }
+    }
+    assert!(
+        entries.windows(2).all(|pair| pair[0].0 < pair[1].0),
+        "Iterator does not return keys in the ascending order"
+    );
+    assert!(
+        entries.iter().all(|&(ref k, _)| k.as_slice() >= from),
+        "Iterator returns keys less than `from`"
+    );
+    entries
+}
+
+/// Collects the entries returned by the reverse iterator over `name` up to `to`
+/// and checks that `peek` is consistent with `next`.
+fn collect_rev(view: &Snapshot, name: &str, to: Bound<&[u8]>) -> Entries {
+    let mut entries = Vec::new();
+    let mut iter = view.iter_rev(name, to);
+    loop {
+        let peeked = iter.peek().map(|(k, v)| (k.to_vec(), v.to_vec()));
+        let next = iter.next().map(|(k, v)| (k.to_vec(), v.to_vec()));
+        assert_eq!(peeked, next, "`peek` is inconsistent with `next`");
+        match next {
+            Some(entry) => entries.push(entry),
+            None => break,
+        }
+    }
+    assert!(
+        entries.windows(2).all(|pair| pair[0].0 > pair[1].0),
+        "Reverse iterator does not return keys in the descending order"
+    );
+    assert!(
+        entries.iter().all(|&(ref k, _)| match to {
+            Included(to) => k.as_slice() <= to,
+            Excluded(to) => k.as_slice() < to,
+            Unbounded => true,
+        }),
+        "Reverse iterator returns keys exceeding the upper bound"
+    );
+    entries
+}
+
+fn entries(items: &[(&[u8], &[u8])]) -> Entries {
+    items
+        .iter()
+        .map(|&(k, v)| (k.to_vec(), v.to_vec()))
+        .collect()
+}
+
+/// Checks that both views contain the same data in all column families used by the checks.
+fn assert_same_views(view: &Snapshot, reference: &Snapshot) {
+    for name in &NAMES {
+        let expected = collect(reference, name, &[]);
+        assert_eq!(collect(view, name, &[]), expected, "Column family {}", name);
+        let mut expected_rev = expected.clone();
+        expected_rev.reverse();
+        assert_eq!(
+            collect_rev(view, name, Unbounded),
+            expected_rev,
+            "Column family {}",
+            name
+        );
+        for &(ref key, ref value) in &expected {
+            assert_eq!(view.get(name, key).as_ref(), Some(value));
+            assert!(view.contains(name, key));
+        }
+    }
+}
+
+/// Checks that snapshots are not affected by the changes merged after their creation.
+pub fn snapshot_isolation<D: Database>(db: &D) {
+    let name = NAMES[0];
+    let mut fork = db.fork();
+    fork.put(name, vec![1], vec![1]);
+    fork.put(name, vec![2], vec![2]);
+    db.merge(fork.into_patch()).unwrap();
+
+    let snapshot = db.snapshot();
+    let fork = db.fork();
+    let iter_snapshot = db.snapshot();
+    let mut iter = iter_snapshot.iter(name, &[]);
+    assert_eq!(iter.next(), Some((&[1][..], &[1][..])));
+
+    let mut other_fork = db.fork();
+    other_fork.put(name, vec![1], vec![10]);
+    other_fork.remove(name, vec![2]);
+    other_fork.put(name, vec![3], vec![3]);
+    other_fork.put(NAMES[1], vec![1], vec![1]);
+    db.merge(other_fork.into_patch()).unwrap();
+
+    let expected = entries(&[(&[1], &[1]), (&[2], &[2])]);
+    assert_eq!(collect(&*snapshot, name, &[]), expected);
+    assert_eq!(collect(&fork, name, &[]), expected);
+    assert_eq!(snapshot.get(name, &[1]), Some(vec![1]));
+    assert!(!snapshot.contains(name, &[3]));
+    assert!(!snapshot.contains(NAMES[1], &[1]));
+    // An iterator created before the merge observes the old state as well.
+    assert_eq!(iter.next(), Some((&[2][..], &[2][..])));
+    assert_eq!(iter.next(), None);
+
+    let snapshot = db.snapshot();
+    assert_eq!(
+        collect(&*snapshot, name, &[]),
+        entries(&[(&[1], &[10]), (&[3], &[3])])
+    );
+    assert_eq!(snapshot.get(NAMES[1], &[1]), Some(vec![1]));
+}
+
+/// Checks that patches are merged as a whole, both with `merge` and `merge_sync`,
+/// and that later changes override the earlier ones.
+pub fn merge<D: Database>(db: &D) {
+    db.merge(db.fork().into_patch()).unwrap();
+    assert!(collect(&*db.snapshot(), NAMES[0], &[]).is_empty());
+
+    let mut fork = db.fork();
+    for name in &NAMES {
+        fork.put(name, vec![1], vec![1]);
+        fork.put(name, vec![2], vec![2]);
+    }
+    fork.put(NAMES[0], vec![], vec![0]);
+    fork.put(NAMES[0], vec![3], vec![]);
+    db.merge(fork.into_patch()).unwrap();
+
+    let mut fork = db.fork();
+    fork.remove(NAMES[0], vec![1]);
+    fork.put(NAMES[1], vec![2], vec![20]);
+    fork.remove(NAMES[2], vec![5]);
+    db.merge_sync(fork.into_patch()).unwrap();
+
+    let snapshot = db.snapshot();
+    assert_eq!(
+        collect(&*snapshot, NAMES[0], &[]),
+        entries(&[(&[], &[0]), (&[2], &[2]), (&[3], &[])])
+    );
+    assert_eq!(
+        collect(&*snapshot, NAMES[1], &[]),
+        entries(&[(&[1], &[1]), (&[2], &[20])])
+    );
+    assert_eq!(
+        collect(&*snapshot, NAMES[2], &[]),
+        entries(&[(&[1], &[1]), (&[2], &[2])])
+    );
+    assert_eq!(snapshot.get(NAMES[0], &[3]), Some(vec![]));
+    assert!(snapshot.contains(NAMES[0], &[]));
+    assert!(!snapshot.contains(NAMES[2], &[5]));
+}
+
+/// Checks that column families are independent and do not need to be created
+/// before use, even if their names share a prefix.
+pub fn column_families<D: Database>(db: &D) {
+    let snapshot = db.snapshot();
+    assert_eq!(snapshot.get("conformance.missing", &[1]), None);
+    assert!(collect(&*snapshot, "conformance.missing", &[]).is_empty());
+
+    let mut fork = db.fork();
+    for (i, name) in NAMES.iter().enumerate() {
+        fork.put(name, vec![1], vec![i as u8]);
+    }
+    fork.put(NAMES[2], vec![2], vec![2]);
+    db.merge(fork.into_patch()).unwrap();
+
+    let snapshot = db.snapshot();
+    for (i, name) in NAMES.iter().enumerate() {
+        assert_eq!(snapshot.get(name, &[1]), Some(vec![i as u8]));
+    }
+    assert_eq!(collect(&*snapshot, NAMES[0], &[]), entries(&[(&[1], &[0])]));
+    assert_eq!(
+        collect(&*snapshot, NAMES[2], &[]),
+        entries(&[(&[1], &[2]), (&[2], &[2])])
+    );
+    assert!(collect(&*snapshot, "conformance.missing", &[]).is_empty());
+}
+
+/// Checks the bounds and the order of iteration.
+pub fn iter_bounds<D: Database>(db: &D) {
+    let name = NAMES[0];
+    let keys: [&[u8]; 7] = [&[], &[0], &[1], &[1, 0], &[1, 255], &[2], &[255, 255]];
+    let mut fork = db.fork();
+    for key in keys.iter().rev() {
+        fork.put(name, key.to_vec(), key.to_vec());
+    }
+    db.merge(fork.into_patch()).unwrap();
+
+    let snapshot = db.snapshot();
+    let all = keys.iter().map(|k| (k.to_vec(), k.to_vec())).collect::<Vec<_>>();
+    assert_eq!(collect(&*snapshot, name, &[]), all);
+    assert_eq!(collect(&*snapshot, name, &[1]), all[2..].to_vec());
+    assert_eq!(collect(&*snapshot, name, &[1, 0, 0]), all[4..].to_vec());
+    assert_eq!(collect(&*snapshot, name, &[1, 255, 0]), all[5..].to_vec());
+    assert_eq!(collect(&*snapshot, name, &[255, 255]), all[6..].to_vec());
+    assert!(collect(&*snapshot, name, &[255, 255, 0]).is_empty());
+
+    let rev = |mut entries: Entries| {
+        entries.reverse();
+        entries
+    };
+    assert_eq!(collect_rev(&*snapshot, name, Unbounded), rev(all.clone()));
+    assert_eq!(
+        collect_rev(&*snapshot, name, Included(&[1, 0][..])),
+        rev(all[..4].to_vec())
+    );
+    assert_eq!(
+        collect_rev(&*snapshot, name, Excluded(&[1, 0][..])),
+        rev(all[..3].to_vec())
+    );
+    assert_eq!(
+        collect_rev(&*snapshot, name, Included(&[1, 0, 0][..])),
+        rev(all[..4].to_vec())
+    );
+    assert_eq!(
+        collect_rev(&*snapshot, name, Included(&[][..])),
+        rev(all[..1].to_vec())
+    );
+    assert!(collect_rev(&*snapshot, name, Excluded(&[][..])).is_empty());
+
+    let mut iter = snapshot.iter(name, &[2]);
+    assert_eq!(iter.peek(), Some((&[2][..], &[2][..])));
+    assert_eq!(iter.peek(), Some((&[2][..], &[2][..])));
+    assert_eq!(iter.next(), Some((&[2][..], &[2][..])));
+    assert_eq!(iter.next(), Some((&[255, 255][..], &[255, 255][..])));
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.peek(), None);
+    assert_eq!(iter.next(), None);
+}
+
+/// Checks that `Fork` iterators merge the stored data with the changes in the fork.
+pub fn fork_iter<D: Database>(db: &D) {
+    let name = NAMES[0];
+    let mut fork = db.fork();
+    for i in &[10, 20, 30] {
+        fork.put(name, vec![*i], vec![*i]);
+    }
+    db.merge(fork.into_patch()).unwrap();
+
+    let mut fork = db.fork();
+    assert_eq!(
+        collect(&fork, name, &[11]),
+        entries(&[(&[20], &[20]), (&[30], &[30])])
+    );
+
+    fork.put(name, vec![5], vec![5]);
+    fork.put(name, vec![25], vec![25]);
+    fork.put(name, vec![30], vec![31]);
+    fork.remove(name, vec![20]);
+    fork.remove(name, vec![15]);
+    fork.put(name, vec![35], vec![35]);
+    let expected = entries(&[
+        (&[5], &[5]),
+        (&[10], &[10]),
+        (&[25], &[25]),
+        (&[30], &[31]),
+        (&[35], &[35]),
+    ]);
+    assert_eq!(collect(&fork, name, &[]), expected);
+    assert_eq!(collect(&fork, name, &[20]), expected[2..].to_vec());
+
+    fork.remove(name, vec![5]);
+    fork.remove(name, vec![10]);
+    fork.remove(name, vec![30]);
+    assert_eq!(
+        collect(&fork, name, &[]),
+        entries(&[(&[25], &[25]), (&[35], &[35])])
+    );
+
+    db.merge(fork.into_patch()).unwrap();
+    assert_eq!(
+        collect(&*db.snapshot(), name, &[]),
+        entries(&[(&[25], &[25]), (&[35], &[35])])
+    );
+}
+
+/// Checks that `remove_by_prefix` removes exactly the keys with the specified prefix,
+/// both stored in the database and added to the fork.
+pub fn remove_by_prefix<D: Database>(db: &D) {
+    let name = NAMES[0];
+    let mut fork = db.fork();
+    for key in &[vec![0], vec![1], vec![1, 0], vec![1, 1], vec![2], vec![2, 1]] {
+        fork.put(name, key.clone(), vec![1]);
+    }
+    fork.put(NAMES[1], vec![1], vec![1]);
+    db.merge(fork.into_patch()).unwrap();
+
+    let mut fork = db.fork();
+    fork.put(name, vec![1, 2], vec![2]);
+    fork.remove_by_prefix(name, Some(&vec![1]));
+    assert_eq!(
+        collect(&fork, name, &[]),
+        entries(&[(&[0], &[1]), (&[2], &[1]), (&[2, 1], &[1])])
+    );
+    fork.put(name, vec![1, 3], vec![3]);
+    db.merge(fork.into_patch()).unwrap();
+
+    let snapshot = db.snapshot();
+    assert_eq!(
+        collect(&*snapshot, name, &[]),
+        entries(&[(&[0], &[1]), (&[1, 3], &[3]), (&[2], &[1]), (&[2, 1], &[1])])
+    );
+    assert_eq!(snapshot.get(NAMES[1], &[1]), Some(vec![1]));
+
+    let mut fork = db.fork();
+    fork.put(name, vec![5], vec![5]);
+    fork.remove_by_prefix(name, None);
+    assert!(collect(&fork, name, &[]).is_empty());
+    db.merge(fork.into_patch()).unwrap();
+    assert!(collect(&*db.snapshot(), name, &[]).is_empty());
+    assert_eq!(db.snapshot().get(NAMES[1], &[1]), Some(vec![1]));
+}
+
+/// Checks that the changes made after a `Fork` checkpoint can be rolled back.
+pub fn changelog<D: Database>(db: &D) {
+    let name = NAMES[0];
+    let mut fork = db.fork();
+    fork.put(name, vec![1], vec![1]);
+    fork.put(name, vec![2], vec![2]);
+    db.merge(fork.into_patch()).unwrap();
+
+    let mut fork = db.fork();
+    fork.put(name, vec![3], vec![3]);
+    fork.checkpoint();
+    fork.put(name, vec![1], vec![10]);
+    fork.remove(name, vec![2]);
+    fork.remove(name, vec![3]);
+    fork.put(name, vec![4], vec![4]);
+    fork.rollback();
+    let expected = entries(&[(&[1], &[1]), (&[2], &[2]), (&[3], &[3])]);
+    assert_eq!(collect(&fork, name, &[]), expected);
+
+    fork.checkpoint();
+    fork.remove_by_prefix(name, None);
+    assert!(collect(&fork, name, &[]).is_empty());
+    fork.rollback();
+    assert_eq!(collect(&fork, name, &[]), expected);
+
+    fork.checkpoint();
+    fork.put(name, vec![4], vec![4]);
+    fork.commit();
+    db.merge(fork.into_patch()).unwrap();
+    assert_eq!(
+        collect(&*db.snapshot(), name, &[]),
+        entries(&[(&[1], &[1]), (&[2], &[2]), (&[3], &[3]), (&[4], &[4])])
+    );
+}
+
+/// Applies the operation to the forks of both databases and checks that the results match.
+fn apply_operation<D: Database>(
+    operation: &Operation,
+    db: &D,
+    fork: &mut Fork,
+    reference: &MemoryDB,
+    reference_fork: &mut Fork,
+    in_checkpoint: &mut bool,
+) {
+    match *operation {
+        Operation::Put(name, ref key, ref value) => {
+            fork.put(NAMES[name], key.clone(), value.clone());
+            reference_fork.put(NAMES[name], key.clone(), value.clone());
+        }
+        Operation::Remove(name, ref key) => {
+            fork.remove(NAMES[name], key.clone());
+            reference_fork.remove(NAMES[name], key.clone());
+        }
+        Operation::RemoveByPrefix(name, ref prefix) => {
+            fork.remove_by_prefix(NAMES[name], prefix.as_ref());
+            reference_fork.remove_by_prefix(NAMES[name], prefix.as_ref());
+        }
+        Operation::Iter(name, ref from) => {
+            assert_eq!(
+                collect(&*fork, NAMES[name], from),
+                collect(&*reference_fork, NAMES[name], from)
+            );
+            assert_eq!(
+                collect(&*db.snapshot(), NAMES[name], from),
+                collect(&*reference.snapshot(), NAMES[name], from)
+            );
+            for &to in &[Included(from.as_slice()), Excluded(from.as_slice())] {
+                assert_eq!(
+                    collect_rev(&*fork, NAMES[name], to),
+                    collect_rev(&*reference_fork, NAMES[name], to)
+                );
+                assert_eq!(
+                    collect_rev(&*db.snapshot(), NAMES[name], to),
+                    collect_rev(&*reference.snapshot(), NAMES[name], to)
+                );
+            }
+        }
+        Operation::Checkpoint => {
+            if !*in_checkpoint {
+                fork.checkpoint();
+                reference_fork.checkpoint();
+                *in_checkpoint = true;
+            }
+        }
+        Operation::Rollback => {
+            if *in_checkpoint {
+                fork.rollback();
+                reference_fork.rollback();
+                *in_checkpoint = false;
+            }
+        }
+        Operation::Merge => {
+            if *in_checkpoint {
+                fork.commit();
+                reference_fork.commit();
+                *in_checkpoint = false;
+            }
+            let old_snapshot = db.snapshot();
+            let old_reference = reference.snapshot();
+            let patch = ::std::mem::replace(fork, db.fork()).into_patch();
+            db.merge(patch).unwrap();
+            let reference_patch = ::std::mem::replace(reference_fork, reference.fork())
+                .into_patch();
+            reference.merge(reference_patch).unwrap();
+
+            assert_same_views(&*old_snapshot, &*old_reference);
+            assert_same_views(&*db.snapshot(), &*reference.snapshot());
+        }
+    }
+    assert_same_views(&*fork, &*reference_fork);
+}
+
+/// Runs random sequences of operations against a database created by `create`
+/// and `MemoryDB`, and checks that the observed data is the same.
+///
+/// `cases` is the number of the generated sequences. Each case uses a new database.
+pub fn differential<D, F>(create: F, cases: u32)
+where
+    D: Database,
+    F: Fn() -> D,
+{
+    let key = || prop::collection::vec(0..4_u8, 0..3);
+    let name = || 0..NAMES.len();
+    let operation = prop_oneof![
+        4 => (name(), key(), prop::collection::vec(any::<u8>(), 0..3))
+            .prop_map(|(name, key, value)| Operation::Put(name, key, value)),
+        2 => (name(), key()).prop_map(|(name, key)| Operation::Remove(name, key)),
+        1 => (name(), prop::option::of(key()))
+            .prop_map(|(name, prefix)| Operation::RemoveByPrefix(name, prefix)),
+        1 => (name(), key()).prop_map(|(name, from)| Operation::Iter(name, from)),
+        1 => Just(Operation::Checkpoint),
+        1 => Just(Operation::Rollback),
+        2 => Just(Operation::Merge),
+    ];
+    let operations = prop::collection::vec(operation, 1..50);
+
+    let mut runner = TestRunner::new(Config::with_cases(cases));
+    let result = runner.run(&operations, |operations| {
+        let db = create();
+        let reference = MemoryDB::new();
+        let mut fork = db.fork();
+        let mut reference_fork = reference.fork();
+        let mut in_checkpoint = false;
+        for operation in operations.iter() {
+            apply_operation(
+                operation,
+                &db,
+                &mut fork,
+                &reference,
+                &mut reference_fork,
+                &mut in_checkpoint,
+            );
+        }
+        apply_operation(
+            &Operation::Merge,
+            &db,
+            &mut fork,
+            &reference,
+            &mut reference_fork,
+            &mut in_checkpoint,
+        );
+        Ok(())
+    });
+    if let Err(err) = result {
+        panic!("Differential test against `MemoryDB` failed: {:?}", err);
+    }
+}
+
+/// Generates tests checking that a `Database` implementation conforms to the contract
+/// of the trait.
+///
+/// The argument is a closure creating a new empty database; it is called once for every
+/// check. The generated tests are named `conformance_*`, so the macro is usually invoked
+/// in a separate module. Optionally, the number of cases for the differential test may be
+/// specified (256 by default).
+///
+/// See [`storage::conformance`] for more details.
+///
+/// [`storage::conformance`]: storage/conformance/index.html
+#[macro_export]
+macro_rules! database_conformance_tests {
+    ($create:expr) => {
+        database_conformance_tests!($create, cases = 256);
+    };
+    ($create:expr,cases = $cases:expr) => {
+        #[test]
+        fn conformance_snapshot_isolation() {
+            $crate::storage::conformance::snapshot_isolation(&($create)());
+        }
+
+        #[test]
+        fn conformance_merge() {
+            $crate::storage::conformance::merge(&($create)());
+        }
+
+        #[test]
+        fn conformance_column_families() {
+            $crate::storage::conformance::column_families(&($create)());
+        }
+
+        #[test]
+        fn conformance_iter_bounds() {
+            $crate::storage::conformance::iter_bounds(&($create)());
+        }
+
+        #[test]
+        fn conformance_fork_iter() {
+            $crate::storage::conformance::fork_iter(&($create)());
+        }
+
+        #[test]
+        fn conformance_remove_by_prefix() {
+            $crate::storage::conformance::remove_by_prefix(&($create)());
+        }
+
+        #[test]
+        fn conformance_changelog() {
+            $crate::storage::conformance::changelog(&($create)());
+        }
+
+        #[test]
+        fn conformance_differential() {
+            $crate::storage::conformance::differential($create, $cases);
+        }
+    };
+}
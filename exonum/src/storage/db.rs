@@ -12,15 +12,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use byteorder::{ByteOrder, LittleEndian};
+use serde::{de::Error as DeError, Deserialize, Deserializer, Serialize, Serializer};
+
 use std::{cmp::Ordering::{Equal, Greater, Less},
-          collections::{btree_map::{BTreeMap, IntoIter as BtmIntoIter, Iter as BtmIter, Range},
+          collections::{btree_map::{BTreeMap, IntoIter as BtmIntoIter, Iter as BtmIter},
                         hash_map::{Entry as HmEntry, IntoIter as HmIntoIter, Iter as HmIter},
-                        Bound::{Included, Unbounded},
+                        Bound::{self, Excluded, Included, Unbounded},
                         HashMap},
-          iter::{Iterator as StdIterator, Peekable}};
+          iter::{Iterator as StdIterator, Peekable},
+          path::Path};
 
 use self::NextIterValue::*;
-use super::Result;
+use super::{metering, metrics, Error, Result};
+use encoding::serialize::{decode_hex, encode_hex};
+
+/// Magic bytes the binary representation of a `Patch` starts with.
+const PATCH_MAGIC: &[u8] = b"EXNMPTCH";
+/// Version of the binary representation of a `Patch`.
+const PATCH_FORMAT_VERSION: u8 = 1;
 
 /// Map containing changes with corresponding key.
 #[derive(Debug, Clone)]
@@ -82,7 +92,7 @@ impl Patch {
     }
 
     /// Returns changes for the given name.
-    fn changes(&self, name: &str) -> Option<&Changes> {
+    pub(crate) fn changes(&self, name: &str) -> Option<&Changes> {
         self.changes.get(name)
     }
 
@@ -117,6 +127,186 @@ impl Patch {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Serializes the patch into a stable binary representation.
+    ///
+    /// The representation starts with the `EXNMPTCH` magic bytes and a format version byte
+    /// followed by the number of column families and, for each column family in the
+    /// lexicographic order of names, its name, the number of changes and the changes ordered
+    /// by key. A change is encoded as a key, a tag byte (`0` for deletion, `1` for insertion)
+    /// and, for insertions, a value. Names, keys and values are prefixed with their lengths;
+    /// lengths and counts are encoded as little-endian `u32` numbers.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut names: Vec<&String> = self.changes.keys().collect();
+        names.sort();
+
+        let mut buf = PATCH_MAGIC.to_vec();
+        buf.push(PATCH_FORMAT_VERSION);
+        write_len(&mut buf, names.len());
+        for name in names {
+            let changes = &self.changes[name];
+            write_bytes(&mut buf, name.as_bytes());
+            write_len(&mut buf, changes.data.len());
+            for (key, change) in &changes.data {
+                write_bytes(&mut buf, key);
+                match *change {
+                    Change::Put(ref value) => {
+                        buf.push(1);
+                        write_bytes(&mut buf, value);
+                    }
+                    Change::Delete => buf.push(0),
+                }
+            }
+        }
+        buf
+    }
+
+    /// Deserializes the patch from the binary representation produced by
+    /// [`to_bytes`](#method.to_bytes).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is malformed or has an unsupported format version.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Patch> {
+        let mut reader = PatchReader { bytes };
+        if reader.read_slice(PATCH_MAGIC.len())? != PATCH_MAGIC {
+            return Err(Error::new("Invalid patch: unexpected magic bytes"));
+        }
+        let version = reader.read_u8()?;
+        if version != PATCH_FORMAT_VERSION {
+            return Err(Error::new(format!(
+                "Invalid patch: unsupported format version {}",
+                version
+            )));
+        }
+
+        let mut patch = Patch::new();
+        for _ in 0..reader.read_len()? {
+            let name = String::from_utf8(reader.read_bytes()?.to_vec())
+                .map_err(|_| Error::new("Invalid patch: column family name is not UTF-8"))?;
+            let mut changes = Changes::new();
+            for _ in 0..reader.read_len()? {
+                let key = reader.read_bytes()?.to_vec();
+                let change = match reader.read_u8()? {
+                    0 => Change::Delete,
+                    1 => Change::Put(reader.read_bytes()?.to_vec()),
+                    tag => {
+                        return Err(Error::new(format!(
+                            "Invalid patch: unknown change tag {}",
+                            tag
+                        )))
+                    }
+                };
+                changes.data.insert(key, change);
+            }
+            if patch.changes.contains_key(&name) {
+                return Err(Error::new(format!(
+                    "Invalid patch: duplicate column family `{}`",
+                    name
+                )));
+            }
+            patch.insert_changes(name, changes);
+        }
+
+        if !reader.bytes.is_empty() {
+            return Err(Error::new("Invalid patch: unexpected trailing data"));
+        }
+        Ok(patch)
+    }
+}
+
+fn write_len(buf: &mut Vec<u8>, len: usize) {
+    assert!(len <= u32::max_value() as usize, "Patch is too large");
+    let mut bytes = [0; 4];
+    LittleEndian::write_u32(&mut bytes, len as u32);
+    buf.extend_from_slice(&bytes);
+}
+
+fn write_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    write_len(buf, bytes.len());
+    buf.extend_from_slice(bytes);
+}
+
+/// A reader of the binary representation of a `Patch`.
+struct PatchReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> PatchReader<'a> {
+    fn read_slice(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(Error::new("Invalid patch: unexpected end of data"));
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read_slice(1)?[0])
+    }
+
+    fn read_len(&mut self) -> Result<usize> {
+        Ok(LittleEndian::read_u32(self.read_slice(4)?) as usize)
+    }
+
+    fn read_bytes(&mut self) -> Result<&'a [u8]> {
+        let len = self.read_len()?;
+        self.read_slice(len)
+    }
+}
+
+/// JSON representation of a patch: column family names mapped to hex-encoded keys,
+/// which are in turn mapped to hex-encoded values or `null` for deleted entries.
+type PatchRepr = BTreeMap<String, BTreeMap<String, Option<String>>>;
+
+impl Serialize for Patch {
+    fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let repr: PatchRepr = self.changes
+            .iter()
+            .map(|(name, changes)| {
+                let changes = changes
+                    .data
+                    .iter()
+                    .map(|(key, change)| {
+                        let value = match *change {
+                            Change::Put(ref value) => Some(encode_hex(value)),
+                            Change::Delete => None,
+                        };
+                        (encode_hex(key), value)
+                    })
+                    .collect();
+                (name.clone(), changes)
+            })
+            .collect();
+        repr.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Patch {
+    fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let repr = PatchRepr::deserialize(deserializer)?;
+        let mut patch = Patch::new();
+        for (name, entries) in repr {
+            let mut changes = Changes::new();
+            for (key, value) in entries {
+                let key = decode_hex(&key).map_err(D::Error::custom)?;
+                let change = match value {
+                    Some(value) => Change::Put(decode_hex(&value).map_err(D::Error::custom)?),
+                    None => Change::Delete,
+                };
+                changes.data.insert(key, change);
+            }
+            patch.insert_changes(name, changes);
+        }
+        Ok(patch)
+    }
 }
 
 /// Iterator over the `Patch` data.
@@ -171,7 +361,9 @@ pub enum Change {
 ///
 /// `Fork` also supports checkpoints ([`checkpoint`], [`commit`] and
 /// [`rollback`] methods), which allows to rollback some of the latest changes (e.g., after
-/// a runtime error).
+/// a runtime error). Inside a checkpoint, a stack of named savepoints may be created
+/// with [`savepoint`]; each savepoint can be rolled back ([`rollback_to_savepoint`]) or
+/// released ([`release_savepoint`]) independently from the outer checkpoint.
 ///
 /// `Fork` implements the [`Snapshot`] trait and provides methods for both reading and
 /// writing data. Thus, `&mut Fork` is used as a storage view for creating
@@ -190,18 +382,37 @@ pub enum Change {
 /// [`checkpoint`]: #method.checkpoint
 /// [`commit`]: #method.commit
 /// [`rollback`]: #method.rollback
+/// [`savepoint`]: #method.savepoint
+/// [`rollback_to_savepoint`]: #method.rollback_to_savepoint
+/// [`release_savepoint`]: #method.release_savepoint
 
 // FIXME: make &mut Fork "unwind safe". (ECR-176)
 pub struct Fork {
     snapshot: Box<Snapshot>,
     patch: Patch,
     changelog: Vec<(String, Vec<u8>, Option<Change>)>,
-    logged: bool,
+    savepoints: Vec<Savepoint>,
+}
+
+/// A checkpoint or a named savepoint in a `Fork`.
+#[derive(Debug)]
+struct Savepoint {
+    /// Name of the savepoint; `None` for the checkpoint.
+    name: Option<String>,
+    /// Length of the changelog at the moment the savepoint was created.
+    changelog_len: usize,
 }
 
 struct ForkIter<'a> {
     snapshot: Iter<'a>,
-    changes: Option<Peekable<Range<'a, Vec<u8>, Change>>>,
+    changes: Option<Peekable<Box<StdIterator<Item = (&'a Vec<u8>, &'a Change)> + 'a>>>,
+    reverse: bool,
+}
+
+/// An iterator over entries collected into a vector.
+struct EntriesIter {
+    entries: Vec<(Vec<u8>, Vec<u8>)>,
+    index: usize,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -247,12 +458,7 @@ pub trait Database: Send + Sync + 'static {
 
     /// Creates a new fork of the database from its current state.
     fn fork(&self) -> Fork {
-        Fork {
-            snapshot: self.snapshot(),
-            patch: Patch::new(),
-            changelog: Vec::new(),
-            logged: false,
-        }
+        Fork::from(self.snapshot())
     }
 
     /// Atomically applies a sequence of patch changes to the database.
@@ -278,17 +484,35 @@ pub trait Database: Send + Sync + 'static {
     /// will be returned. In case of an error the method guarantees no changes were applied to
     /// the database.
     fn merge_sync(&self, patch: Patch) -> Result<()>;
+
+    /// Creates a consistent copy of the database at the specified path. The copy
+    /// can be later opened as a separate database.
+    ///
+    /// The copy reflects the state of the database at the moment of the call, so this method
+    /// may be called while other threads keep merging changes into the database.
+    ///
+    /// # Errors
+    ///
+    /// Default implementation returns an error, which means that the database
+    /// does not support checkpoints.
+    fn create_checkpoint(&self, path: &Path) -> Result<()> {
+        Err(Error::new(format!(
+            "Unable to create a checkpoint at {}: not supported by the database",
+            path.display()
+        )))
+    }
 }
 
 /// A read-only snapshot of a storage backend.
 ///
 /// A `Snapshot` instance is an immutable representation of a certain storage state.
 /// It provides read isolation, so consistency is guaranteed even if the data in
-/// the database changes between reads.
+/// the database changes between reads. A snapshot can be shared between threads,
+/// for example, to execute the transactions of a block in parallel on the same state.
 ///
 /// **Note.** Unless stated otherwise, "key" in the method descriptions below refers
 /// to a full key (a string column family name + key as an array of bytes within the family).
-pub trait Snapshot: 'static {
+pub trait Snapshot: Send + Sync + 'static {
     /// Returns a value corresponding to the specified key as a raw vector of bytes,
     /// or `None` if it does not exist.
     fn get(&self, name: &str, key: &[u8]) -> Option<Vec<u8>>;
@@ -303,6 +527,25 @@ pub trait Snapshot: 'static {
     /// Returns an iterator over the entries of the snapshot in ascending order starting from
     /// the specified key. The iterator element type is `(&[u8], &[u8])`.
     fn iter<'a>(&'a self, name: &str, from: &[u8]) -> Iter<'a>;
+
+    /// Returns an iterator over the entries of the snapshot in descending order starting from
+    /// the greatest key within the specified upper bound. The iterator element type
+    /// is `(&[u8], &[u8])`.
+    ///
+    /// Default implementation collects the entries within the bound using [`iter`](#tymethod.iter),
+    /// so database backends are expected to provide a more efficient implementation.
+    fn iter_rev<'a>(&'a self, name: &str, to: Bound<&[u8]>) -> Iter<'a> {
+        let mut entries = Vec::new();
+        let mut iter = self.iter(name, &[]);
+        while let Some((key, value)) = iter.next() {
+            if !is_within_upper_bound(key, to) {
+                break;
+            }
+            entries.push((key.to_vec(), value.to_vec()));
+        }
+        entries.reverse();
+        Box::new(EntriesIter { entries, index: 0 })
+    }
 }
 
 /// A trait that defines streaming iterator over storage view entries.
@@ -342,13 +585,32 @@ impl Snapshot for Fork {
     fn iter<'a>(&'a self, name: &str, from: &[u8]) -> Iter<'a> {
         let range = (Included(from), Unbounded);
         let changes = match self.patch.changes(name) {
-            Some(changes) => Some(changes.data.range::<[u8], _>(range).peekable()),
+            Some(changes) => Some(changes.data.range::<[u8], _>(range)),
             None => None,
         };
 
         Box::new(ForkIter {
             snapshot: self.snapshot.iter(name, from),
-            changes,
+            changes: changes.map(|changes| {
+                (Box::new(changes) as Box<StdIterator<Item = _> + 'a>).peekable()
+            }),
+            reverse: false,
+        })
+    }
+
+    fn iter_rev<'a>(&'a self, name: &str, to: Bound<&[u8]>) -> Iter<'a> {
+        let range = (Unbounded, to);
+        let changes = match self.patch.changes(name) {
+            Some(changes) => Some(changes.data.range::<[u8], _>(range).rev()),
+            None => None,
+        };
+
+        Box::new(ForkIter {
+            snapshot: self.snapshot.iter_rev(name, to),
+            changes: changes.map(|changes| {
+                (Box::new(changes) as Box<StdIterator<Item = _> + 'a>).peekable()
+            }),
+            reverse: true,
         })
     }
 }
@@ -358,39 +620,128 @@ impl Fork {
     ///
     /// # Panics
     ///
-    /// Panics if another checkpoint was created before and has not been committed or rolled back.
+    /// Panics if another checkpoint or a savepoint was created before and has not been
+    /// finalized yet.
     pub fn checkpoint(&mut self) {
-        if self.logged {
+        if !self.savepoints.is_empty() {
             panic!("call checkpoint before rollback or commit");
         }
-        self.logged = true;
+        self.savepoints.push(Savepoint {
+            name: None,
+            changelog_len: 0,
+        });
     }
 
-    /// Finalizes all changes after the latest checkpoint.
+    /// Finalizes all changes after the latest checkpoint. Savepoints created within
+    /// the checkpoint and not released yet are discarded.
     ///
     /// # Panics
     ///
     /// Panics if there is no active checkpoint, or the latest checkpoint
     /// is already committed or rolled back.
     pub fn commit(&mut self) {
-        if !self.logged {
+        if !self.in_checkpoint() {
             panic!("call commit before checkpoint");
         }
         self.changelog.clear();
-        self.logged = false;
+        self.savepoints.clear();
     }
 
-    /// Rolls back all changes after the latest checkpoint.
+    /// Rolls back all changes after the latest checkpoint, including the changes
+    /// made within savepoints.
     ///
     /// # Panics
     ///
     /// Panics if there is no active checkpoint, or the latest checkpoint
     /// is already committed or rolled back.
     pub fn rollback(&mut self) {
-        if !self.logged {
+        if !self.in_checkpoint() {
             panic!("call rollback before checkpoint");
         }
-        for (name, k, c) in self.changelog.drain(..).rev() {
+        self.undo_changes(0);
+        self.savepoints.clear();
+    }
+
+    /// Creates a new savepoint with the given name.
+    ///
+    /// Savepoints form a stack: a savepoint may be created while other savepoints
+    /// (or a checkpoint) are active, and finalizing a savepoint also finalizes all
+    /// savepoints created after it. If several active savepoints have the same name,
+    /// the latest one is used.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{Database, MemoryDB};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    ///
+    /// fork.checkpoint();
+    /// fork.put("index", vec![1], vec![1]);
+    /// fork.savepoint("sub-operation");
+    /// fork.put("index", vec![2], vec![2]);
+    /// fork.rollback_to_savepoint("sub-operation");
+    /// assert!(fork.contains("index", &[1]));
+    /// assert!(!fork.contains("index", &[2]));
+    ///
+    /// fork.rollback();
+    /// assert!(!fork.contains("index", &[1]));
+    /// ```
+    pub fn savepoint(&mut self, name: &str) {
+        self.savepoints.push(Savepoint {
+            name: Some(name.to_owned()),
+            changelog_len: self.changelog.len(),
+        });
+    }
+
+    /// Finalizes the savepoint with the given name, keeping all changes made after it.
+    /// The savepoints created after the released one are released as well.
+    ///
+    /// The changes are still rolled back if an outer savepoint or checkpoint is rolled back.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no active savepoint with the given name.
+    pub fn release_savepoint(&mut self, name: &str) {
+        let index = self.savepoint_index(name);
+        self.savepoints.truncate(index);
+        if self.savepoints.is_empty() {
+            self.changelog.clear();
+        }
+    }
+
+    /// Rolls back all changes made after the savepoint with the given name
+    /// and finalizes the savepoint, along with all savepoints created after it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no active savepoint with the given name.
+    pub fn rollback_to_savepoint(&mut self, name: &str) {
+        let index = self.savepoint_index(name);
+        let changelog_len = self.savepoints[index].changelog_len;
+        self.undo_changes(changelog_len);
+        self.savepoints.truncate(index);
+    }
+
+    /// Returns `true` if the fork has an active checkpoint.
+    fn in_checkpoint(&self) -> bool {
+        self.savepoints
+            .first()
+            .map_or(false, |savepoint| savepoint.name.is_none())
+    }
+
+    /// Returns the position of the latest active savepoint with the given name.
+    fn savepoint_index(&self, name: &str) -> usize {
+        self.savepoints
+            .iter()
+            .rposition(|savepoint| savepoint.name.as_ref().map(String::as_str) == Some(name))
+            .unwrap_or_else(|| panic!("no active savepoint named `{}`", name))
+    }
+
+    /// Reverts the changes recorded in the changelog after the given position.
+    fn undo_changes(&mut self, changelog_len: usize) {
+        for (name, k, c) in self.changelog.drain(changelog_len..).rev() {
             if let Some(changes) = self.patch.changes_mut(&name) {
                 match c {
                     Some(change) => changes.data.insert(k, change),
@@ -398,15 +749,16 @@ impl Fork {
                 };
             }
         }
-        self.logged = false;
     }
 
     /// Inserts a key-value pair into the fork.
     pub fn put(&mut self, name: &str, key: Vec<u8>, value: Vec<u8>) {
+        metrics::record_write(name, key.len() + value.len());
+        metering::record_write(key.len() + value.len());
         let changes = self.patch
             .changes_entry(name.to_string())
             .or_insert_with(Changes::new);
-        if self.logged {
+        if !self.savepoints.is_empty() {
             self.changelog.push((
                 name.to_string(),
                 key.clone(),
@@ -419,10 +771,12 @@ impl Fork {
 
     /// Removes the key from the fork.
     pub fn remove(&mut self, name: &str, key: Vec<u8>) {
+        metrics::record_deletes(name, 1);
+        metering::record_write(key.len());
         let changes = self.patch
             .changes_entry(name.to_string())
             .or_insert_with(Changes::new);
-        if self.logged {
+        if !self.savepoints.is_empty() {
             self.changelog.push((
                 name.to_string(),
                 key.clone(),
@@ -440,28 +794,39 @@ impl Fork {
             .changes_entry(name.to_string())
             .or_insert_with(Changes::new);
         // Remove changes
-        if let Some(prefix) = prefix {
-            let keys = changes
+        let keys = if let Some(prefix) = prefix {
+            changes
                 .data
                 .range::<Vec<u8>, _>((Included(prefix), Unbounded))
                 .map(|(k, _)| k.to_vec())
                 .take_while(|k| k.starts_with(prefix))
-                .collect::<Vec<_>>();
-            for k in keys {
-                changes.data.remove(&k);
-            }
+                .collect::<Vec<_>>()
         } else {
-            changes.data.clear();
+            changes.data.keys().cloned().collect::<Vec<_>>()
+        };
+        for k in keys {
+            metering::record_write(k.len());
+            let change = changes.data.remove(&k);
+            if !self.savepoints.is_empty() {
+                self.changelog.push((name.to_string(), k, change));
+            }
         }
         // Remove from storage
-        let mut iter = self.snapshot
-            .iter(name, prefix.map_or(&[], |k| k.as_slice()));
+        let prefix = prefix.map_or(&[][..], |k| k.as_slice());
+        let mut iter = self.snapshot.iter(name, prefix);
+        let mut deleted = 0;
         while let Some((k, ..)) = iter.next() {
+            if !k.starts_with(prefix) {
+                break;
+            }
             let change = changes.data.insert(k.to_vec(), Change::Delete);
-            if self.logged {
+            if !self.savepoints.is_empty() {
                 self.changelog.push((name.to_string(), k.to_vec(), change));
             }
+            metering::record_write(k.len());
+            deleted += 1;
         }
+        metrics::record_deletes(name, deleted);
     }
 
     /// Converts the fork into `Patch`.
@@ -481,9 +846,9 @@ impl Fork {
     ///
     /// # Panics
     ///
-    /// Panics if checkpoint was created before and it was not committed or rolled back yet.
+    /// Panics if a checkpoint or a savepoint was created before and it was not finalized yet.
     pub fn merge(&mut self, patch: Patch) {
-        if self.logged {
+        if !self.savepoints.is_empty() {
             panic!("call merge before commit or rollback");
         }
 
@@ -519,16 +884,25 @@ impl ::std::fmt::Debug for Fork {
 
 impl<'a> ForkIter<'a> {
     fn step(&mut self) -> NextIterValue {
+        let reverse = self.reverse;
+        let cmp = |change_key: &[u8], key: &[u8]| {
+            let ordering = change_key.cmp(key);
+            if reverse {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        };
         if let Some(ref mut changes) = self.changes {
             match changes.peek() {
                 Some(&(k, change)) => match self.snapshot.peek() {
                     Some((key, ..)) => match *change {
-                        Change::Put(..) => match k[..].cmp(key) {
+                        Change::Put(..) => match cmp(k, key) {
                             Equal => Replaced,
                             Less => Inserted,
                             Greater => Stored,
                         },
-                        Change::Delete => match k[..].cmp(key) {
+                        Change::Delete => match cmp(k, key) {
                             Equal => Deleted,
                             Less => MissDeleted,
                             Greater => Stored,
@@ -621,6 +995,46 @@ impl<'a> Iterator for ForkIter<'a> {
     }
 }
 
+impl Iterator for EntriesIter {
+    fn next(&mut self) -> Option<(&[u8], &[u8])> {
+        if self.index < self.entries.len() {
+            self.index += 1;
+            self.entries
+                .get(self.index - 1)
+                .map(|&(ref k, ref v)| (k.as_slice(), v.as_slice()))
+        } else {
+            None
+        }
+    }
+
+    fn peek(&mut self) -> Option<(&[u8], &[u8])> {
+        self.entries
+            .get(self.index)
+            .map(|&(ref k, ref v)| (k.as_slice(), v.as_slice()))
+    }
+}
+
+/// Returns `true` if the key does not exceed the specified upper bound.
+pub(crate) fn is_within_upper_bound(key: &[u8], bound: Bound<&[u8]>) -> bool {
+    match bound {
+        Included(upper) => key <= upper,
+        Excluded(upper) => key < upper,
+        Unbounded => true,
+    }
+}
+
+impl From<Box<Snapshot>> for Fork {
+    /// Creates a fork with no changes on top of the specified snapshot.
+    fn from(snapshot: Box<Snapshot>) -> Self {
+        Fork {
+            snapshot,
+            patch: Patch::new(),
+            changelog: Vec::new(),
+            savepoints: Vec::new(),
+        }
+    }
+}
+
 impl<T: Database> From<T> for Box<Database> {
     fn from(db: T) -> Self {
         Box::new(db) as Box<Database>
@@ -0,0 +1,269 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An implementation of operations on whole index families.
+
+use std::marker::PhantomData;
+
+use super::{base_index::{BaseIndex, BaseIndexIter},
+            indexes_metadata::{self, INDEX_FAMILY_MEMBERS_TABLE_NAME},
+            Fork,
+            Snapshot,
+            StorageKey};
+
+/// A view of an index family, that is, of all indexes created by the `new_in_family`
+/// constructors with the same family name.
+///
+/// `IndexFamily` allows to enumerate the keys of the indexes in the family, count them
+/// and remove all of them at once. `K` is the type of the keys the indexes are created with,
+/// for example, `PublicKey` for a family of per-wallet histories.
+///
+/// The keys are tracked in the storage metadata: an index is registered in its family
+/// when it is modified for the first time and unregistered when it is cleared. Indexes
+/// created before the keys were tracked are not enumerated, although [`clear`] removes
+/// their contents as well.
+///
+/// [`clear`]: #method.clear
+#[derive(Debug)]
+pub struct IndexFamily<T, K> {
+    name: String,
+    members: BaseIndex<T>,
+    _k: PhantomData<K>,
+}
+
+/// An iterator over the keys of the indexes in an `IndexFamily`.
+///
+/// This struct is created by the [`keys`] method on [`IndexFamily`].
+/// See its documentation for more.
+///
+/// [`keys`]: struct.IndexFamily.html#method.keys
+/// [`IndexFamily`]: struct.IndexFamily.html
+#[derive(Debug)]
+pub struct IndexFamilyKeys<'a, K> {
+    base_iter: BaseIndexIter<'a, K, ()>,
+}
+
+impl<T, K> IndexFamily<T, K>
+where
+    T: AsRef<Snapshot>,
+    K: StorageKey,
+{
+    /// Creates a new view of the index family with the given name.
+    ///
+    /// Storage view can be specified as [`&Snapshot`] or [`&mut Fork`]. In the first case only
+    /// immutable methods are available. In the second case both immutable and mutable methods are
+    /// available.
+    ///
+    /// # Panics
+    ///
+    /// If an ordinary index with the given name exists.
+    ///
+    /// [`&Snapshot`]: ../trait.Snapshot.html
+    /// [`&mut Fork`]: ../struct.Fork.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{Database, IndexFamily, ListIndex, MemoryDB};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// ListIndex::new_in_family("history", &1_u64, &mut fork).push(10_u8);
+    /// ListIndex::new_in_family("history", &2_u64, &mut fork).push(20_u8);
+    ///
+    /// let family: IndexFamily<_, u64> = IndexFamily::new("history", &fork);
+    /// assert_eq!(family.keys().collect::<Vec<_>>(), vec![1, 2]);
+    /// ```
+    pub fn new<S: AsRef<str>>(family_name: S, view: T) -> Self {
+        indexes_metadata::assert_is_family(family_name.as_ref(), view.as_ref());
+        IndexFamily {
+            name: family_name.as_ref().to_owned(),
+            members: BaseIndex::family_members(family_name, view),
+            _k: PhantomData,
+        }
+    }
+
+    /// Returns `true` if the family contains an index with the given key.
+    pub fn contains(&self, key: &K) -> bool {
+        self.members.contains(key)
+    }
+
+    /// Returns an iterator over the keys of the indexes in the family in ascending order.
+    pub fn keys(&self) -> IndexFamilyKeys<K> {
+        IndexFamilyKeys {
+            base_iter: self.members.iter(&()),
+        }
+    }
+
+    /// Returns the number of the indexes in the family.
+    ///
+    /// The indexes are counted by iterating over their keys, so the method takes time
+    /// linear in the size of the family.
+    pub fn len(&self) -> u64 {
+        self.keys().count() as u64
+    }
+
+    /// Returns `true` if the family contains no indexes.
+    pub fn is_empty(&self) -> bool {
+        self.keys().next().is_none()
+    }
+}
+
+impl<'a, K> IndexFamily<&'a mut Fork, K>
+where
+    K: StorageKey,
+{
+    /// Removes all indexes in the family.
+    ///
+    /// Unlike clearing the indexes one by one, the family is cleared with a single scan
+    /// of its column family, regardless of the number of the indexes in it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{Database, IndexFamily, ListIndex, MemoryDB};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// ListIndex::new_in_family("history", &1_u64, &mut fork).push(10_u8);
+    /// ListIndex::new_in_family("history", &2_u64, &mut fork).push(20_u8);
+    ///
+    /// IndexFamily::<_, u64>::new("history", &mut fork).clear();
+    /// let list: ListIndex<_, u8> = ListIndex::new_in_family("history", &1_u64, &fork);
+    /// assert!(list.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        let prefix = {
+            let mut prefix = self.name.as_bytes().to_vec();
+            prefix.push(0);
+            prefix
+        };
+        let fork = self.members.fork();
+        fork.remove_by_prefix(&self.name, None);
+        fork.remove_by_prefix(INDEX_FAMILY_MEMBERS_TABLE_NAME, Some(&prefix));
+    }
+}
+
+impl<'a, K> Iterator for IndexFamilyKeys<'a, K>
+where
+    K: StorageKey,
+{
+    type Item = K::Owned;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.base_iter.next().map(|(k, ..)| k)
+    }
+}
+
+impl<'a, K> DoubleEndedIterator for IndexFamilyKeys<'a, K>
+where
+    K: StorageKey,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.base_iter.next_back().map(|(k, ..)| k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Database, ListIndex, MapIndex, MemoryDB, ProofListIndex};
+    use super::*;
+
+    #[test]
+    fn family_members_are_tracked() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        {
+            let family: IndexFamily<_, u32> = IndexFamily::new("family", &fork);
+            assert!(family.is_empty());
+        }
+
+        for key in &[3_u32, 1, 2] {
+            let mut list = ListIndex::new_in_family("family", key, &mut fork);
+            list.push(u64::from(*key));
+        }
+        // Indexes which have been only read are not registered.
+        let _: ListIndex<_, u64> = ListIndex::new_in_family("family", &4_u32, &fork);
+        // Indexes of families with a common prefix do not interfere.
+        ProofListIndex::new_in_family("family_2", &5_u32, &mut fork).push(5_u64);
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        let family: IndexFamily<_, u32> = IndexFamily::new("family", &snapshot);
+        assert_eq!(family.keys().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(family.keys().rev().collect::<Vec<_>>(), vec![3, 2, 1]);
+        assert_eq!(family.len(), 3);
+        assert!(family.contains(&2));
+        assert!(!family.contains(&4));
+        assert!(!family.contains(&5));
+    }
+
+    #[test]
+    fn cleared_members_are_unregistered() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        for key in 0_u8..3 {
+            MapIndex::new_in_family("family", &key, &mut fork).put(&key, 1_u8);
+        }
+
+        {
+            let mut map: MapIndex<_, u8, u8> = MapIndex::new_in_family("family", &1_u8, &mut fork);
+            map.clear();
+        }
+        assert!(!IndexFamily::<_, u8>::new("family", &fork).contains(&1));
+
+        {
+            let mut map = MapIndex::new_in_family("family", &1_u8, &mut fork);
+            map.clear();
+            // The index is registered again once it is modified after clearing.
+            map.put(&1_u8, 2_u8);
+        }
+        let family: IndexFamily<_, u8> = IndexFamily::new("family", &fork);
+        assert_eq!(family.keys().collect::<Vec<_>>(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn clear_family() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        for key in 0_u8..3 {
+            ListIndex::new_in_family("family", &key, &mut fork).push(key);
+            ListIndex::new_in_family("other_family", &key, &mut fork).push(key);
+        }
+        db.merge(fork.into_patch()).unwrap();
+
+        let mut fork = db.fork();
+        IndexFamily::<_, u8>::new("family", &mut fork).clear();
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        assert!(IndexFamily::<_, u8>::new("family", &snapshot).is_empty());
+        assert_eq!(IndexFamily::<_, u8>::new("other_family", &snapshot).len(), 3);
+        for key in 0_u8..3 {
+            let list: ListIndex<_, u8> = ListIndex::new_in_family("family", &key, &snapshot);
+            assert!(list.is_empty());
+            let list: ListIndex<_, u8> = ListIndex::new_in_family("other_family", &key, &snapshot);
+            assert_eq!(list.len(), 1);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Attempt to access an ordinary index 'list' as index family")]
+    fn ordinary_index_as_family() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        ListIndex::new("list", &mut fork).push(1_u8);
+        let _: IndexFamily<_, u8> = IndexFamily::new("list", &fork);
+    }
+}
@@ -25,6 +25,7 @@ use encoding::{serialize::{json, WriteBufferWrapper},
 use storage::{base_index::BaseIndex, Fork, Snapshot, StorageValue};
 
 pub const INDEXES_METADATA_TABLE_NAME: &str = "__INDEXES_METADATA__";
+pub const INDEX_FAMILY_MEMBERS_TABLE_NAME: &str = "__INDEX_FAMILY_MEMBERS__";
 
 encoding_struct! {
     struct IndexMetadata {
@@ -33,17 +34,61 @@ encoding_struct! {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// Type of an index, as recorded in the metadata of the storage.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum IndexType {
+    /// [`Entry`](struct.Entry.html).
     Entry,
+    /// [`KeySetIndex`](key_set_index/struct.KeySetIndex.html).
     KeySet,
+    /// [`ListIndex`](list_index/struct.ListIndex.html).
     List,
+    /// [`SparseListIndex`](sparse_list_index/struct.SparseListIndex.html).
     SparseList,
+    /// [`MapIndex`](map_index/struct.MapIndex.html).
     Map,
+    /// [`ProofListIndex`](proof_list_index/struct.ProofListIndex.html).
     ProofList,
+    /// [`ProofMapIndex`](proof_map_index/struct.ProofMapIndex.html).
     ProofMap,
+    /// [`ValueSetIndex`](value_set_index/struct.ValueSetIndex.html).
     ValueSet,
+    /// [`ProofKeySetIndex`](proof_key_set_index/struct.ProofKeySetIndex.html).
+    ProofKeySet,
+    /// [`ProofEntry`](proof_entry/struct.ProofEntry.html).
+    ProofEntry,
+    /// [`QueueIndex`](queue_index/struct.QueueIndex.html).
+    Queue,
+}
+
+/// Information about an index stored in the database.
+///
+/// The information is returned by the [`list_indexes`] function.
+///
+/// [`list_indexes`]: fn.list_indexes.html
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexInfo {
+    /// Name of the index or of the index family.
+    pub name: String,
+    /// Type of the index.
+    pub index_type: IndexType,
+    /// `true` if the index is an index family.
+    pub is_family: bool,
+    /// Number of the stored entries, including the auxiliary ones, such as the length
+    /// of a list or the branches of a Merkle tree. For an index family, the entries of all
+    /// indexes of the family are counted.
+    ///
+    /// `None` if the sizes of the indexes were not requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entries: Option<u64>,
+    /// Total size of the keys and values of the stored entries in bytes. The space actually
+    /// occupied by the index in the database differs from it due to compression and overhead
+    /// of the storage backend.
+    ///
+    /// `None` if the sizes of the indexes were not requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
 }
 
 impl From<u8> for IndexType {
@@ -58,6 +103,9 @@ impl From<u8> for IndexType {
             5 => ProofList,
             6 => ProofMap,
             7 => ValueSet,
+            8 => ProofKeySet,
+            9 => ProofEntry,
+            10 => Queue,
             invalid => panic!(
                 "Unreachable pattern ({:?}) while constructing table type. \
                  Storage data is probably corrupted",
@@ -126,6 +174,108 @@ impl json::ExonumJson for IndexType {
     }
 }
 
+/// Returns information about all indexes in the storage view in the ascending order
+/// of their names.
+///
+/// The entry count and the size of each index are calculated only if `with_sizes`
+/// is `true`. The calculation iterates over all entries of every index, so it takes
+/// time proportional to the size of the database. Otherwise, only the index metadata
+/// is read.
+///
+/// # Examples
+///
+/// ```
+/// use exonum::storage::{list_indexes, Database, IndexType, ListIndex, MemoryDB};
+///
+/// let db = MemoryDB::new();
+/// let mut fork = db.fork();
+/// ListIndex::new("list", &mut fork).extend(vec![1_u8, 2, 3]);
+///
+/// let indexes = list_indexes(&fork, false);
+/// assert_eq!(indexes.len(), 1);
+/// assert_eq!(indexes[0].name, "list");
+/// assert_eq!(indexes[0].index_type, IndexType::List);
+/// assert_eq!(indexes[0].entries, None);
+///
+/// let indexes = list_indexes(&fork, true);
+/// // Three items and the length of the list.
+/// assert_eq!(indexes[0].entries, Some(4));
+/// ```
+pub fn list_indexes(view: &Snapshot, with_sizes: bool) -> Vec<IndexInfo> {
+    let metadata = BaseIndex::indexes_metadata(view);
+    let mut indexes = Vec::new();
+    for (name, metadata) in metadata.iter::<_, String, IndexMetadata>(&()) {
+        let (entries, size) = if with_sizes {
+            let (entries, size) = index_size(view, &name);
+            (Some(entries), Some(size))
+        } else {
+            (None, None)
+        };
+        indexes.push(IndexInfo {
+            name,
+            index_type: metadata.index_type(),
+            is_family: metadata.is_family(),
+            entries,
+            size,
+        });
+    }
+    indexes
+}
+
+/// Removes an ordinary index with the given name from the storage together with its
+/// metadata, so that an index of another type can be created with the same name.
+///
+/// The function is intended for [schema migrations], which change the type of an index
+/// while keeping its name. It does nothing if the index does not exist.
+///
+/// # Panics
+///
+/// Panics if the name refers to an index family or to an internal storage table.
+///
+/// # Examples
+///
+/// ```
+/// use exonum::storage::{remove_index, Database, Entry, MemoryDB, ProofEntry};
+///
+/// let db = MemoryDB::new();
+/// let mut fork = db.fork();
+/// Entry::new("value", &mut fork).set(1_u64);
+///
+/// let value: Option<u64> = Entry::new("value", &fork).get();
+/// remove_index("value", &mut fork);
+/// ProofEntry::new("value", &mut fork).set(value.unwrap());
+/// assert_eq!(ProofEntry::new("value", &fork).get(), Some(1_u64));
+/// ```
+///
+/// [schema migrations]: ../blockchain/struct.Migration.html
+pub fn remove_index(name: &str, view: &mut Fork) {
+    if name == INDEXES_METADATA_TABLE_NAME || name == INDEX_FAMILY_MEMBERS_TABLE_NAME {
+        panic!("Attempt to access an internal storage infrastructure");
+    }
+    {
+        let mut metadata = BaseIndex::indexes_metadata(&mut *view);
+        if let Some(value) = metadata.get::<_, IndexMetadata>(name) {
+            assert!(
+                !value.is_family(),
+                "Attempt to remove index family '{}' as an ordinary index",
+                name
+            );
+            metadata.remove(name);
+        }
+    }
+    view.remove_by_prefix(name, None);
+}
+
+fn index_size(view: &Snapshot, name: &str) -> (u64, u64) {
+    let mut iter = view.iter(name, &[]);
+    let (mut entries, mut size) = (0, 0);
+    while let Some((key, value)) = iter.next() {
+        entries += 1;
+        size += (key.len() + value.len()) as u64;
+    }
+    (entries, size)
+}
+
 pub fn assert_index_type(name: &str, index_type: IndexType, is_family: bool, view: &Snapshot) {
     let metadata = BaseIndex::indexes_metadata(view);
     if let Some(value) = metadata.get::<_, IndexMetadata>(name) {
@@ -156,8 +306,19 @@ pub fn assert_index_type(name: &str, index_type: IndexType, is_family: bool, vie
     }
 }
 
+pub fn assert_is_family(name: &str, view: &Snapshot) {
+    let metadata = BaseIndex::indexes_metadata(view);
+    if let Some(value) = metadata.get::<_, IndexMetadata>(name) {
+        assert!(
+            value.is_family(),
+            "Attempt to access an ordinary index '{}' as index family",
+            name
+        );
+    }
+}
+
 pub fn set_index_type(name: &str, index_type: IndexType, is_family: bool, view: &mut Fork) {
-    if name == INDEXES_METADATA_TABLE_NAME {
+    if name == INDEXES_METADATA_TABLE_NAME || name == INDEX_FAMILY_MEMBERS_TABLE_NAME {
         panic!("Attempt to access an internal storage infrastructure");
     }
     let mut metadata = BaseIndex::indexes_metadata(view);
@@ -166,20 +327,42 @@ pub fn set_index_type(name: &str, index_type: IndexType, is_family: bool, view:
     }
 }
 
+pub fn add_family_member(family_name: &str, index_id: &[u8], view: &mut Fork) {
+    let mut members = BaseIndex::family_members(family_name, view);
+    if !members.contains(index_id) {
+        members.put(&index_id.to_vec(), ());
+    }
+}
+
+pub fn remove_family_member(family_name: &str, index_id: &[u8], view: &mut Fork) {
+    BaseIndex::family_members(family_name, view).remove(index_id);
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{IndexMetadata, IndexType, INDEXES_METADATA_TABLE_NAME};
+    use super::{list_indexes, remove_index, IndexInfo, IndexMetadata, IndexType,
+                INDEXES_METADATA_TABLE_NAME};
     use crypto::{Hash, PublicKey};
-    use storage::{Database, MapIndex, MemoryDB, ProofMapIndex};
+    use storage::{Database, Entry, ListIndex, MapIndex, MemoryDB, ProofMapIndex};
 
     #[test]
     fn index_metadata_roundtrip() {
         use self::IndexType::*;
 
         let index_types = [
-            Entry, KeySet, List, SparseList, Map, ProofList, ProofMap, ValueSet,
+            Entry,
+            KeySet,
+            List,
+            SparseList,
+            Map,
+            ProofList,
+            ProofMap,
+            ValueSet,
+            ProofKeySet,
+            ProofEntry,
+            Queue,
         ];
-        let is_family = [true, true, false, false, true, false, true, false];
+        let is_family = [true, true, false, false, true, false, true, false, true, false, true];
         for (t, f) in index_types.iter().zip(&is_family) {
             let metadata = IndexMetadata::new(*t, *f);
             assert_eq!(metadata.index_type(), *t);
@@ -317,4 +500,75 @@ mod tests {
         let mut index = MapIndex::new("test_index", &mut fork);
         index.put(&Hash::zero(), 43);
     }
+
+    #[test]
+    fn remove_index_changes_type() {
+        let database = MemoryDB::new();
+        let mut fork = database.fork();
+        Entry::new("entry", &mut fork).set(1_u64);
+        ListIndex::new("list", &mut fork).push(2_u64);
+
+        remove_index("entry", &mut fork);
+        assert_eq!(Entry::<_, u64>::new("entry", &fork).get(), None);
+        MapIndex::new("entry", &mut fork).put(&1_u8, 3_u64);
+        assert_eq!(MapIndex::new("entry", &fork).get(&1_u8), Some(3_u64));
+
+        let indexes = list_indexes(&fork, false);
+        assert_eq!(indexes.len(), 2);
+        assert_eq!(indexes[0].name, "entry");
+        assert_eq!(indexes[0].index_type, IndexType::Map);
+        assert_eq!(indexes[1].name, "list");
+    }
+
+    #[test]
+    #[should_panic(expected = "Attempt to remove index family 'family'")]
+    fn remove_index_family() {
+        let database = MemoryDB::new();
+        let mut fork = database.fork();
+        ListIndex::new_in_family("family", &1_u8, &mut fork).push(2_u64);
+        remove_index("family", &mut fork);
+    }
+
+    #[test]
+    fn list_indexes_in_snapshot() {
+        let database = MemoryDB::new();
+        let mut fork = database.fork();
+        assert!(list_indexes(&fork, true).is_empty());
+
+        ListIndex::new("b.list", &mut fork).extend(vec![1_u32, 2]);
+        MapIndex::new_in_family("a.family", &1_u8, &mut fork).put(&1_u8, 2_u64);
+        MapIndex::new_in_family("a.family", &2_u8, &mut fork).put(&1_u8, 2_u64);
+        // Indexes which have been only read are not recorded.
+        let _entry: Entry<_, u8> = Entry::new("c.entry", &mut fork);
+        database.merge(fork.into_patch()).unwrap();
+
+        let indexes = list_indexes(&database.snapshot(), true);
+        assert_eq!(
+            indexes,
+            vec![
+                IndexInfo {
+                    name: "a.family".to_owned(),
+                    index_type: IndexType::Map,
+                    is_family: true,
+                    entries: Some(2),
+                    size: Some(2 * (2 + 8)),
+                },
+                IndexInfo {
+                    name: "b.list".to_owned(),
+                    index_type: IndexType::List,
+                    is_family: false,
+                    entries: Some(3),
+                    size: Some(2 * (8 + 4) + 8),
+                },
+            ]
+        );
+
+        let indexes = list_indexes(&database.snapshot(), false);
+        assert_eq!(indexes.len(), 2);
+        assert!(
+            indexes
+                .iter()
+                .all(|index| index.entries.is_none() && index.size.is_none())
+        );
+    }
 }
@@ -14,7 +14,7 @@
 
 //! An implementation of set for items that implement `StorageKey` trait.
 
-use std::{borrow::Borrow, marker::PhantomData};
+use std::{borrow::Borrow, marker::PhantomData, ops::RangeBounds};
 
 use super::{base_index::{BaseIndex, BaseIndexIter},
             indexes_metadata::IndexType,
@@ -178,6 +178,37 @@ where
             base_iter: self.base.iter_from(&(), from),
         }
     }
+
+    /// Returns an iterator over the set elements within the specified range, in ascending order.
+    /// The iterator element type is K.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, KeySetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = KeySetIndex::new(name, &mut fork);
+    /// for i in 0..10u8 {
+    ///     index.insert(i);
+    /// }
+    ///
+    /// let elements: Vec<_> = index.range(2..5).rev().collect();
+    /// assert_eq!(elements, vec![4, 3, 2]);
+    /// ```
+    pub fn range<Q, R>(&self, range: R) -> KeySetIndexIter<K>
+    where
+        K: Borrow<Q>,
+        Q: StorageKey + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        KeySetIndexIter {
+            base_iter: self.base
+                .range(&(), range.start_bound(), range.end_bound()),
+        }
+    }
 }
 
 impl<'a, K> KeySetIndex<&'a mut Fork, K>
@@ -282,6 +313,15 @@ where
     }
 }
 
+impl<'a, K> DoubleEndedIterator for KeySetIndexIter<'a, K>
+where
+    K: StorageKey,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.base_iter.next_back().map(|(k, ..)| k)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::{Database, MemoryDB};
@@ -14,9 +14,9 @@
 
 //! An implementation of array list of items.
 
-use std::{cell::Cell, marker::PhantomData};
+use std::{cell::Cell, marker::PhantomData, ops::RangeBounds};
 
-use super::{base_index::{BaseIndex, BaseIndexIter},
+use super::{base_index::{list_start_bound, BaseIndex, BaseIndexIter},
             indexes_metadata::IndexType,
             Fork,
             Snapshot,
@@ -253,6 +253,39 @@ where
             base_iter: self.base.iter_from(&(), &from),
         }
     }
+
+    /// Returns an iterator over the list items at the positions within the specified range.
+    /// The iterator element type is V.
+    ///
+    /// Like other iterators over the list, the returned iterator is double-ended, so the items
+    /// may be iterated in reverse order without scanning the entire range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ListIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ListIndex::new(name, &mut fork);
+    ///
+    /// index.extend([1, 2, 3, 4, 5].iter().cloned());
+    ///
+    /// let items: Vec<i32> = index.range(1..3).collect();
+    /// assert_eq!(items, vec![2, 3]);
+    /// let latest: Vec<i32> = index.iter().rev().take(2).collect();
+    /// assert_eq!(latest, vec![5, 4]);
+    /// ```
+    pub fn range<R: RangeBounds<u64>>(&self, range: R) -> ListIndexIter<V> {
+        ListIndexIter {
+            base_iter: self.base.range(
+                &(),
+                list_start_bound(range.start_bound()),
+                range.end_bound(),
+            ),
+        }
+    }
 }
 
 impl<'a, V> ListIndex<&'a mut Fork, V>
@@ -457,6 +490,15 @@ where
     }
 }
 
+impl<'a, V> DoubleEndedIterator for ListIndexIter<'a, V>
+where
+    V: StorageValue,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.base_iter.next_back().map(|(.., v)| v)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{Fork, ListIndex};
@@ -516,6 +558,22 @@ mod tests {
             list_index.iter_from(3).collect::<Vec<u8>>(),
             Vec::<u8>::new()
         );
+
+        assert_eq!(list_index.iter().rev().collect::<Vec<u8>>(), vec![3, 2, 1]);
+        assert_eq!(list_index.range(..).collect::<Vec<u8>>(), vec![1, 2, 3]);
+        assert_eq!(list_index.range(1..).rev().collect::<Vec<u8>>(), vec![3, 2]);
+        assert_eq!(list_index.range(..2).rev().collect::<Vec<u8>>(), vec![2, 1]);
+        assert_eq!(list_index.range(1..=1).collect::<Vec<u8>>(), vec![2]);
+        assert_eq!(
+            list_index.range(5..).rev().collect::<Vec<u8>>(),
+            Vec::<u8>::new()
+        );
+
+        let mut iter = list_index.iter();
+        assert_eq!(iter.next_back(), Some(3));
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next_back(), Some(2));
+        assert_eq!(iter.next(), None);
     }
 
     mod memorydb_tests {
@@ -570,6 +628,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "rocksdb")]
     mod rocksdb_tests {
         use std::path::Path;
         use storage::{Database, DbOptions, ListIndex, RocksDB};
@@ -0,0 +1,618 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An implementation of `LogDB` database.
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use std::{collections::{BTreeMap, HashMap, Bound::{self, Excluded, Included, Unbounded}},
+          fs::{self, File, OpenOptions},
+          io::{self, Read, Seek, SeekFrom, Write},
+          path::{Path, PathBuf},
+          sync::{Arc, Mutex, RwLock}};
+
+use crypto::{self, Hash, HASH_SIZE};
+use storage::{self, db::Change, Database, DbOptions, Iter, Iterator, Patch, Snapshot};
+
+impl From<io::Error> for storage::Error {
+    fn from(err: io::Error) -> storage::Error {
+        storage::Error::new(err.to_string())
+    }
+}
+
+/// Name of the log file within the database directory.
+const LOG_FILE_NAME: &str = "data.log";
+/// Name of the temporary file used while compacting the log.
+const COMPACTED_LOG_FILE_NAME: &str = "data.log.compact";
+/// Size of the record header: payload length followed by the payload hash.
+const RECORD_HEADER_SIZE: usize = 8 + HASH_SIZE;
+/// Maximum number of entries loaded into an iterator at once.
+const ITER_CHUNK_SIZE: usize = 256;
+/// Log size below which the log is never compacted.
+const MIN_COMPACTION_SIZE: usize = 1 << 20;
+
+/// A single change recorded in the log: the column family name, the key
+/// and the new value (`None` for removals).
+type LogEntry = (String, Vec<u8>, Option<Vec<u8>>);
+
+/// Versions of a value sorted by the database version in which they were written.
+/// `None` denotes a removed value.
+type Versions = Vec<(u64, Option<Vec<u8>>)>;
+
+/// Result of decoding a record of the log.
+enum Record {
+    /// A valid record: its entries and length.
+    Valid(Vec<LogEntry>, usize),
+    /// A record running past the end of the log.
+    Incomplete,
+    /// A record of the given length with a mismatched checksum or malformed contents.
+    Corrupted(usize),
+}
+
+/// Database implementation written in pure Rust that persists data into
+/// an append-only log.
+///
+/// Every merged patch is appended to the log as a single checksummed record, so
+/// a patch is either fully persisted or discarded when the database is reopened.
+/// Old versions of values are retained only while there are snapshots that can
+/// observe them. The log is compacted on opening once it becomes significantly larger
+/// than the live data.
+///
+/// `LogDB` is not an ordered on-disk store like `RocksDB` and is not used as the database
+/// of a node. It is intended for tests and tools working with small data sets when
+/// the native `RocksDB` library is not available or undesirable, for example, when Exonum
+/// is built without the `rocksdb` feature.
+///
+/// # Memory usage
+///
+/// **`LogDB` keeps the whole state of the database in memory.** The log on disk is
+/// used only for durability: it is replayed into memory every time the database
+/// is opened. Thus, the size of the blockchain is limited by the RAM of the node,
+/// and opening a large database takes time proportional to the size of the log.
+/// Superseded values are removed from the log only when it is compacted.
+pub struct LogDB {
+    inner: Arc<LogDBInner>,
+}
+
+struct LogDBInner {
+    path: PathBuf,
+    log: Mutex<File>,
+    store: RwLock<Store>,
+}
+
+/// In-memory multi-version state of a `LogDB`.
+#[derive(Debug, Default)]
+struct Store {
+    version: u64,
+    tables: HashMap<String, BTreeMap<Vec<u8>, Versions>>,
+    /// Versions observed by the alive snapshots along with the number of such snapshots.
+    snapshots: BTreeMap<u64, usize>,
+}
+
+/// A snapshot of a `LogDB`.
+pub struct LogDBSnapshot {
+    inner: Arc<LogDBInner>,
+    version: u64,
+}
+
+/// An iterator over the entries of a `LogDB`.
+struct LogDBIter<'a> {
+    snapshot: &'a LogDBSnapshot,
+    name: String,
+    /// Bound of the remaining entries: the lower one for the forward iteration
+    /// and the upper one for the reverse iteration.
+    bound: Bound<Vec<u8>>,
+    reverse: bool,
+    buffer: Vec<(Vec<u8>, Vec<u8>)>,
+    index: usize,
+    finished: bool,
+}
+
+impl LogDB {
+    /// Opens a database stored in the specified directory with the specified options.
+    ///
+    /// An incomplete or corrupted record at the end of the log, which may be left
+    /// after a crash, is discarded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a corrupted record is followed by other records. The log
+    /// is left intact in this case.
+    pub fn open<P: AsRef<Path>>(path: P, options: &DbOptions) -> storage::Result<LogDB> {
+        let path = path.as_ref().to_path_buf();
+        if !path.exists() {
+            if !options.create_if_missing {
+                return Err(storage::Error::new(format!(
+                    "Database does not exist at {}",
+                    path.display()
+                )));
+            }
+            fs::create_dir_all(&path)?;
+        }
+
+        let log_path = path.join(LOG_FILE_NAME);
+        let mut log = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&log_path)?;
+        let mut data = Vec::new();
+        log.read_to_end(&mut data)?;
+
+        let mut store = Store::default();
+        let mut offset = 0;
+        while offset < data.len() {
+            let len = match decode_record(&data[offset..]) {
+                Record::Valid(entries, len) => {
+                    store.apply(entries);
+                    offset += len;
+                    continue;
+                }
+                Record::Incomplete => data.len() - offset,
+                Record::Corrupted(len) => len,
+            };
+            // Only the last record may be damaged by a crash. A damaged record followed
+            // by other data means that the log is corrupted, and discarding the rest
+            // of the log would lose committed changes.
+            if offset + len < data.len() {
+                return Err(storage::Error::new(format!(
+                    "Corrupted record at offset {} of {}",
+                    offset,
+                    log_path.display()
+                )));
+            }
+            warn!(
+                "Discarding {} bytes of the incomplete record at the end of {}",
+                len,
+                log_path.display()
+            );
+            log.set_len(offset as u64)?;
+            break;
+        }
+
+        let live_size = store.live_size();
+        let db = LogDB {
+            inner: Arc::new(LogDBInner {
+                path,
+                log: Mutex::new(log),
+                store: RwLock::new(store),
+            }),
+        };
+        if offset > MIN_COMPACTION_SIZE && offset > 2 * live_size {
+            db.compact()?;
+        }
+        Ok(db)
+    }
+
+    /// Rewrites the log so that it contains only the current values.
+    pub fn compact(&self) -> storage::Result<()> {
+        let mut log = self.inner.log.lock().unwrap();
+        let log_path = self.inner.path.join(LOG_FILE_NAME);
+        let compacted_path = self.inner.path.join(COMPACTED_LOG_FILE_NAME);
+        {
+            let store = self.inner.store.read().unwrap();
+            store.write_to(&compacted_path)?;
+        }
+        fs::rename(&compacted_path, &log_path)?;
+        sync_dir(&self.inner.path)?;
+        *log = OpenOptions::new().read(true).append(true).open(&log_path)?;
+        Ok(())
+    }
+}
+
+/// Flushes the directory entries, so that a renamed file survives a crash.
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> io::Result<()> {
+    File::open(path)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+impl LogDBInner {
+    fn merge(&self, patch: Patch, sync: bool) -> storage::Result<()> {
+        storage::metrics::record_merge(&patch);
+        let entries = patch
+            .into_iter()
+            .flat_map(|(name, changes)| {
+                changes.into_iter().map(move |(key, change)| {
+                    let value = match change {
+                        Change::Put(value) => Some(value),
+                        Change::Delete => None,
+                    };
+                    (name.clone(), key, value)
+                })
+            })
+            .collect::<Vec<_>>();
+        let record = encode_record(&encode_entries(&entries));
+
+        // The log lock is held until the changes are applied, so the in-memory state
+        // is updated in the same order as the log.
+        let mut log = self.log.lock().unwrap();
+        let position = log.seek(SeekFrom::End(0))?;
+        let result = log.write_all(&record).and_then(|_| {
+            if sync {
+                log.sync_data()
+            } else {
+                Ok(())
+            }
+        });
+        if let Err(err) = result {
+            // Discard the partially written record.
+            log.set_len(position)?;
+            return Err(err.into());
+        }
+
+        self.store.write().unwrap().apply(entries);
+        Ok(())
+    }
+}
+
+impl Store {
+    /// Applies the changes as a new version of the state.
+    fn apply(&mut self, entries: Vec<LogEntry>) {
+        let version = self.version + 1;
+        let oldest_snapshot = self.snapshots.keys().next().cloned().unwrap_or(version);
+        for (name, key, value) in entries {
+            let table = self.tables.entry(name).or_insert_with(BTreeMap::new);
+            let is_empty = {
+                let versions = table.entry(key.clone()).or_insert_with(Vec::new);
+                versions.push((version, value));
+                prune_versions(versions, oldest_snapshot);
+                versions.is_empty()
+            };
+            if is_empty {
+                table.remove(&key);
+            }
+        }
+        self.version = version;
+    }
+
+    /// Returns the entries of the current version of the state.
+    fn current_entries<'a>(
+        &'a self,
+    ) -> Box<::std::iter::Iterator<Item = (&'a str, &'a [u8], &'a [u8])> + 'a> {
+        let version = self.version;
+        Box::new(self.tables.iter().flat_map(move |(name, table)| {
+            table.iter().filter_map(move |(key, versions)| {
+                visible_value(versions, version)
+                    .map(|value| (name.as_str(), key.as_slice(), value.as_slice()))
+            })
+        }))
+    }
+
+    /// Returns the approximate size of the current version of the state in the log.
+    fn live_size(&self) -> usize {
+        self.current_entries()
+            .map(|(name, key, value)| name.len() + key.len() + value.len() + 13)
+            .sum()
+    }
+
+    /// Writes the current version of the state as a new log at the specified path.
+    fn write_to(&self, path: &Path) -> storage::Result<()> {
+        let mut file = File::create(path)?;
+        let mut payload = Vec::new();
+        for (name, key, value) in self.current_entries() {
+            encode_entry(&mut payload, name, key, Some(value));
+            if payload.len() >= MIN_COMPACTION_SIZE {
+                file.write_all(&encode_record(&payload))?;
+                payload.clear();
+            }
+        }
+        if !payload.is_empty() {
+            file.write_all(&encode_record(&payload))?;
+        }
+        file.sync_all()?;
+        Ok(())
+    }
+}
+
+impl Database for LogDB {
+    fn snapshot(&self) -> Box<Snapshot> {
+        let version = {
+            let mut store = self.inner.store.write().unwrap();
+            let version = store.version;
+            *store.snapshots.entry(version).or_insert(0) += 1;
+            version
+        };
+        Box::new(LogDBSnapshot {
+            inner: Arc::clone(&self.inner),
+            version,
+        })
+    }
+
+    fn merge(&self, patch: Patch) -> storage::Result<()> {
+        self.inner.merge(patch, false)
+    }
+
+    fn merge_sync(&self, patch: Patch) -> storage::Result<()> {
+        self.inner.merge(patch, true)
+    }
+
+    fn create_checkpoint(&self, path: &Path) -> storage::Result<()> {
+        if path.exists() {
+            return Err(storage::Error::new(format!(
+                "Unable to create a checkpoint at {}: the path already exists",
+                path.display()
+            )));
+        }
+        let result = fs::create_dir_all(path)
+            .map_err(storage::Error::from)
+            .and_then(|()| {
+                self.inner
+                    .store
+                    .read()
+                    .unwrap()
+                    .write_to(&path.join(LOG_FILE_NAME))
+            });
+        if result.is_err() {
+            let _ = fs::remove_dir_all(path);
+        }
+        result
+    }
+}
+
+impl Snapshot for LogDBSnapshot {
+    fn get(&self, name: &str, key: &[u8]) -> Option<Vec<u8>> {
+        self.inner
+            .store
+            .read()
+            .unwrap()
+            .tables
+            .get(name)
+            .and_then(|table| table.get(key))
+            .and_then(|versions| visible_value(versions, self.version).cloned())
+    }
+
+    fn iter<'a>(&'a self, name: &str, from: &[u8]) -> Iter<'a> {
+        Box::new(LogDBIter {
+            snapshot: self,
+            name: name.to_owned(),
+            bound: Included(from.to_vec()),
+            reverse: false,
+            buffer: Vec::new(),
+            index: 0,
+            finished: false,
+        })
+    }
+
+    fn iter_rev<'a>(&'a self, name: &str, to: Bound<&[u8]>) -> Iter<'a> {
+        let bound = match to {
+            Included(key) => Included(key.to_vec()),
+            Excluded(key) => Excluded(key.to_vec()),
+            Unbounded => Unbounded,
+        };
+        Box::new(LogDBIter {
+            snapshot: self,
+            name: name.to_owned(),
+            bound,
+            reverse: true,
+            buffer: Vec::new(),
+            index: 0,
+            finished: false,
+        })
+    }
+}
+
+impl Drop for LogDBSnapshot {
+    fn drop(&mut self) {
+        if let Ok(mut store) = self.inner.store.write() {
+            let is_last = match store.snapshots.get_mut(&self.version) {
+                Some(count) => {
+                    *count -= 1;
+                    *count == 0
+                }
+                None => false,
+            };
+            if is_last {
+                store.snapshots.remove(&self.version);
+            }
+        }
+    }
+}
+
+impl<'a> LogDBIter<'a> {
+    /// Loads the next chunk of entries if the current one is exhausted.
+    fn fill_buffer(&mut self) {
+        if self.index < self.buffer.len() || self.finished {
+            return;
+        }
+
+        let snapshot = self.snapshot;
+        let version = snapshot.version;
+        let buffer = {
+            let store = snapshot.inner.store.read().unwrap();
+            match store.tables.get(&self.name) {
+                Some(table) => {
+                    let bound = match self.bound {
+                        Included(ref key) => Included(key.as_slice()),
+                        Excluded(ref key) => Excluded(key.as_slice()),
+                        Unbounded => Unbounded,
+                    };
+                    let visible = |(key, versions): (&Vec<u8>, &Versions)| {
+                        visible_value(versions, version).map(|value| (key.clone(), value.clone()))
+                    };
+                    if self.reverse {
+                        table
+                            .range::<[u8], _>((Unbounded, bound))
+                            .rev()
+                            .filter_map(visible)
+                            .take(ITER_CHUNK_SIZE)
+                            .collect::<Vec<_>>()
+                    } else {
+                        table
+                            .range::<[u8], _>((bound, Unbounded))
+                            .filter_map(visible)
+                            .take(ITER_CHUNK_SIZE)
+                            .collect::<Vec<_>>()
+                    }
+                }
+                None => Vec::new(),
+            }
+        };
+
+        self.finished = buffer.len() < ITER_CHUNK_SIZE;
+        if let Some(&(ref key, _)) = buffer.last() {
+            self.bound = Excluded(key.clone());
+        }
+        self.buffer = buffer;
+        self.index = 0;
+    }
+}
+
+impl<'a> Iterator for LogDBIter<'a> {
+    fn next(&mut self) -> Option<(&[u8], &[u8])> {
+        self.fill_buffer();
+        if self.index < self.buffer.len() {
+            self.index += 1;
+            self.buffer
+                .get(self.index - 1)
+                .map(|&(ref k, ref v)| (k.as_slice(), v.as_slice()))
+        } else {
+            None
+        }
+    }
+
+    fn peek(&mut self) -> Option<(&[u8], &[u8])> {
+        self.fill_buffer();
+        self.buffer
+            .get(self.index)
+            .map(|&(ref k, ref v)| (k.as_slice(), v.as_slice()))
+    }
+}
+
+impl From<LogDB> for Arc<Database> {
+    fn from(db: LogDB) -> Arc<Database> {
+        Arc::from(Box::new(db) as Box<Database>)
+    }
+}
+
+/// Returns the value visible at the specified version of the state.
+fn visible_value(versions: &Versions, version: u64) -> Option<&Vec<u8>> {
+    versions
+        .iter()
+        .rev()
+        .find(|&&(v, _)| v <= version)
+        .and_then(|&(_, ref value)| value.as_ref())
+}
+
+/// Removes the versions of a value that are not visible to any snapshot
+/// not older than `oldest_snapshot`.
+fn prune_versions(versions: &mut Versions, oldest_snapshot: u64) {
+    if let Some(pos) = versions.iter().rposition(|&(v, _)| v <= oldest_snapshot) {
+        versions.drain(..pos);
+    }
+    // A removal visible to all snapshots is indistinguishable from a missing value.
+    if versions.len() == 1 && versions[0].0 <= oldest_snapshot && versions[0].1.is_none() {
+        versions.clear();
+    }
+}
+
+fn encode_record(payload: &[u8]) -> Vec<u8> {
+    let mut record = vec![0; RECORD_HEADER_SIZE];
+    LittleEndian::write_u64(&mut record[..8], payload.len() as u64);
+    record[8..].copy_from_slice(crypto::hash(payload).as_ref());
+    record.extend_from_slice(payload);
+    record
+}
+
+/// Decodes a record at the beginning of the buffer.
+fn decode_record(buffer: &[u8]) -> Record {
+    if buffer.len() < RECORD_HEADER_SIZE {
+        return Record::Incomplete;
+    }
+    let len = LittleEndian::read_u64(&buffer[..8]);
+    if len > (buffer.len() - RECORD_HEADER_SIZE) as u64 {
+        return Record::Incomplete;
+    }
+    let end = RECORD_HEADER_SIZE + len as usize;
+    let payload = &buffer[RECORD_HEADER_SIZE..end];
+    if Hash::from_slice(&buffer[8..RECORD_HEADER_SIZE]) != Some(crypto::hash(payload)) {
+        return Record::Corrupted(end);
+    }
+    match decode_entries(payload) {
+        Some(entries) => Record::Valid(entries, end),
+        None => Record::Corrupted(end),
+    }
+}
+
+fn encode_entries(entries: &[LogEntry]) -> Vec<u8> {
+    let mut payload = Vec::new();
+    for &(ref name, ref key, ref value) in entries {
+        encode_entry(
+            &mut payload,
+            name,
+            key,
+            value.as_ref().map(|value| value.as_slice()),
+        );
+    }
+    payload
+}
+
+fn encode_entry(buffer: &mut Vec<u8>, name: &str, key: &[u8], value: Option<&[u8]>) {
+    write_bytes(buffer, name.as_bytes());
+    write_bytes(buffer, key);
+    match value {
+        Some(value) => {
+            buffer.push(1);
+            write_bytes(buffer, value);
+        }
+        None => buffer.push(0),
+    }
+}
+
+fn decode_entries(payload: &[u8]) -> Option<Vec<LogEntry>> {
+    let mut buffer = payload;
+    let mut entries = Vec::new();
+    while !buffer.is_empty() {
+        let name = String::from_utf8(read_bytes(&mut buffer)?.to_vec()).ok()?;
+        let key = read_bytes(&mut buffer)?.to_vec();
+        let value = match read_u8(&mut buffer)? {
+            0 => None,
+            1 => Some(read_bytes(&mut buffer)?.to_vec()),
+            _ => return None,
+        };
+        entries.push((name, key, value));
+    }
+    Some(entries)
+}
+
+fn write_bytes(buffer: &mut Vec<u8>, bytes: &[u8]) {
+    let mut len = [0; 4];
+    LittleEndian::write_u32(&mut len, bytes.len() as u32);
+    buffer.extend_from_slice(&len);
+    buffer.extend_from_slice(bytes);
+}
+
+fn read_bytes<'a>(buffer: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let data: &'a [u8] = *buffer;
+    if data.len() < 4 {
+        return None;
+    }
+    let len = LittleEndian::read_u32(&data[..4]) as usize;
+    if data.len() - 4 < len {
+        return None;
+    }
+    *buffer = &data[4 + len..];
+    Some(&data[4..4 + len])
+}
+
+fn read_u8(buffer: &mut &[u8]) -> Option<u8> {
+    let data = *buffer;
+    let (&byte, rest) = data.split_first()?;
+    *buffer = rest;
+    Some(byte)
+}
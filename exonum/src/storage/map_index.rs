@@ -14,7 +14,7 @@
 
 //! An implementation of key-value map.
 
-use std::{borrow::Borrow, marker::PhantomData};
+use std::{borrow::Borrow, marker::PhantomData, ops::RangeBounds};
 
 use super::{base_index::{BaseIndex, BaseIndexIter},
             indexes_metadata::IndexType,
@@ -39,11 +39,12 @@ pub struct MapIndex<T, K, V> {
 
 /// An iterator over the entries of a `MapIndex`.
 ///
-/// This struct is created by the [`iter`] or
-/// [`iter_from`] methods on [`MapIndex`]. See its documentation for more.
+/// This struct is created by the [`iter`], [`iter_from`] or
+/// [`range`] methods on [`MapIndex`]. See its documentation for more.
 ///
 /// [`iter`]: struct.MapIndex.html#method.iter
 /// [`iter_from`]: struct.MapIndex.html#method.iter_from
+/// [`range`]: struct.MapIndex.html#method.range
 /// [`MapIndex`]: struct.MapIndex.html
 #[derive(Debug)]
 pub struct MapIndexIter<'a, K, V> {
@@ -143,6 +144,10 @@ where
         }
     }
 
+    pub(crate) fn snapshot(&self) -> &Snapshot {
+        self.base.snapshot()
+    }
+
     /// Returns a value corresponding to the key.
     ///
     /// # Examples
@@ -338,6 +343,42 @@ where
             base_iter: self.base.iter_from(&(), from),
         }
     }
+
+    /// Returns an iterator over the entries of the map with the keys within the specified range,
+    /// in ascending order. The iterator element type is (K, V).
+    ///
+    /// Like other iterators over the map, the returned iterator is double-ended, so the entries
+    /// may be iterated in descending order without scanning the entire range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, MapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = MapIndex::new(name, &mut fork);
+    /// for i in 0..10u8 {
+    ///     index.put(&i, i * 10);
+    /// }
+    ///
+    /// let entries: Vec<_> = index.range(2..5).collect();
+    /// assert_eq!(entries, vec![(2, 20), (3, 30), (4, 40)]);
+    /// let latest: Vec<_> = index.range(5..).rev().take(2).collect();
+    /// assert_eq!(latest, vec![(9, 90), (8, 80)]);
+    /// ```
+    pub fn range<Q, R>(&self, range: R) -> MapIndexIter<K, V>
+    where
+        K: Borrow<Q>,
+        Q: StorageKey + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        MapIndexIter {
+            base_iter: self.base
+                .range(&(), range.start_bound(), range.end_bound()),
+        }
+    }
 }
 
 impl<'a, K, V> MapIndex<&'a mut Fork, K, V>
@@ -345,6 +386,10 @@ where
     K: StorageKey,
     V: StorageValue,
 {
+    pub(crate) fn fork(&mut self) -> &mut Fork {
+        self.base.fork()
+    }
+
     /// Inserts the key-value pair into the map.
     ///
     /// # Examples
@@ -441,6 +486,16 @@ where
     }
 }
 
+impl<'a, K, V> DoubleEndedIterator for MapIndexIter<'a, K, V>
+where
+    K: StorageKey,
+    V: StorageValue,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.base_iter.next_back()
+    }
+}
+
 impl<'a, K> Iterator for MapIndexKeys<'a, K>
 where
     K: StorageKey,
@@ -452,6 +507,15 @@ where
     }
 }
 
+impl<'a, K> DoubleEndedIterator for MapIndexKeys<'a, K>
+where
+    K: StorageKey,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.base_iter.next_back().map(|(k, ..)| k)
+    }
+}
+
 impl<'a, V> Iterator for MapIndexValues<'a, V>
 where
     V: StorageValue,
@@ -463,6 +527,15 @@ where
     }
 }
 
+impl<'a, V> DoubleEndedIterator for MapIndexValues<'a, V>
+where
+    V: StorageValue,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.base_iter.next_back().map(|(.., v)| v)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::{Database, MemoryDB};
@@ -572,6 +645,89 @@ mod tests {
         );
     }
 
+    fn range_and_rev(db: Box<Database>) {
+        let mut fork = db.fork();
+        // Neighbouring indexes in the same family must not leak into the iterators.
+        {
+            let mut map_index = MapIndex::new_in_family(IDX_NAME, &0u8, &mut fork);
+            map_index.put(&1u8, 10u8);
+        }
+        {
+            let mut map_index = MapIndex::new_in_family(IDX_NAME, &2u8, &mut fork);
+            map_index.put(&1u8, 30u8);
+        }
+        {
+            let mut map_index = MapIndex::new_in_family(IDX_NAME, &1u8, &mut fork);
+            for i in 1u8..6 {
+                map_index.put(&i, i * 2);
+            }
+
+            assert_eq!(
+                map_index.iter().rev().collect::<Vec<(u8, u8)>>(),
+                vec![(5, 10), (4, 8), (3, 6), (2, 4), (1, 2)]
+            );
+            assert_eq!(
+                map_index.keys().rev().collect::<Vec<u8>>(),
+                vec![5, 4, 3, 2, 1]
+            );
+            assert_eq!(
+                map_index.values().rev().collect::<Vec<u8>>(),
+                vec![10, 8, 6, 4, 2]
+            );
+
+            assert_eq!(
+                map_index.range(2u8..4).collect::<Vec<(u8, u8)>>(),
+                vec![(2, 4), (3, 6)]
+            );
+            assert_eq!(
+                map_index.range(2u8..4).rev().collect::<Vec<(u8, u8)>>(),
+                vec![(3, 6), (2, 4)]
+            );
+            assert_eq!(
+                map_index.range(..3u8).rev().collect::<Vec<(u8, u8)>>(),
+                vec![(2, 4), (1, 2)]
+            );
+            assert_eq!(
+                map_index.range(4u8..).collect::<Vec<(u8, u8)>>(),
+                vec![(4, 8), (5, 10)]
+            );
+            assert_eq!(
+                map_index.range(6u8..).rev().collect::<Vec<(u8, u8)>>(),
+                Vec::<(u8, u8)>::new()
+            );
+
+            // Front and back cursors must not cross each other.
+            let mut iter = map_index.range(1u8..4);
+            assert_eq!(iter.next(), Some((1, 2)));
+            assert_eq!(iter.next_back(), Some((3, 6)));
+            assert_eq!(iter.next_back(), Some((2, 4)));
+            assert_eq!(iter.next(), None);
+            assert_eq!(iter.next_back(), None);
+        }
+        {
+            // Reverse iteration over the uncommitted changes.
+            let mut map_index = MapIndex::new_in_family(IDX_NAME, &1u8, &mut fork);
+            map_index.remove(&5u8);
+            map_index.put(&0u8, 0u8);
+            assert_eq!(
+                map_index.keys().rev().collect::<Vec<u8>>(),
+                vec![4, 3, 2, 1, 0]
+            );
+        }
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        let map_index: MapIndex<_, u8, u8> = MapIndex::new_in_family(IDX_NAME, &1u8, &snapshot);
+        assert_eq!(
+            map_index.keys().rev().collect::<Vec<u8>>(),
+            vec![4, 3, 2, 1, 0]
+        );
+        assert_eq!(
+            map_index.range(1u8..=3).rev().collect::<Vec<(u8, u8)>>(),
+            vec![(3, 6), (2, 4), (1, 2)]
+        );
+    }
+
     fn gen_tempdir_name() -> String {
         thread_rng().gen_ascii_chars().take(10).collect()
     }
@@ -593,8 +749,17 @@ mod tests {
             super::iter(db);
         }
 
+        #[test]
+        fn test_range_and_rev() {
+            let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+            let path = dir.path();
+            let db = create_database(path);
+            super::range_and_rev(db);
+        }
+
     }
 
+    #[cfg(feature = "rocksdb")]
     mod rocksdb_tests {
         use std::path::Path;
         use storage::Database;
@@ -613,5 +778,13 @@ mod tests {
             let db = create_database(path);
             super::iter(db);
         }
+
+        #[test]
+        fn test_range_and_rev() {
+            let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+            let path = dir.path();
+            let db = create_database(path);
+            super::range_and_rev(db);
+        }
     }
 }
@@ -15,10 +15,14 @@
 //! An implementation of `MemoryDB` database.
 
 use std::{clone::Clone,
-          collections::{BTreeMap, HashMap},
+          collections::{BTreeMap, HashMap, Bound::{self, Unbounded}},
+          fs,
+          path::Path,
           sync::{Arc, RwLock}};
 
-use super::{db::Change, Database, Iter, Iterator, Patch, Result, Snapshot};
+use super::{db::Change, metrics, Database, Error, Iter, Iterator, Patch, Result, Snapshot};
+#[cfg(feature = "rocksdb")]
+use super::{DbOptions, RocksDB};
 
 type DB = HashMap<String, BTreeMap<Vec<u8>, Vec<u8>>>;
 
@@ -43,6 +47,103 @@ impl MemoryDB {
             map: RwLock::new(HashMap::new()),
         }
     }
+
+    /// Saves the contents of the database into a file at the specified path.
+    ///
+    /// The file contains a patch inserting all the entries of the database
+    /// in the binary format of [`Patch::to_bytes`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate tempdir;
+    /// use exonum::storage::{Database, MapIndex, MemoryDB};
+    /// # use tempdir::TempDir;
+    ///
+    /// # fn main() {
+    /// # let dir = TempDir::new("exonum_memorydb").unwrap();
+    /// # let path = dir.path().join("fixture.bin");
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// MapIndex::new("wallets", &mut fork).put(&1_u8, 10_u64);
+    /// db.merge(fork.into_patch()).unwrap();
+    /// db.dump_to(&path).unwrap();
+    ///
+    /// let db = MemoryDB::load_from(&path).unwrap();
+    /// let snapshot = db.snapshot();
+    /// let wallets: MapIndex<_, u8, u64> = MapIndex::new("wallets", &snapshot);
+    /// assert_eq!(wallets.get(&1), Some(10));
+    /// # }
+    /// ```
+    ///
+    /// [`Patch::to_bytes`]: struct.Patch.html#method.to_bytes
+    pub fn dump_to<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        fs::write(path, self.contents().to_bytes()).map_err(|e| {
+            Error::new(format!(
+                "Unable to dump the database to {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    /// Loads a database from a file created with [`dump_to`](#method.dump_to).
+    pub fn load_from<P: AsRef<Path>>(path: P) -> Result<MemoryDB> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|e| {
+            Error::new(format!(
+                "Unable to load the database from {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let db = MemoryDB::new();
+        db.merge(Patch::from_bytes(&bytes)?)?;
+        Ok(db)
+    }
+
+    /// Creates a database containing a copy of the current state of the `RocksDB` database.
+    #[cfg(feature = "rocksdb")]
+    pub fn from_rocksdb(db: &RocksDB) -> Result<MemoryDB> {
+        let snapshot = db.snapshot();
+        let mut map = HashMap::new();
+        for name in db.column_families()? {
+            let mut table = BTreeMap::new();
+            let mut iter = snapshot.iter(&name, &[]);
+            while let Some((key, value)) = iter.next() {
+                table.insert(key.to_vec(), value.to_vec());
+            }
+            if !table.is_empty() {
+                map.insert(name, table);
+            }
+        }
+        Ok(MemoryDB {
+            map: RwLock::new(map),
+        })
+    }
+
+    /// Copies the contents of the database into a `RocksDB` database at the specified path,
+    /// which is opened with the given options. The entries already stored in the `RocksDB`
+    /// database are overwritten.
+    #[cfg(feature = "rocksdb")]
+    pub fn to_rocksdb<P: AsRef<Path>>(&self, path: P, options: &DbOptions) -> Result<RocksDB> {
+        let db = RocksDB::open(path, options)?;
+        db.merge_sync(self.contents())?;
+        Ok(db)
+    }
+
+    /// Returns a patch inserting all the entries of the database.
+    fn contents(&self) -> Patch {
+        let mut fork = MemoryDB::new().fork();
+        for (name, table) in self.map.read().unwrap().iter() {
+            for (key, value) in table {
+                fork.put(name, key.clone(), value.clone());
+            }
+        }
+        fork.into_patch()
+    }
 }
 
 impl Database for MemoryDB {
@@ -53,6 +154,7 @@ impl Database for MemoryDB {
     }
 
     fn merge(&self, patch: Patch) -> Result<()> {
+        metrics::record_merge(&patch);
         let mut guard = self.map.write().unwrap();
         for (cf_name, changes) in patch {
             if !guard.contains_key(&cf_name) {
@@ -108,6 +210,20 @@ impl Snapshot for MemoryDB {
 
         Box::new(MemoryDBIter { data, index: 0 })
     }
+
+    fn iter_rev(&self, name: &str, to: Bound<&[u8]>) -> Iter {
+        let map_guard = self.map.read().unwrap();
+        let data = match map_guard.get(name) {
+            Some(table) => table
+                .range::<[u8], _>((Unbounded, to))
+                .rev()
+                .map(|(k, v)| (k.to_vec(), v.to_vec()))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        Box::new(MemoryDBIter { data, index: 0 })
+    }
 }
 
 impl Iterator for MemoryDBIter {
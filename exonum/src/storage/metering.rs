@@ -0,0 +1,156 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Metering of the storage operations performed by the current thread.
+//!
+//! Unlike the process-wide index metrics, metering accounts only for
+//! the operations performed within [`metered`](fn.metered.html), which allows to attribute
+//! the operations to a particular transaction.
+
+use std::cell::Cell;
+
+thread_local! {
+    static USAGE: Cell<Option<StorageUsage>> = Cell::new(None);
+}
+
+/// Storage operations performed by a metered piece of code.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageUsage {
+    /// Number of the values read by key or while iterating over an index.
+    pub reads: u64,
+    /// Number of the values put into or removed from a `Fork`.
+    pub writes: u64,
+    /// Total size of the keys and values read or written, in bytes.
+    pub bytes: u64,
+}
+
+impl StorageUsage {
+    fn add(&mut self, other: &StorageUsage) {
+        self.reads += other.reads;
+        self.writes += other.writes;
+        self.bytes += other.bytes;
+    }
+}
+
+/// Restores the usage of the enclosing metered code when dropped, even if
+/// the metered code panics.
+struct Guard {
+    outer: Option<StorageUsage>,
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        let outer = self.outer.take();
+        USAGE.with(|usage| {
+            let inner = usage.get();
+            usage.set(outer.map(|mut outer| {
+                if let Some(ref inner) = inner {
+                    outer.add(inner);
+                }
+                outer
+            }));
+        });
+    }
+}
+
+/// Calls the closure and returns its result together with the storage operations
+/// it has performed in the current thread. If `metered` is nested, the operations are
+/// accounted for in both the inner and the outer usage.
+pub(crate) fn metered<F, R>(f: F) -> (R, StorageUsage)
+where
+    F: FnOnce() -> R,
+{
+    let guard = Guard {
+        outer: USAGE.with(|usage| usage.replace(Some(StorageUsage::default()))),
+    };
+    let result = f();
+    let usage = USAGE.with(Cell::get).unwrap_or_default();
+    drop(guard);
+    (result, usage)
+}
+
+fn record(reads: u64, writes: u64, bytes: usize) {
+    USAGE.with(|usage| {
+        if let Some(mut current) = usage.get() {
+            current.add(&StorageUsage {
+                reads,
+                writes,
+                bytes: bytes as u64,
+            });
+            usage.set(Some(current));
+        }
+    });
+}
+
+/// Records the read of a value with the given total size of the key and the value.
+pub(crate) fn record_read(bytes: usize) {
+    record(1, 0, bytes);
+}
+
+/// Records the write or removal of a value with the given total size of the key
+/// and the value.
+pub(crate) fn record_write(bytes: usize) {
+    record(0, 1, bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Database, ListIndex, MapIndex, MemoryDB};
+    use super::*;
+
+    #[test]
+    fn operations_are_metered() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let mut map = MapIndex::new("metering", &mut fork);
+        // The first change of the index also writes the index type into the metadata.
+        map.put(&0_u8, 0_u16);
+
+        let ((), usage) = metered(|| {
+            map.put(&1_u8, 10_u16);
+            map.remove(&0_u8);
+            assert_eq!(map.get(&1), Some(10));
+            assert_eq!(map.get(&2), None);
+            assert_eq!(map.iter().count(), 1);
+        });
+        assert_eq!(usage.writes, 2);
+        assert_eq!(usage.reads, 3);
+        // Keys take 1 byte and values take 2 bytes.
+        assert_eq!(usage.bytes, 3 + 1 + 3 + 1 + 3);
+
+        // Operations outside `metered` are not accounted.
+        let ((), usage) = metered(|| ());
+        map.put(&2_u8, 20_u16);
+        assert_eq!(usage, StorageUsage::default());
+    }
+
+    #[test]
+    fn nested_metering() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        ListIndex::new("nested_metering", &mut fork).push(1_u8);
+
+        let (inner, outer) = metered(|| {
+            let list: ListIndex<_, u8> = ListIndex::new("nested_metering", &fork);
+            assert_eq!(list.get(0), Some(1));
+            let ((), inner) = metered(|| {
+                assert_eq!(list.get(0), Some(1));
+            });
+            inner
+        });
+        assert_eq!(inner.reads, 1);
+        assert_eq!(outer.reads, 2);
+        assert_eq!(outer.bytes, 2 * inner.bytes);
+    }
+}
@@ -0,0 +1,233 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Counters of the storage operations performed on the named indexes.
+//!
+//! The counters are updated on every read and write, so they are collected only if Exonum
+//! is built with the `index-metrics` feature; otherwise the recording functions are no-ops.
+
+use std::{collections::{BTreeMap, HashMap},
+          sync::{atomic::{AtomicUsize, Ordering},
+                 RwLock}};
+
+use super::{db::Change, Patch};
+
+/// Whether the index metrics are collected.
+const ENABLED: bool = cfg!(feature = "index-metrics");
+
+lazy_static! {
+    static ref COUNTERS: RwLock<HashMap<String, Counters>> = RwLock::new(HashMap::new());
+}
+
+/// Numbers of the storage operations performed on an index since the start of the process
+/// or the last call of [`reset_index_metrics`].
+///
+/// Operations on all indexes of a family are accounted together under the family name.
+///
+/// [`reset_index_metrics`]: fn.reset_index_metrics.html
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexMetrics {
+    /// Number of the reads of a value by key.
+    pub reads: u64,
+    /// Total size of the values read by key, in bytes.
+    pub read_bytes: u64,
+    /// Number of the values put into a `Fork`.
+    pub writes: u64,
+    /// Total size of the keys and values put into a `Fork`, in bytes.
+    pub written_bytes: u64,
+    /// Number of the values removed from a `Fork`.
+    pub deletes: u64,
+    /// Number of the changes merged into a database.
+    pub merged_changes: u64,
+    /// Total size of the keys and values merged into a database, in bytes.
+    pub merged_bytes: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    reads: AtomicUsize,
+    read_bytes: AtomicUsize,
+    writes: AtomicUsize,
+    written_bytes: AtomicUsize,
+    deletes: AtomicUsize,
+    merged_changes: AtomicUsize,
+    merged_bytes: AtomicUsize,
+}
+
+impl Counters {
+    fn to_metrics(&self) -> IndexMetrics {
+        let load = |counter: &AtomicUsize| counter.load(Ordering::Relaxed) as u64;
+        IndexMetrics {
+            reads: load(&self.reads),
+            read_bytes: load(&self.read_bytes),
+            writes: load(&self.writes),
+            written_bytes: load(&self.written_bytes),
+            deletes: load(&self.deletes),
+            merged_changes: load(&self.merged_changes),
+            merged_bytes: load(&self.merged_bytes),
+        }
+    }
+}
+
+/// Returns the metrics of all indexes accessed since the start of the process
+/// or the last call of [`reset_index_metrics`], keyed by the index name.
+///
+/// The metrics are collected only if Exonum is built with the `index-metrics` feature;
+/// otherwise the returned map is always empty.
+///
+/// # Examples
+///
+/// ```
+/// use exonum::storage::{index_metrics, Database, ListIndex, MemoryDB};
+///
+/// let db = MemoryDB::new();
+/// let mut fork = db.fork();
+/// ListIndex::new("metrics_example", &mut fork).push(1_u64);
+/// db.merge(fork.into_patch()).unwrap();
+///
+/// if cfg!(feature = "index-metrics") {
+///     let metrics = index_metrics()["metrics_example"];
+///     assert!(metrics.writes >= 2); // the element and the length of the list
+///     assert!(metrics.merged_changes >= 2);
+/// } else {
+///     assert!(index_metrics().is_empty());
+/// }
+/// ```
+///
+/// [`reset_index_metrics`]: fn.reset_index_metrics.html
+pub fn index_metrics() -> BTreeMap<String, IndexMetrics> {
+    COUNTERS
+        .read()
+        .unwrap()
+        .iter()
+        .map(|(name, counters)| (name.clone(), counters.to_metrics()))
+        .collect()
+}
+
+/// Resets the metrics of all indexes.
+pub fn reset_index_metrics() {
+    COUNTERS.write().unwrap().clear();
+}
+
+fn with_counters<F: FnOnce(&Counters)>(name: &str, f: F) {
+    if !ENABLED {
+        return;
+    }
+    {
+        let counters = COUNTERS.read().unwrap();
+        if let Some(counters) = counters.get(name) {
+            f(counters);
+            return;
+        }
+    }
+    let mut counters = COUNTERS.write().unwrap();
+    f(counters
+        .entry(name.to_owned())
+        .or_insert_with(Counters::default));
+}
+
+fn add(counter: &AtomicUsize, value: usize) {
+    counter.fetch_add(value, Ordering::Relaxed);
+}
+
+/// Records the read of a value by key.
+pub(crate) fn record_read(name: &str, value: Option<&[u8]>) {
+    with_counters(name, |counters| {
+        add(&counters.reads, 1);
+        add(&counters.read_bytes, value.map_or(0, <[u8]>::len));
+    });
+}
+
+/// Records the write of a value into a `Fork`.
+pub(crate) fn record_write(name: &str, bytes: usize) {
+    with_counters(name, |counters| {
+        add(&counters.writes, 1);
+        add(&counters.written_bytes, bytes);
+    });
+}
+
+/// Records the removal of values from a `Fork`.
+pub(crate) fn record_deletes(name: &str, count: usize) {
+    with_counters(name, |counters| add(&counters.deletes, count));
+}
+
+/// Records the merge of a patch into a database and reports the updated metrics
+/// of the changed indexes with the `metric!` macro.
+pub(crate) fn record_merge(patch: &Patch) {
+    if !ENABLED {
+        return;
+    }
+    for (name, changes) in patch.iter() {
+        let mut count = 0;
+        let mut bytes = 0;
+        for (key, change) in changes.iter() {
+            count += 1;
+            bytes += key.len();
+            if let Change::Put(ref value) = *change {
+                bytes += value.len();
+            }
+        }
+
+        with_counters(name, |counters| {
+            add(&counters.merged_changes, count);
+            add(&counters.merged_bytes, bytes);
+
+            let metrics = counters.to_metrics();
+            metric!(&format!("storage.{}.reads", name), metrics.reads);
+            metric!(&format!("storage.{}.writes", name), metrics.writes);
+            metric!(&format!("storage.{}.deletes", name), metrics.deletes);
+            metric!(
+                &format!("storage.{}.merged_bytes", name),
+                metrics.merged_bytes
+            );
+        });
+    }
+}
+
+#[cfg(all(test, feature = "index-metrics"))]
+mod tests {
+    use super::super::{Database, MapIndex, MemoryDB};
+    use super::*;
+
+    #[test]
+    fn index_operations_are_counted() {
+        // Metrics are global, so the test uses index names unique to it.
+        const NAME: &str = "metrics_test_map";
+        let db = MemoryDB::new();
+
+        let mut fork = db.fork();
+        {
+            let mut map = MapIndex::new(NAME, &mut fork);
+            map.put(&1_u8, 10_u8);
+            map.put(&2_u8, 20_u8);
+            map.remove(&2_u8);
+        }
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        let map: MapIndex<_, u8, u8> = MapIndex::new(NAME, &snapshot);
+        assert_eq!(map.get(&1), Some(10));
+        assert_eq!(map.get(&2), None);
+        assert!(map.contains(&1));
+
+        let metrics = index_metrics()[NAME];
+        assert_eq!(metrics.writes, 2);
+        assert_eq!(metrics.written_bytes, 4);
+        assert_eq!(metrics.deletes, 1);
+        assert_eq!(metrics.reads, 3);
+        assert_eq!(metrics.read_bytes, 1);
+        assert_eq!(metrics.merged_changes, 2);
+        assert_eq!(metrics.merged_bytes, 3);
+    }
+}
@@ -24,7 +24,16 @@
 //! that is, the Exonum process has exclusive access to the DB during blockchain operation.
 //! You can interact with the `Database` from multiple threads by cloning its instance.
 //!
-//! Exonum provides two database types: [`RocksDB`] and [`MemoryDB`].
+//! Exonum provides three database types: [`RocksDB`], [`LogDB`] and [`MemoryDB`]. Nodes
+//! store the blockchain in `RocksDB`, which is opened with [`DbOptions`]. `LogDB` is a simple
+//! persistent database implemented in pure Rust, which keeps the whole state in memory;
+//! it is intended for tests and tools working with small data sets rather than for nodes.
+//! Any database can be wrapped into a [`CachedDatabase`], which keeps the recently read
+//! entries in memory.
+//!
+//! `RocksDB` is available only if the `rocksdb` feature, which is enabled by default,
+//! is turned on. Without the feature Exonum does not depend on the native `RocksDB`
+//! library, but the node database cannot be opened with [`open_database`].
 //!
 //! # Snapshot and Fork
 //!
@@ -40,7 +49,8 @@
 //! the [`fork`][2] method of the `Database`. Like `Snapshot`, `Fork` provides read isolation,
 //! but also allows to create a sequence of changes to the database that are specified
 //! as a [`Patch`]. A patch can be atomically [`merge`]d into a database. Different threads
-//! may call `merge` concurrently.
+//! may call `merge` concurrently. Patches can be exported in binary or JSON form and inspected
+//! as a per-index [`PatchDiff`] against a snapshot.
 //!
 //! # `StorageKey` and `StorageValue` traits
 //!
@@ -58,6 +68,7 @@
 //! On the other hand, multiple indices can be stored in the same column family, provided
 //! that their key spaces do not intersect. Isolation is commonly achieved with the help
 //! of column families; see `new_in_family` constructor in the built-in index types.
+//! The indices of a family can be enumerated and removed at once with [`IndexFamily`].
 //!
 //! Merkelized indices can generate cryptographic proofs about inclusion
 //! of entries. Having such a proof, an external client may verify locally that the received data
@@ -71,80 +82,129 @@
 //! - [`ListIndex`] is a list of items stored in the sequential order. Similar to [`Vec`].
 //! - [`SparseListIndex`] is a list of items stored in the sequential order. Similar to `ListIndex`,
 //!   but may contain indices without elements.
+//! - [`QueueIndex`] is a first-in, first-out queue of items. Similar to [`VecDeque`], but
+//!   supports adding items only to the back and removing them only from the front.
 //! - [`MapIndex`] is a map of keys and values. Similar to [`BTreeMap`].
 //! - [`ProofListIndex`] is a Merkelized version of `ListIndex` that supports cryptographic
 //!   proofs of existence and is implemented as a Merkle tree.
 //! - [`ProofMapIndex`] is a Merkelized version of `MapIndex` that supports cryptographic
 //!   proofs of existence and is implemented as a binary Merkle Patricia tree.
+//! - [`ProofEntry`] is a Merkelized version of `Entry` that supports cryptographic proofs
+//!   of the stored value.
+//! - [`ProofKeySetIndex`] is a Merkelized set of keys that supports cryptographic proofs
+//!   of membership and non-membership. It is built on top of the `ProofMapIndex` tree.
 //! - [`KeySetIndex`] and [`ValueSetIndex`] is a set of items, similar to [`BTreeSet`] and
 //!   [`HashSet`].
 //!
+//! [`IndexedMapIndex`] and [`IndexedProofMapIndex`] wrap `MapIndex` and `ProofMapIndex`
+//! respectively and keep the declared secondary indexes in sync with the map.
+//!
 //! [`Database`]: trait.Database.html
 //! [`RocksDB`]: struct.RocksDB.html
 //! [`MemoryDB`]: struct.MemoryDB.html
+//! [`LogDB`]: struct.LogDB.html
+//! [`CachedDatabase`]: struct.CachedDatabase.html
+//! [`DbOptions`]: struct.DbOptions.html
+//! [`open_database`]: fn.open_database.html
 //! [`Snapshot`]: trait.Snapshot.html
 //! [`Fork`]: struct.Fork.html
 //! [`Patch`]: struct.Patch.html
 //! [1]: trait.Database.html#tymethod.snapshot
 //! [2]: trait.Database.html#method.fork
 //! [`merge`]: trait.Database.html#tymethod.merge
+//! [`PatchDiff`]: struct.PatchDiff.html
 //! [`StorageKey`]: trait.StorageKey.html
 //! [`StorageValue`]: trait.StorageValue.html
 //! [`Entry`]: struct.Entry.html
+//! [`IndexFamily`]: index_family/struct.IndexFamily.html
 //! [`ListIndex`]: list_index/struct.ListIndex.html
 //! [`SparseListIndex`]: sparse_list_index/struct.SparseListIndex.html
+//! [`QueueIndex`]: queue_index/struct.QueueIndex.html
 //! [`MapIndex`]: map_index/struct.MapIndex.html
 //! [`ProofListIndex`]: proof_list_index/struct.ProofListIndex.html
 //! [`ProofMapIndex`]: proof_map_index/struct.ProofMapIndex.html
+//! [`ProofEntry`]: proof_entry/struct.ProofEntry.html
+//! [`ProofKeySetIndex`]: proof_key_set_index/struct.ProofKeySetIndex.html
 //! [`KeySetIndex`]: key_set_index/struct.KeySetIndex.html
 //! [`ValueSetIndex`]: value_set_index/struct.ValueSetIndex.html
+//! [`IndexedMapIndex`]: secondary_index/struct.IndexedMapIndex.html
+//! [`IndexedProofMapIndex`]: secondary_index/struct.IndexedProofMapIndex.html
 //! [doc:storage]: https://exonum.com/doc/architecture/storage
 //! [`Option`]: https://doc.rust-lang.org/std/option/enum.Option.html
 //! [`Box`]: https://doc.rust-lang.org/std/boxed/struct.Box.html
 //! [`Vec`]: https://doc.rust-lang.org/std/vec/struct.Vec.html
+//! [`VecDeque`]: https://doc.rust-lang.org/std/collections/struct.VecDeque.html
 //! [`BTreeMap`]: https://doc.rust-lang.org/std/collections/struct.BTreeMap.html
 //! [`BTreeSet`]: https://doc.rust-lang.org/std/collections/struct.BTreeSet.html
 //! [`HashSet`]: https://doc.rust-lang.org/std/collections/struct.HashSet.html
 
 #[doc(no_inline)]
 pub use self::proof_map_index::{HashedKey, MapProof, ProofMapIndex};
-pub use self::{db::{Change, Changes, ChangesIterator, Database, Fork, Iter, Iterator, Patch,
+pub use self::{cached_db::{CacheMetrics, CachedDatabase},
+               db::{Change, Changes, ChangesIterator, Database, Fork, Iter, Iterator, Patch,
                     PatchIterator, Snapshot},
                entry::Entry,
                error::Error,
                hash::UniqueHash,
+               index_family::IndexFamily,
+               indexes_metadata::{list_indexes, remove_index, IndexInfo, IndexType},
                key_set_index::KeySetIndex,
                keys::StorageKey,
                list_index::ListIndex,
+               logdb::LogDB,
                map_index::MapIndex,
                memorydb::MemoryDB,
-               options::DbOptions,
+               metering::StorageUsage,
+               metrics::{index_metrics, reset_index_metrics, IndexMetrics},
+               options::{open_database, CompressionType, DbOptions, IndexOptions},
+               patch_diff::{EntryDiff, IndexDiff, PatchDiff},
+               proof_entry::{EntryProof, ProofEntry},
+               proof_key_set_index::ProofKeySetIndex,
                proof_list_index::{ListProof, ProofListIndex},
-               rocksdb::RocksDB,
+               queue_index::QueueIndex,
+               secondary_index::{IndexedMapIndex, IndexedProofMapIndex},
                sparse_list_index::SparseListIndex,
                value_set_index::ValueSetIndex,
                values::StorageValue};
+#[cfg(feature = "rocksdb")]
+pub use self::rocksdb::RocksDB;
+
+pub(crate) use self::metering::metered;
 
 /// A specialized `Result` type for I/O operations with storage.
 pub type Result<T> = ::std::result::Result<T, Error>;
 
 mod base_index;
+mod cached_db;
 mod db;
 mod entry;
 mod error;
 mod hash;
 mod indexes_metadata;
 mod keys;
+mod logdb;
 mod memorydb;
+mod metering;
+mod metrics;
 mod options;
+mod patch_diff;
+#[cfg(feature = "rocksdb")]
 mod rocksdb;
 mod values;
 
+#[cfg(feature = "conformance")]
+#[macro_use]
+pub mod conformance;
+pub mod index_family;
 pub mod key_set_index;
 pub mod list_index;
 pub mod map_index;
+pub mod proof_entry;
+pub mod proof_key_set_index;
 pub mod proof_list_index;
 pub mod proof_map_index;
+pub mod queue_index;
+pub mod secondary_index;
 pub mod sparse_list_index;
 pub mod value_set_index;
 
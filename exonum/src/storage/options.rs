@@ -14,8 +14,75 @@
 
 //! Abstract settings for databases.
 
+use std::{collections::BTreeMap, path::Path};
+
+use super::{Database, Result};
+#[cfg(not(feature = "rocksdb"))]
+use super::Error;
+#[cfg(feature = "rocksdb")]
+use super::{CachedDatabase, RocksDB};
+
+/// Algorithm used to compress the data blocks of the `RocksDB` backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionType {
+    /// No compression.
+    None,
+    /// Snappy compression.
+    Snappy,
+    /// Zlib compression.
+    Zlib,
+    /// Bzip2 compression.
+    Bz2,
+    /// LZ4 compression.
+    Lz4,
+    /// LZ4HC (high compression) compression.
+    Lz4hc,
+    /// Zstandard compression.
+    Zstd,
+}
+
+/// Options of an individual index which override the corresponding database-wide
+/// values of [`DbOptions`].
+///
+/// Unspecified options fall back to the values of [`DbOptions`].
+///
+/// [`DbOptions`]: struct.DbOptions.html
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IndexOptions {
+    /// Compression algorithm of the index data.
+    pub compression_type: Option<CompressionType>,
+    /// Size of the block cache of the index in bytes. The cache is used only by
+    /// the index (or by all indexes of the family).
+    pub block_cache_size: Option<usize>,
+    /// Size of the in-memory write buffer of the index in bytes.
+    pub write_buffer_size: Option<usize>,
+    /// Number of bits per key in the bloom filter of the index.
+    pub bloom_filter_bits: Option<i32>,
+}
+
 /// Options for database.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+///
+/// Besides the common options, `DbOptions` contains tuning options of the `RocksDB`
+/// database of the node, which are ignored by other databases. Unless stated otherwise,
+/// these options default to `None`, which means the default `RocksDB` value is used.
+///
+/// # Examples
+///
+/// The options can be specified in the `database` section of the node configuration:
+///
+/// ```toml
+/// [database]
+/// create_if_missing = true
+/// compression_type = "lz4"
+/// block_cache_size_per_cf = 536870912
+/// max_background_compactions = 4
+///
+/// [database.indexes."core.transactions"]
+/// compression_type = "zstd"
+/// bloom_filter_bits = 10
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DbOptions {
     /// Number of open files that can be used by the DB.
     ///
@@ -25,6 +92,55 @@ pub struct DbOptions {
     ///
     /// Defaults to `true`.
     pub create_if_missing: bool,
+    /// Maximum number of recently read entries kept in memory by [`CachedDatabase`].
+    ///
+    /// Defaults to `None`, which means lookups are not cached.
+    ///
+    /// [`CachedDatabase`]: struct.CachedDatabase.html
+    #[serde(default)]
+    pub cache_size: Option<usize>,
+    /// Compression algorithm of the data blocks.
+    #[serde(default)]
+    pub compression_type: Option<CompressionType>,
+    /// Size of the block cache of each column family in bytes.
+    ///
+    /// Note that the caches are not shared between column families: every index or index
+    /// family gets a separate cache of this size, so the total memory used by the caches
+    /// grows with the number of column families in the database.
+    #[serde(default)]
+    pub block_cache_size_per_cf: Option<usize>,
+    /// Size of the in-memory write buffer (memtable) of a column family in bytes.
+    #[serde(default)]
+    pub write_buffer_size: Option<usize>,
+    /// Number of bits per key in bloom filters, which speed up the lookups of missing keys.
+    ///
+    /// Defaults to `None`, which means bloom filters are not used.
+    #[serde(default)]
+    pub bloom_filter_bits: Option<i32>,
+    /// Maximum total size of the write-ahead log files in bytes. Once the size is exceeded,
+    /// the column families backed by the oldest log file are flushed.
+    #[serde(default)]
+    pub max_total_wal_size: Option<u64>,
+    /// Whether the write-ahead log is disabled for the changes merged with
+    /// [`Database::merge`]. Such changes may be lost if the process crashes.
+    /// `Database::merge_sync` always uses the write-ahead log.
+    ///
+    /// Defaults to `false`.
+    ///
+    /// [`Database::merge`]: trait.Database.html#tymethod.merge
+    #[serde(default)]
+    pub disable_wal: bool,
+    /// Maximum number of concurrent background compactions. The thread pool
+    /// of the database is extended accordingly.
+    #[serde(default)]
+    pub max_background_compactions: Option<i32>,
+    /// Options of individual indexes, keyed by the index name. For index families,
+    /// the name of the family is used.
+    ///
+    /// The options are applied both when the column family of the index is created
+    /// and when the database is opened.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub indexes: BTreeMap<String, IndexOptions>,
 }
 
 impl Default for DbOptions {
@@ -32,6 +148,43 @@ impl Default for DbOptions {
         Self {
             max_open_files: None,
             create_if_missing: true,
+            cache_size: None,
+            compression_type: None,
+            block_cache_size_per_cf: None,
+            write_buffer_size: None,
+            bloom_filter_bits: None,
+            max_total_wal_size: None,
+            disable_wal: false,
+            max_background_compactions: None,
+            indexes: BTreeMap::new(),
         }
     }
 }
+
+/// Opens the `RocksDB` database of a node stored in the specified path with the options
+/// specified in `options`.
+///
+/// If `cache_size` is specified, the database is wrapped into a [`CachedDatabase`].
+///
+/// [`CachedDatabase`]: struct.CachedDatabase.html
+#[cfg(feature = "rocksdb")]
+pub fn open_database<P: AsRef<Path>>(path: P, options: &DbOptions) -> Result<Box<Database>> {
+    let db = RocksDB::open(path, options)?;
+    Ok(match options.cache_size {
+        Some(size) => Box::new(CachedDatabase::new(db, size)),
+        None => Box::new(db),
+    })
+}
+
+/// Opens the database of a node stored in the specified path.
+///
+/// # Errors
+///
+/// Always returns an error, since the node database requires the `RocksDB` backend,
+/// and Exonum is built without the `rocksdb` feature.
+#[cfg(not(feature = "rocksdb"))]
+pub fn open_database<P: AsRef<Path>>(_path: P, _options: &DbOptions) -> Result<Box<Database>> {
+    Err(Error::new(
+        "RocksDB backend is not available: Exonum is built without the `rocksdb` feature",
+    ))
+}
@@ -0,0 +1,190 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Human-readable representation of the changes made by a `Patch`.
+
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+use std::fmt;
+
+use super::{db::Change, Patch, Snapshot};
+use encoding::serialize::encode_hex;
+
+/// Change of a single entry made by a patch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryDiff {
+    /// Key of the entry.
+    pub key: Vec<u8>,
+    /// Value of the entry before the patch is applied, or `None` if the entry is added.
+    pub old_value: Option<Vec<u8>>,
+    /// Value of the entry after the patch is applied, or `None` if the entry is removed.
+    pub new_value: Option<Vec<u8>>,
+}
+
+/// Changes made by a patch to a single column family.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IndexDiff {
+    /// Name of the column family.
+    pub name: String,
+    /// Changed entries ordered by key.
+    pub entries: Vec<EntryDiff>,
+}
+
+/// Changes made by a [`Patch`] compared with the state of a snapshot, grouped by
+/// column families.
+///
+/// Unlike the patch itself, the diff contains the values replaced by the patch and omits
+/// the changes which do not affect the snapshot, such as removals of the missing entries
+/// or insertions of the values equal to the stored ones.
+///
+/// The `Display` implementation of the diff outputs the changes in the following form,
+/// with keys and values encoded in hex:
+///
+/// ```text
+/// index `wallets`: 1 added, 1 modified, 1 removed
+/// + 01: 0a
+/// ~ 02: 0b -> 0c
+/// - 03: 0d
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use exonum::storage::{Database, MemoryDB, PatchDiff};
+///
+/// let db = MemoryDB::new();
+/// let mut fork = db.fork();
+/// fork.put("wallets", vec![1], vec![10]);
+/// let diff = PatchDiff::new(fork.patch(), &db.snapshot());
+/// assert_eq!(diff.to_string(), "index `wallets`: 1 added, 0 modified, 0 removed\n+ 01: 0a\n");
+/// ```
+///
+/// [`Patch`]: struct.Patch.html
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PatchDiff {
+    indexes: Vec<IndexDiff>,
+}
+
+impl EntryDiff {
+    /// Returns `true` if the entry is added by the patch.
+    pub fn is_added(&self) -> bool {
+        self.old_value.is_none()
+    }
+
+    /// Returns `true` if the entry is removed by the patch.
+    pub fn is_removed(&self) -> bool {
+        self.new_value.is_none()
+    }
+}
+
+impl PatchDiff {
+    /// Computes the changes the patch makes to the state of the snapshot.
+    pub fn new(patch: &Patch, snapshot: &Snapshot) -> Self {
+        let mut indexes: Vec<IndexDiff> = patch
+            .iter()
+            .map(|(name, changes)| {
+                let entries = changes
+                    .iter()
+                    .filter_map(|(key, change)| {
+                        let old_value = snapshot.get(name, key);
+                        let new_value = match *change {
+                            Change::Put(ref value) => Some(value.clone()),
+                            Change::Delete => None,
+                        };
+                        if old_value == new_value {
+                            return None;
+                        }
+                        Some(EntryDiff {
+                            key: key.clone(),
+                            old_value,
+                            new_value,
+                        })
+                    })
+                    .collect();
+                IndexDiff {
+                    name: name.clone(),
+                    entries,
+                }
+            })
+            .filter(|index| !index.entries.is_empty())
+            .collect();
+        indexes.sort_by(|a, b| a.name.cmp(&b.name));
+        PatchDiff { indexes }
+    }
+
+    /// Returns the changed column families ordered by name.
+    pub fn indexes(&self) -> &[IndexDiff] {
+        &self.indexes
+    }
+
+    /// Returns `true` if the patch does not change the snapshot.
+    pub fn is_empty(&self) -> bool {
+        self.indexes.is_empty()
+    }
+}
+
+impl Serialize for EntryDiff {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("EntryDiff", 3)?;
+        state.serialize_field("key", &encode_hex(&self.key))?;
+        state.serialize_field("old_value", &self.old_value.as_ref().map(encode_hex))?;
+        state.serialize_field("new_value", &self.new_value.as_ref().map(encode_hex))?;
+        state.end()
+    }
+}
+
+impl fmt::Display for EntryDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let key = encode_hex(&self.key);
+        match (&self.old_value, &self.new_value) {
+            (&None, &Some(ref new)) => write!(f, "+ {}: {}", key, encode_hex(new)),
+            (&Some(ref old), &None) => write!(f, "- {}: {}", key, encode_hex(old)),
+            (&Some(ref old), &Some(ref new)) => {
+                write!(f, "~ {}: {} -> {}", key, encode_hex(old), encode_hex(new))
+            }
+            (&None, &None) => write!(f, "  {}", key),
+        }
+    }
+}
+
+impl fmt::Display for IndexDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let added = self.entries.iter().filter(|e| e.is_added()).count();
+        let removed = self.entries.iter().filter(|e| e.is_removed()).count();
+        writeln!(
+            f,
+            "index `{}`: {} added, {} modified, {} removed",
+            self.name,
+            added,
+            self.entries.len() - added - removed,
+            removed
+        )?;
+        for entry in &self.entries {
+            writeln!(f, "{}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for PatchDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for index in &self.indexes {
+            write!(f, "{}", index)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,424 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An implementation of an authenticated index that may only contain one element.
+
+use std::marker::PhantomData;
+
+use super::{base_index::BaseIndex,
+            indexes_metadata::IndexType,
+            proof_map_index::{MapProof, MapProofError},
+            Fork,
+            Snapshot,
+            StorageValue};
+use crypto::Hash;
+
+/// An index that may only contain one element and supports cryptographic proofs
+/// of its value.
+///
+/// `ProofEntry` is an authenticated counterpart of [`Entry`]. The root hash of the index
+/// is equal to the hash of the stored value or to the default hash value if the index
+/// is empty, so an `Entry` which state is aggregated into the blockchain state can be replaced
+/// by a `ProofEntry` without changing the state hash.
+///
+/// A value should implement [`StorageValue`] trait.
+///
+/// [`Entry`]: ../struct.Entry.html
+/// [`StorageValue`]: ../trait.StorageValue.html
+#[derive(Debug)]
+pub struct ProofEntry<T, V> {
+    base: BaseIndex<T>,
+    _v: PhantomData<V>,
+}
+
+/// A proof of the value stored in a `ProofEntry`.
+///
+/// The proof is created by the [`get_proof`] method of [`ProofEntry`] and can be verified
+/// either against the root hash of the index with the [`validate`] method, or against
+/// the `state_hash` of a block with the [`validate_to_state`] method.
+///
+/// [`get_proof`]: struct.ProofEntry.html#method.get_proof
+/// [`ProofEntry`]: struct.ProofEntry.html
+/// [`validate`]: #method.validate
+/// [`validate_to_state`]: #method.validate_to_state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryProof<V> {
+    value: Option<V>,
+}
+
+/// An error returned when an entry proof is invalid.
+#[derive(Debug, Fail)]
+pub enum EntryProofError {
+    /// The hash of the proof is not equal to the trusted root hash.
+    #[fail(display = "unmatched root hash")]
+    UnmatchedRootHash,
+
+    /// The proof of the service table is invalid.
+    #[fail(display = "invalid proof of the service table: {}", _0)]
+    InvalidTableProof(#[cause] MapProofError),
+
+    /// The proof of the service table does not contain the root hash of the table.
+    #[fail(display = "service table is missing in the proof")]
+    MissingTable,
+
+    /// The hash of the proof of the service table is not equal to the trusted state hash.
+    #[fail(display = "unmatched state hash")]
+    UnmatchedStateHash,
+}
+
+impl<T, V> ProofEntry<T, V>
+where
+    T: AsRef<Snapshot>,
+    V: StorageValue,
+{
+    /// Creates a new index representation based on the name and storage view.
+    ///
+    /// Storage view can be specified as [`&Snapshot`] or [`&mut Fork`]. In the first case only
+    /// immutable methods are available. In the second case both immutable and mutable methods are
+    /// available.
+    ///
+    /// [`&Snapshot`]: ../trait.Snapshot.html
+    /// [`&mut Fork`]: ../struct.Fork.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofEntry};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: ProofEntry<_, u8> = ProofEntry::new(name, &snapshot);
+    ///
+    /// let mut fork = db.fork();
+    /// let mut mut_index: ProofEntry<_, u8> = ProofEntry::new(name, &mut fork);
+    /// ```
+    pub fn new<S: AsRef<str>>(index_name: S, view: T) -> Self {
+        ProofEntry {
+            base: BaseIndex::new(index_name.as_ref(), IndexType::ProofEntry, view),
+            _v: PhantomData,
+        }
+    }
+
+    /// Returns a value of the entry or `None` if does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofEntry};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = ProofEntry::new("name", &mut fork);
+    /// assert_eq!(None, index.get());
+    ///
+    /// index.set(10);
+    /// assert_eq!(Some(10), index.get());
+    /// ```
+    pub fn get(&self) -> Option<V> {
+        self.base.get(&())
+    }
+
+    /// Returns `true` if a value of the entry exists.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofEntry};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = ProofEntry::new("name", &mut fork);
+    /// assert!(!index.exists());
+    ///
+    /// index.set(10);
+    /// assert!(index.exists());
+    /// ```
+    pub fn exists(&self) -> bool {
+        self.base.contains(&())
+    }
+
+    /// Returns the root hash of the entry, which is the hash of its value
+    /// or default hash value if the entry is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofEntry};
+    /// use exonum::crypto::{CryptoHash, Hash};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = ProofEntry::new("name", &mut fork);
+    /// assert_eq!(Hash::default(), index.merkle_root());
+    ///
+    /// let value = 10_u8;
+    /// index.set(value);
+    /// assert_eq!(value.hash(), index.merkle_root());
+    /// ```
+    pub fn merkle_root(&self) -> Hash {
+        self.base
+            .get::<(), V>(&())
+            .map(|v| v.hash())
+            .unwrap_or_default()
+    }
+
+    /// Returns the proof of the value stored in the entry, or of its absence.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofEntry};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = ProofEntry::new("name", &mut fork);
+    /// index.set(10_u8);
+    ///
+    /// let proof = index.get_proof();
+    /// assert_eq!(proof.validate(index.merkle_root()).unwrap(), Some(&10));
+    /// ```
+    pub fn get_proof(&self) -> EntryProof<V> {
+        EntryProof { value: self.get() }
+    }
+}
+
+impl<'a, V> ProofEntry<&'a mut Fork, V>
+where
+    V: StorageValue,
+{
+    /// Changes a value of the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofEntry};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = ProofEntry::new("name", &mut fork);
+    ///
+    /// index.set(10);
+    /// assert_eq!(Some(10), index.get());
+    /// ```
+    pub fn set(&mut self, value: V) {
+        self.base.put(&(), value)
+    }
+
+    /// Removes a value of the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofEntry};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = ProofEntry::new("name", &mut fork);
+    ///
+    /// index.set(10);
+    /// assert_eq!(Some(10), index.get());
+    ///
+    /// index.remove();
+    /// assert_eq!(None, index.get());
+    /// ```
+    pub fn remove(&mut self) {
+        self.base.remove(&())
+    }
+}
+
+impl<V: StorageValue> EntryProof<V> {
+    /// Returns the value asserted by the proof, without verifying the proof.
+    pub fn value_unchecked(&self) -> Option<&V> {
+        self.value.as_ref()
+    }
+
+    /// Computes the root hash of the entry asserted by the proof.
+    pub fn merkle_root(&self) -> Hash {
+        self.value
+            .as_ref()
+            .map(|v| v.hash())
+            .unwrap_or_default()
+    }
+
+    /// Verifies the correctness of the proof by the trusted root hash of the entry.
+    ///
+    /// If the proof is valid, the value of the entry is returned (`None` means
+    /// that the entry is empty). Otherwise, `Err` is returned.
+    pub fn validate(&self, merkle_root: Hash) -> Result<Option<&V>, EntryProofError> {
+        if self.merkle_root() != merkle_root {
+            return Err(EntryProofError::UnmatchedRootHash);
+        }
+        Ok(self.value.as_ref())
+    }
+
+    /// Verifies the correctness of the proof by the trusted `state_hash` of a block.
+    ///
+    /// `table_proof` is the proof of the service table returned by
+    /// [`Schema::get_proof_to_service_table()`] and `table_key` is the key of the table
+    /// in the state of the blockchain, as returned by [`Blockchain::service_table_unique_key()`].
+    ///
+    /// If the both proofs are valid, the value of the entry is returned (`None` means
+    /// that the entry is empty). Otherwise, `Err` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofEntry, ProofMapIndex};
+    /// use exonum::crypto::{hash, Hash};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let table_key = hash(&[1]);
+    /// let (table_proof, state_hash) = {
+    ///     let mut entry = ProofEntry::new("entry", &mut fork);
+    ///     entry.set(10_u8);
+    ///     let table_hash = entry.merkle_root();
+    ///     // The state of the blockchain aggregated as in `Schema::state_hash_aggregator()`.
+    ///     let mut state: ProofMapIndex<_, Hash, Hash> = ProofMapIndex::new("state", &mut fork);
+    ///     state.put(&table_key, table_hash);
+    ///     (state.get_proof(table_key), state.merkle_root())
+    /// };
+    ///
+    /// let entry: ProofEntry<_, u8> = ProofEntry::new("entry", &fork);
+    /// let proof = entry.get_proof();
+    /// let value = proof.validate_to_state(table_proof, &table_key, state_hash).unwrap();
+    /// assert_eq!(value, Some(&10));
+    /// ```
+    ///
+    /// [`Schema::get_proof_to_service_table()`]:
+    /// ../../blockchain/struct.Schema.html#method.get_proof_to_service_table
+    /// [`Blockchain::service_table_unique_key()`]:
+    /// ../../blockchain/struct.Blockchain.html#method.service_table_unique_key
+    pub fn validate_to_state(
+        &self,
+        table_proof: MapProof<Hash, Hash>,
+        table_key: &Hash,
+        state_hash: Hash,
+    ) -> Result<Option<&V>, EntryProofError> {
+        let checked = table_proof
+            .check()
+            .map_err(EntryProofError::InvalidTableProof)?;
+        if checked.merkle_root() != state_hash {
+            return Err(EntryProofError::UnmatchedStateHash);
+        }
+        let table_hash = checked
+            .entries()
+            .into_iter()
+            .find(|&(key, _)| key == table_key)
+            .map(|(_, hash)| *hash)
+            .ok_or(EntryProofError::MissingTable)?;
+        self.validate(table_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Database, Entry, MemoryDB, ProofMapIndex};
+    use super::*;
+    use crypto::hash;
+
+    const INDEX_NAME: &str = "test_index_name";
+
+    #[test]
+    fn merkle_root_matches_entry() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+
+        {
+            let mut index = ProofEntry::new(INDEX_NAME, &mut fork);
+            assert_eq!(index.merkle_root(), Hash::zero());
+            index.set("value".to_owned());
+        }
+        {
+            let mut entry = Entry::new("entry", &mut fork);
+            entry.set("value".to_owned());
+        }
+
+        let index: ProofEntry<_, String> = ProofEntry::new(INDEX_NAME, &fork);
+        let entry: Entry<_, String> = Entry::new("entry", &fork);
+        assert_eq!(index.merkle_root(), entry.hash());
+        assert_ne!(index.merkle_root(), Hash::zero());
+    }
+
+    #[test]
+    fn proofs() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let mut index = ProofEntry::new(INDEX_NAME, &mut fork);
+
+        let proof = index.get_proof();
+        assert_eq!(proof.validate(index.merkle_root()).unwrap(), None);
+        assert!(proof.validate(hash(&[1])).is_err());
+
+        index.set(42_u64);
+        let proof = index.get_proof();
+        assert_eq!(proof.validate(index.merkle_root()).unwrap(), Some(&42));
+        assert_eq!(proof.value_unchecked(), Some(&42));
+
+        match proof.validate(Hash::zero()) {
+            Err(EntryProofError::UnmatchedRootHash) => {}
+            other => panic!("Unexpected validation result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn proofs_to_state() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let table_key = hash(&[0]);
+        let other_key = hash(&[1]);
+
+        let entry_proof = {
+            let mut index = ProofEntry::new(INDEX_NAME, &mut fork);
+            index.set(42_u64);
+            index.get_proof()
+        };
+        let mut state = ProofMapIndex::new("state", &mut fork);
+        state.put(&table_key, entry_proof.merkle_root());
+        state.put(&other_key, hash(&[2]));
+        let state_hash = state.merkle_root();
+
+        let value = entry_proof
+            .validate_to_state(state.get_proof(table_key), &table_key, state_hash)
+            .unwrap();
+        assert_eq!(value, Some(&42));
+
+        match entry_proof.validate_to_state(state.get_proof(table_key), &table_key, Hash::zero()) {
+            Err(EntryProofError::UnmatchedStateHash) => {}
+            other => panic!("Unexpected validation result: {:?}", other),
+        }
+        match entry_proof.validate_to_state(state.get_proof(hash(&[3])), &table_key, state_hash) {
+            Err(EntryProofError::MissingTable) => {}
+            other => panic!("Unexpected validation result: {:?}", other),
+        }
+        match entry_proof.validate_to_state(state.get_proof(other_key), &other_key, state_hash) {
+            Err(EntryProofError::UnmatchedRootHash) => {}
+            other => panic!("Unexpected validation result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn proof_serde() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let mut index = ProofEntry::new(INDEX_NAME, &mut fork);
+        index.set(hash(&[1]));
+
+        let proof = index.get_proof();
+        let json = ::serde_json::to_value(&proof).unwrap();
+        let restored: EntryProof<Hash> = ::serde_json::from_value(json).unwrap();
+        assert_eq!(restored, proof);
+    }
+}
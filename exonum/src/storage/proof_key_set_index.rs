@@ -0,0 +1,530 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An implementation of a Merkelized set of keys.
+
+use std::fmt;
+
+use super::{base_index::BaseIndex,
+            indexes_metadata::IndexType,
+            proof_map_index::{MapProof, ProofMapIndex, ProofMapIndexKeys, ProofMapKey},
+            Fork,
+            Snapshot,
+            StorageKey};
+use crypto::Hash;
+
+/// A Merkelized set of items that implement `ProofMapKey` trait.
+///
+/// `ProofKeySetIndex` stores the elements as leaves of a binary Merkle Patricia tree with
+/// empty values, in the same way [`ProofMapIndex`] stores its entries. Thus, the index
+/// provides proofs of membership and non-membership of the elements against
+/// [`merkle_root`] in the form of a [`MapProof`].
+///
+/// The same restrictions as for [`ProofMapIndex`] keys apply to the elements: their size must be
+/// exactly 32 bytes and they must have a uniform distribution.
+///
+/// [`ProofMapIndex`]: ../proof_map_index/struct.ProofMapIndex.html
+/// [`MapProof`]: ../proof_map_index/struct.MapProof.html
+/// [`merkle_root`]: #method.merkle_root
+pub struct ProofKeySetIndex<T, K> {
+    map: ProofMapIndex<T, K, ()>,
+}
+
+/// An iterator over the items of a `ProofKeySetIndex`.
+///
+/// This struct is created by the [`iter`] or
+/// [`iter_from`] methods on [`ProofKeySetIndex`]. See its documentation for more.
+///
+/// [`iter`]: struct.ProofKeySetIndex.html#method.iter
+/// [`iter_from`]: struct.ProofKeySetIndex.html#method.iter_from
+/// [`ProofKeySetIndex`]: struct.ProofKeySetIndex.html
+#[derive(Debug)]
+pub struct ProofKeySetIndexIter<'a, K> {
+    keys: ProofMapIndexKeys<'a, K>,
+}
+
+impl<T, K> ProofKeySetIndex<T, K>
+where
+    T: AsRef<Snapshot>,
+    K: ProofMapKey,
+{
+    /// Creates a new index representation based on the name and storage view.
+    ///
+    /// Storage view can be specified as [`&Snapshot`] or [`&mut Fork`]. In the first case only
+    /// immutable methods are available. In the second case both immutable and mutable methods are
+    /// available.
+    ///
+    /// [`&Snapshot`]: ../trait.Snapshot.html
+    /// [`&mut Fork`]: ../struct.Fork.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofKeySetIndex};
+    /// use exonum::crypto::Hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: ProofKeySetIndex<_, Hash> = ProofKeySetIndex::new(name, &snapshot);
+    ///
+    /// let mut fork = db.fork();
+    /// let mut mut_index: ProofKeySetIndex<_, Hash> = ProofKeySetIndex::new(name, &mut fork);
+    /// ```
+    pub fn new<S: AsRef<str>>(index_name: S, view: T) -> Self {
+        ProofKeySetIndex {
+            map: ProofMapIndex::from_base(BaseIndex::new(
+                index_name,
+                IndexType::ProofKeySet,
+                view,
+            )),
+        }
+    }
+
+    /// Creates a new index representation based on the name, common prefix of its keys
+    /// and storage view.
+    ///
+    /// Storage view can be specified as [`&Snapshot`] or [`&mut Fork`]. In the first case only
+    /// immutable methods are available. In the second case both immutable and mutable methods are
+    /// available.
+    ///
+    /// [`&Snapshot`]: ../trait.Snapshot.html
+    /// [`&mut Fork`]: ../struct.Fork.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofKeySetIndex};
+    /// use exonum::crypto::Hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let index_id = vec![01];
+    ///
+    /// let snapshot = db.snapshot();
+    /// let index: ProofKeySetIndex<_, Hash> =
+    ///     ProofKeySetIndex::new_in_family(name, &index_id, &snapshot);
+    ///
+    /// let mut fork = db.fork();
+    /// let mut mut_index: ProofKeySetIndex<_, Hash> =
+    ///     ProofKeySetIndex::new_in_family(name, &index_id, &mut fork);
+    /// ```
+    pub fn new_in_family<S: AsRef<str>, I: StorageKey>(
+        family_name: S,
+        index_id: &I,
+        view: T,
+    ) -> Self {
+        ProofKeySetIndex {
+            map: ProofMapIndex::from_base(BaseIndex::new_in_family(
+                family_name,
+                index_id,
+                IndexType::ProofKeySet,
+                view,
+            )),
+        }
+    }
+
+    /// Returns the root hash of the set or default hash value if it is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofKeySetIndex};
+    /// use exonum::crypto::Hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = ProofKeySetIndex::new("name", &mut fork);
+    ///
+    /// let default_hash = index.merkle_root();
+    /// assert_eq!(Hash::default(), default_hash);
+    ///
+    /// index.insert(&default_hash);
+    /// assert_ne!(index.merkle_root(), default_hash);
+    /// ```
+    pub fn merkle_root(&self) -> Hash {
+        self.map.merkle_root()
+    }
+
+    /// Returns `true` if the set contains the specified item.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofKeySetIndex};
+    /// use exonum::crypto::Hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = ProofKeySetIndex::new("name", &mut fork);
+    ///
+    /// let hash = Hash::default();
+    /// assert!(!index.contains(&hash));
+    ///
+    /// index.insert(&hash);
+    /// assert!(index.contains(&hash));
+    /// ```
+    pub fn contains(&self, item: &K) -> bool {
+        self.map.contains(item)
+    }
+
+    /// Returns the proof of membership or non-membership for the specified item.
+    ///
+    /// A member of the set is included into the proof as an entry with an empty value,
+    /// while a missing item is included as a missing key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofKeySetIndex};
+    /// use exonum::crypto::Hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = ProofKeySetIndex::new("name", &mut fork);
+    /// index.insert(&Hash::zero());
+    ///
+    /// let proof = index.get_proof(Hash::zero()).check().unwrap();
+    /// assert_eq!(proof.merkle_root(), index.merkle_root());
+    /// assert_eq!(proof.entries(), vec![(&Hash::zero(), &())]);
+    /// ```
+    pub fn get_proof(&self, item: K) -> MapProof<K, ()> {
+        self.map.get_proof(item)
+    }
+
+    /// Returns the combined proof of membership or non-membership for the multiple
+    /// specified items.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofKeySetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let snapshot = db.snapshot();
+    /// let index: ProofKeySetIndex<_, [u8; 32]> = ProofKeySetIndex::new("index", &snapshot);
+    ///
+    /// let proof = index.get_multiproof(vec![[0; 32], [1; 32]]);
+    /// ```
+    pub fn get_multiproof<KI>(&self, items: KI) -> MapProof<K, ()>
+    where
+        KI: IntoIterator<Item = K>,
+    {
+        self.map.get_multiproof(items)
+    }
+
+    /// Returns an iterator over the items of the set in the order of the underlying
+    /// Merkle Patricia tree. The iterator element type is `K::Output`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofKeySetIndex};
+    /// use exonum::crypto::Hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let snapshot = db.snapshot();
+    /// let index: ProofKeySetIndex<_, Hash> = ProofKeySetIndex::new("name", &snapshot);
+    ///
+    /// for item in index.iter() {
+    ///     println!("{:?}", item);
+    /// }
+    /// ```
+    pub fn iter(&self) -> ProofKeySetIndexIter<K> {
+        ProofKeySetIndexIter {
+            keys: self.map.keys(),
+        }
+    }
+
+    /// Returns an iterator over the items of the set starting from the specified item.
+    /// The iterator element type is `K::Output`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofKeySetIndex};
+    /// use exonum::crypto::Hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let snapshot = db.snapshot();
+    /// let index: ProofKeySetIndex<_, Hash> = ProofKeySetIndex::new("name", &snapshot);
+    ///
+    /// let hash = Hash::default();
+    /// for item in index.iter_from(&hash) {
+    ///     println!("{:?}", item);
+    /// }
+    /// ```
+    pub fn iter_from(&self, from: &K) -> ProofKeySetIndexIter<K> {
+        ProofKeySetIndexIter {
+            keys: self.map.keys_from(from),
+        }
+    }
+}
+
+impl<'a, K> ProofKeySetIndex<&'a mut Fork, K>
+where
+    K: ProofMapKey,
+{
+    /// Adds an item to the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofKeySetIndex};
+    /// use exonum::crypto::Hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = ProofKeySetIndex::new("name", &mut fork);
+    ///
+    /// let hash = Hash::default();
+    /// index.insert(&hash);
+    /// assert!(index.contains(&hash));
+    /// ```
+    pub fn insert(&mut self, item: &K) {
+        self.map.put(item, ())
+    }
+
+    /// Removes an item from the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofKeySetIndex};
+    /// use exonum::crypto::Hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = ProofKeySetIndex::new("name", &mut fork);
+    ///
+    /// let hash = Hash::default();
+    /// index.insert(&hash);
+    /// assert!(index.contains(&hash));
+    ///
+    /// index.remove(&hash);
+    /// assert!(!index.contains(&hash));
+    /// ```
+    pub fn remove(&mut self, item: &K) {
+        self.map.remove(item)
+    }
+
+    /// Clears the set, removing all items.
+    ///
+    /// # Notes
+    ///
+    /// Currently this method is not optimized to delete large set of data. During the execution of
+    /// this method the amount of allocated memory is linearly dependent on the number of elements
+    /// in the index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofKeySetIndex};
+    /// use exonum::crypto::Hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = ProofKeySetIndex::new("name", &mut fork);
+    ///
+    /// let hash = Hash::default();
+    /// index.insert(&hash);
+    /// assert!(index.contains(&hash));
+    ///
+    /// index.clear();
+    /// assert!(!index.contains(&hash));
+    /// ```
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+}
+
+impl<'a, T, K> ::std::iter::IntoIterator for &'a ProofKeySetIndex<T, K>
+where
+    T: AsRef<Snapshot>,
+    K: ProofMapKey,
+{
+    type Item = K::Output;
+    type IntoIter = ProofKeySetIndexIter<'a, K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K> Iterator for ProofKeySetIndexIter<'a, K>
+where
+    K: ProofMapKey,
+{
+    type Item = K::Output;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.keys.next()
+    }
+}
+
+impl<'a, K> DoubleEndedIterator for ProofKeySetIndexIter<'a, K>
+where
+    K: ProofMapKey,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.keys.next_back()
+    }
+}
+
+impl<T, K> fmt::Debug for ProofKeySetIndex<T, K>
+where
+    T: AsRef<Snapshot>,
+    K: ProofMapKey,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("ProofKeySetIndex").field(&self.map).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Database, MapIndex, MemoryDB, ProofMapIndex};
+    use super::*;
+    use crypto::{hash, CryptoHash};
+
+    const INDEX_NAME: &str = "test_index_name";
+
+    #[test]
+    fn insert_remove() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let mut index = ProofKeySetIndex::new(INDEX_NAME, &mut fork);
+
+        let items = [hash(&[1]), hash(&[2]), hash(&[3])];
+        for item in &items {
+            assert!(!index.contains(item));
+            index.insert(item);
+            assert!(index.contains(item));
+        }
+        assert_eq!(index.iter().count(), items.len());
+
+        index.remove(&items[1]);
+        assert!(!index.contains(&items[1]));
+        assert_eq!(index.iter().count(), items.len() - 1);
+
+        index.clear();
+        assert!(index.iter().next().is_none());
+        assert_eq!(index.merkle_root(), Hash::zero());
+    }
+
+    #[test]
+    fn merkle_root_matches_proof_map() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let items: Vec<_> = (0_u8..50).map(|i| hash(&[i])).collect();
+
+        let set_root = {
+            let mut index = ProofKeySetIndex::new("set", &mut fork);
+            for item in &items {
+                index.insert(item);
+            }
+            index.merkle_root()
+        };
+        let map_root = {
+            let mut index = ProofMapIndex::new("map", &mut fork);
+            for item in &items {
+                index.put(item, ());
+            }
+            index.merkle_root()
+        };
+        assert_eq!(set_root, map_root);
+        assert_ne!(set_root, Hash::zero());
+
+        let index: ProofKeySetIndex<_, Hash> = ProofKeySetIndex::new("set", &fork);
+        let mut iterated: Vec<_> = index.iter().collect();
+        iterated.sort();
+        let mut expected = items.clone();
+        expected.sort();
+        assert_eq!(iterated, expected);
+
+        let mut reversed: Vec<_> = index.iter().rev().collect();
+        reversed.reverse();
+        assert_eq!(reversed, index.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn membership_proofs() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let mut index = ProofKeySetIndex::new(INDEX_NAME, &mut fork);
+
+        let members: Vec<_> = (0_u8..20).map(|i| hash(&[i])).collect();
+        let strangers: Vec<_> = (20_u8..30).map(|i| hash(&[i])).collect();
+
+        let proof = index.get_proof(members[0]).check().unwrap();
+        assert_eq!(proof.merkle_root(), Hash::zero());
+        assert_eq!(proof.missing_keys(), vec![&members[0]]);
+
+        for item in &members {
+            index.insert(item);
+        }
+        let root = index.merkle_root();
+
+        for item in &members {
+            let proof = index.get_proof(*item).check().unwrap();
+            assert_eq!(proof.merkle_root(), root);
+            assert_eq!(proof.entries(), vec![(item, &())]);
+            assert!(proof.missing_keys().is_empty());
+        }
+        for item in &strangers {
+            let proof = index.get_proof(*item).check().unwrap();
+            assert_eq!(proof.merkle_root(), root);
+            assert!(proof.entries().is_empty());
+            assert_eq!(proof.missing_keys(), vec![item]);
+        }
+
+        let proof = index
+            .get_multiproof(vec![members[3], strangers[0], members[7]])
+            .check()
+            .unwrap();
+        assert_eq!(proof.merkle_root(), root);
+        assert_eq!(proof.entries().len(), 2);
+        assert_eq!(proof.missing_keys(), vec![&strangers[0]]);
+    }
+
+    #[test]
+    fn index_in_family() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let item = ().hash();
+
+        {
+            let mut index = ProofKeySetIndex::new_in_family(INDEX_NAME, &1_u8, &mut fork);
+            index.insert(&item);
+        }
+        let index: ProofKeySetIndex<_, Hash> =
+            ProofKeySetIndex::new_in_family(INDEX_NAME, &2_u8, &fork);
+        assert!(!index.contains(&item));
+        let index: ProofKeySetIndex<_, Hash> =
+            ProofKeySetIndex::new_in_family(INDEX_NAME, &1_u8, &fork);
+        assert!(index.contains(&item));
+    }
+
+    #[test]
+    #[should_panic(expected = "while said index was initially created with type ProofKeySet")]
+    fn index_type_conflict() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        {
+            let mut index = ProofKeySetIndex::new(INDEX_NAME, &mut fork);
+            index.insert(&Hash::zero());
+        }
+        let _map: MapIndex<_, Hash, ()> = MapIndex::new(INDEX_NAME, &fork);
+    }
+}
@@ -16,10 +16,10 @@
 
 pub use self::proof::{ListProof, ListProofError};
 
-use std::{cell::Cell, marker::PhantomData};
+use std::{cell::Cell, marker::PhantomData, ops::RangeBounds};
 
 use self::key::ProofListKey;
-use super::{base_index::{BaseIndex, BaseIndexIter},
+use super::{base_index::{bound_ref, map_bound, BaseIndex, BaseIndexIter},
             indexes_metadata::IndexType,
             Fork,
             Snapshot,
@@ -189,6 +189,37 @@ where
         }
     }
 
+    /// Constructs a proof for the elements at the specified positions, which must be
+    /// sorted in the increasing order and deduplicated.
+    fn construct_multiproof(&self, key: ProofListKey, indices: &[u64]) -> ListProof<V> {
+        if key.height() == 1 {
+            return ListProof::Leaf(self.get(key.index()).unwrap());
+        }
+        let middle = key.first_right_leaf_index();
+        let (left, right) = indices.split_at(
+            indices
+                .iter()
+                .position(|&index| index >= middle)
+                .unwrap_or_else(|| indices.len()),
+        );
+        if right.is_empty() {
+            ListProof::Left(
+                Box::new(self.construct_multiproof(key.left(), left)),
+                self.get_branch(key.right()),
+            )
+        } else if left.is_empty() {
+            ListProof::Right(
+                self.get_branch_unchecked(key.left()),
+                Box::new(self.construct_multiproof(key.right(), right)),
+            )
+        } else {
+            ListProof::Full(
+                Box::new(self.construct_multiproof(key.left(), left)),
+                Box::new(self.construct_multiproof(key.right(), right)),
+            )
+        }
+    }
+
     /// Returns an element at that position or `None` if out of bounds.
     ///
     /// # Examples
@@ -393,6 +424,93 @@ where
         self.construct_proof(self.root_key(), from, to)
     }
 
+    /// Returns the combined proof of existence for the list elements at the specified
+    /// positions. The positions do not need to be contiguous; the hashes shared by
+    /// the paths to the elements are included into the proof only once.
+    ///
+    /// The proof is verified with [`ListProof::validate()`], which returns the elements
+    /// in the increasing order of their positions. Duplicate positions are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `indices` is empty or if any of the positions is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofListIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofListIndex::new(name, &mut fork);
+    ///
+    /// index.extend(0..10);
+    ///
+    /// let proof = index.get_multiproof(vec![7, 2, 3]);
+    /// let elements = proof.validate(index.merkle_root(), index.len()).unwrap();
+    /// assert_eq!(elements, vec![(2, &2), (3, &3), (7, &7)]);
+    /// ```
+    ///
+    /// [`ListProof::validate()`]: enum.ListProof.html#method.validate
+    pub fn get_multiproof<I>(&self, indices: I) -> ListProof<V>
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        let mut indices: Vec<u64> = indices.into_iter().collect();
+        indices.sort_unstable();
+        indices.dedup();
+        match indices.last() {
+            None => panic!("Illegal indices: at least one index is required"),
+            Some(&last) if last >= self.len() => panic!(
+                "Index out of bounds: the len is {} but the index is {}",
+                self.len(),
+                last
+            ),
+            _ => {}
+        }
+        self.construct_multiproof(self.root_key(), &indices)
+    }
+
+    /// Returns the proof that the list with the first `old_len` elements of this list
+    /// is a prefix of this list, i.e., that the list has only been appended to since
+    /// it had `old_len` elements.
+    ///
+    /// The proof is the proof of existence of the last element of the old list; it is verified
+    /// with [`ListProof::validate_consistency()`] by the Merkle root hashes and lengths
+    /// of both lists.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `old_len` is zero or is greater than the length of the list.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofListIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofListIndex::new(name, &mut fork);
+    ///
+    /// index.extend([1, 2, 3, 4, 5].iter().cloned());
+    ///
+    /// let consistency_proof = index.get_consistency_proof(3);
+    /// ```
+    ///
+    /// [`ListProof::validate_consistency()`]: enum.ListProof.html#method.validate_consistency
+    pub fn get_consistency_proof(&self, old_len: u64) -> ListProof<V> {
+        if old_len == 0 || old_len > self.len() {
+            panic!(
+                "Illegal old length: the len is {:?}, but the old length is {:?}",
+                self.len(),
+                old_len
+            )
+        }
+        self.construct_proof(self.root_key(), old_len - 1, old_len)
+    }
+
     /// Returns an iterator over the list. The iterator element type is V.
     ///
     /// # Examples
@@ -437,6 +555,38 @@ where
             base_iter: self.base.iter_from(&0u8, &ProofListKey::leaf(from)),
         }
     }
+
+    /// Returns an iterator over the list items at the positions within the specified range.
+    /// The iterator element type is V.
+    ///
+    /// Like other iterators over the list, the returned iterator is double-ended, so the items
+    /// may be iterated in reverse order without scanning the entire range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofListIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofListIndex::new(name, &mut fork);
+    ///
+    /// index.extend([1u8, 2, 3, 4, 5].iter().cloned());
+    ///
+    /// let items: Vec<u8> = index.range(1..3).collect();
+    /// assert_eq!(items, vec![2, 3]);
+    /// let latest: Vec<u8> = index.iter().rev().take(2).collect();
+    /// assert_eq!(latest, vec![5, 4]);
+    /// ```
+    pub fn range<R: RangeBounds<u64>>(&self, range: R) -> ProofListIndexIter<V> {
+        let start = map_bound(range.start_bound(), |&index| ProofListKey::leaf(index));
+        let end = map_bound(range.end_bound(), |&index| ProofListKey::leaf(index));
+        ProofListIndexIter {
+            base_iter: self.base
+                .range(&0u8, bound_ref(&start), bound_ref(&end)),
+        }
+    }
 }
 
 impl<'a, V> ProofListIndex<&'a mut Fork, V>
@@ -614,3 +764,12 @@ where
         self.base_iter.next().map(|(_, v)| v)
     }
 }
+
+impl<'a, V> DoubleEndedIterator for ProofListIndexIter<'a, V>
+where
+    V: StorageValue,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.base_iter.next_back().map(|(_, v)| v)
+    }
+}
@@ -41,6 +41,12 @@ pub enum ListProofError {
     UnexpectedBranch,
     /// The hash of the proof is not equal to the trusted root hash.
     UnmatchedRootHash,
+    /// The proof is not a proof of the last element of the old list, or the lengths
+    /// of the lists are inconsistent.
+    InvalidConsistencyProof,
+    /// The hash of the old list restored from the proof is not equal to the trusted
+    /// old root hash.
+    UnmatchedOldRootHash,
 }
 
 impl<V: StorageValue> ListProof<V> {
@@ -71,6 +77,18 @@ impl<V: StorageValue> ListProof<V> {
         Ok(hash)
     }
 
+    /// Computes the hash of the subtree covered by the proof as if the list ended with
+    /// the single element of the proof.
+    fn prefix_hash(&self) -> Result<Hash, ListProofError> {
+        let hash = match *self {
+            Left(ref left, _) => hash(left.prefix_hash()?.as_ref()),
+            Right(ref left, ref right) => pair_hash(left, &right.prefix_hash()?),
+            Leaf(ref value) => value.hash(),
+            Full(..) => return Err(ListProofError::InvalidConsistencyProof),
+        };
+        Ok(hash)
+    }
+
     /// Verifies the correctness of the proof by the trusted Merkle root hash and the number of
     /// elements in the tree.
     ///
@@ -78,12 +96,75 @@ impl<V: StorageValue> ListProof<V> {
     /// Otherwise, `Err` is returned.
     pub fn validate(&self, merkle_root: Hash, len: u64) -> Result<Vec<(u64, &V)>, ListProofError> {
         let mut vec = Vec::new();
-        let height = len.next_power_of_two().trailing_zeros() as u8 + 1;
+        let height = tree_height(len);
         if self.collect(ProofListKey::new(height, 0), &mut vec)? != merkle_root {
             return Err(ListProofError::UnmatchedRootHash);
         }
         Ok(vec)
     }
+
+    /// Verifies that the list with the `old_root` hash and `old_len` elements is a prefix
+    /// of the list with the trusted `merkle_root` hash and `len` elements, i.e., that the list
+    /// has only been appended to. The proof should be obtained with
+    /// [`get_consistency_proof()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofListIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = ProofListIndex::new("index", &mut fork);
+    ///
+    /// index.extend([1, 2, 3].iter().cloned());
+    /// let (old_root, old_len) = (index.merkle_root(), index.len());
+    /// index.extend([4, 5].iter().cloned());
+    ///
+    /// let proof = index.get_consistency_proof(old_len);
+    /// assert!(
+    ///     proof
+    ///         .validate_consistency(old_root, old_len, index.merkle_root(), index.len())
+    ///         .is_ok()
+    /// );
+    /// ```
+    ///
+    /// [`get_consistency_proof()`]: struct.ProofListIndex.html#method.get_consistency_proof
+    pub fn validate_consistency(
+        &self,
+        old_root: Hash,
+        old_len: u64,
+        merkle_root: Hash,
+        len: u64,
+    ) -> Result<(), ListProofError> {
+        if old_len == 0 || old_len > len {
+            return Err(ListProofError::InvalidConsistencyProof);
+        }
+        let entries = self.validate(merkle_root, len)?;
+        if entries.len() != 1 || entries[0].0 != old_len - 1 {
+            return Err(ListProofError::InvalidConsistencyProof);
+        }
+
+        // The root of the old tree is located on the path to its last element;
+        // all nodes above it have the path going to the left.
+        let old_height = tree_height(old_len);
+        let mut proof = self;
+        for _ in old_height..tree_height(len) {
+            proof = match *proof {
+                Left(ref left, _) => left,
+                _ => return Err(ListProofError::InvalidConsistencyProof),
+            };
+        }
+        if proof.prefix_hash()? != old_root {
+            return Err(ListProofError::UnmatchedOldRootHash);
+        }
+        Ok(())
+    }
+}
+
+/// Returns the height of the Merkle tree for the list with the given number of elements.
+fn tree_height(len: u64) -> u8 {
+    len.next_power_of_two().trailing_zeros() as u8 + 1
 }
 
 impl<V: Serialize> Serialize for ListProof<V> {
@@ -15,7 +15,7 @@
 use rand::{thread_rng, Rng};
 
 use self::ListProof::*;
-use super::{pair_hash, ListProof, ProofListIndex};
+use super::{pair_hash, ListProof, ListProofError, ProofListIndex};
 use crypto::{hash, CryptoHash, Hash};
 use encoding::serialize::{json::reexport::{from_str, to_string},
                           reexport::Serialize};
@@ -289,6 +289,150 @@ fn randomly_generate_proofs(db: Box<Database>) {
     }
 }
 
+fn multiproofs(db: Box<Database>) {
+    let mut fork = db.fork();
+    let mut index = ProofListIndex::new(IDX_NAME, &mut fork);
+    let num_values = 100;
+    let values = random_values(num_values as usize);
+    index.extend(values.iter().cloned());
+    let table_merkle_root = index.merkle_root();
+    let mut rng = thread_rng();
+
+    for _ in 0..50 {
+        let count = rng.gen_range(1, 10);
+        let indices: Vec<u64> = (0..count).map(|_| rng.gen_range(0, num_values)).collect();
+        let proof = index.get_multiproof(indices.clone());
+
+        let mut expected_indices = indices;
+        expected_indices.sort();
+        expected_indices.dedup();
+        let (actual_indices, actual_values): (Vec<_>, Vec<_>) = proof
+            .validate(table_merkle_root, index.len())
+            .unwrap()
+            .into_iter()
+            .unzip();
+        assert_eq!(actual_indices, expected_indices);
+        for (&i, actual) in expected_indices.iter().zip(actual_values) {
+            assert_eq!(values[i as usize], *actual);
+        }
+
+        let json_representation = to_string(&proof).unwrap();
+        assert_eq!(proof, from_str(&json_representation).unwrap());
+    }
+
+    // Multiproofs for contiguous ranges coincide with range proofs.
+    assert_eq!(index.get_multiproof(3..17), index.get_range_proof(3, 17));
+    assert_eq!(index.get_multiproof(vec![99]), index.get_proof(99));
+    assert_eq!(
+        index.get_multiproof(0..num_values),
+        index.get_range_proof(0, num_values)
+    );
+
+    // The shared part of the paths is included once.
+    let proof = index.get_multiproof(vec![0, 1]);
+    let mut node = &proof;
+    while let Left(ref left, Some(_)) = *node {
+        node = left;
+    }
+    match *node {
+        Full(ref left, ref right) => {
+            assert_eq!(**left, Leaf(values[0].clone()));
+            assert_eq!(**right, Leaf(values[1].clone()));
+        }
+        ref other => panic!("Unexpected proof: {:?}", other),
+    }
+}
+
+fn multiproof_illegal_index(db: Box<Database>) {
+    let mut fork = db.fork();
+    let mut index = ProofListIndex::new(IDX_NAME, &mut fork);
+    index.extend(vec![vec![1], vec![2]]);
+    index.get_multiproof(vec![0, 2]);
+}
+
+fn consistency_proofs(db: Box<Database>) {
+    let mut fork = db.fork();
+    let mut index = ProofListIndex::new(IDX_NAME, &mut fork);
+    let values = random_values(40);
+
+    let mut roots = Vec::new();
+    for value in &values {
+        index.push(value.clone());
+        roots.push(index.merkle_root());
+    }
+
+    for len in 1..values.len() as u64 + 1 {
+        let (root, old_values) = (roots[len as usize - 1], &values[..len as usize]);
+        let mut fork = db.fork();
+        let mut index = ProofListIndex::new(IDX_NAME, &mut fork);
+        index.extend(old_values.iter().cloned());
+        assert_eq!(index.merkle_root(), root);
+
+        for old_len in 1..len + 1 {
+            let old_root = roots[old_len as usize - 1];
+            let proof = index.get_consistency_proof(old_len);
+            assert!(
+                proof
+                    .validate_consistency(old_root, old_len, root, len)
+                    .is_ok()
+            );
+
+            let json_representation = to_string(&proof).unwrap();
+            let deserialized_proof: ListProof<Vec<u8>> = from_str(&json_representation).unwrap();
+            assert_eq!(deserialized_proof, proof);
+
+            // Invalid old root.
+            match proof.validate_consistency(Hash::zero(), old_len, root, len) {
+                Err(ListProofError::UnmatchedOldRootHash) => {}
+                other => panic!("Unexpected result: {:?}", other),
+            }
+            // Invalid new root.
+            match proof.validate_consistency(old_root, old_len, Hash::zero(), len) {
+                Err(ListProofError::UnmatchedRootHash) => {}
+                other => panic!("Unexpected result: {:?}", other),
+            }
+            // The proof is not a proof for the other old length.
+            if old_len > 1 {
+                let other_root = roots[old_len as usize - 2];
+                assert!(
+                    proof
+                        .validate_consistency(other_root, old_len - 1, root, len)
+                        .is_err()
+                );
+            }
+        }
+        assert!(
+            index
+                .get_proof(0)
+                .validate_consistency(root, len + 1, root, len)
+                .is_err()
+        );
+    }
+
+    // A list modified not only by appending is inconsistent with its old version.
+    let old_root = index.merkle_root();
+    index.set(3, vec![1, 2, 3]);
+    index.push(vec![4, 5, 6]);
+    let proof = index.get_consistency_proof(values.len() as u64);
+    assert!(
+        proof
+            .validate_consistency(
+                old_root,
+                values.len() as u64,
+                index.merkle_root(),
+                index.len()
+            )
+            .is_err()
+    );
+}
+
+fn consistency_proof_illegal_length(db: Box<Database>) {
+    let mut fork = db.fork();
+    let mut index = ProofListIndex::new(IDX_NAME, &mut fork);
+    index.extend(vec![vec![1], vec![2]]);
+    index.get_consistency_proof(3);
+}
+
 fn index_and_proof_roots(db: Box<Database>) {
     let mut fork = db.fork();
     let mut index = ProofListIndex::new(IDX_NAME, &mut fork);
@@ -601,6 +745,40 @@ mod memorydb_tests {
         super::index_and_proof_roots(db);
     }
 
+    #[test]
+    fn test_multiproofs() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::multiproofs(db);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index out of bounds")]
+    fn test_multiproof_illegal_index() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::multiproof_illegal_index(db);
+    }
+
+    #[test]
+    fn test_consistency_proofs() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::consistency_proofs(db);
+    }
+
+    #[test]
+    #[should_panic(expected = "Illegal old length")]
+    fn test_consistency_proof_illegal_length() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::consistency_proof_illegal_length(db);
+    }
+
     #[test]
     #[should_panic]
     fn test_proof_illegal_lower_bound() {
@@ -656,6 +834,7 @@ mod memorydb_tests {
     }
 }
 
+#[cfg(feature = "rocksdb")]
 mod rocksdb_tests {
     use std::path::Path;
     use storage::{Database, DbOptions, RocksDB};
@@ -714,6 +893,40 @@ mod rocksdb_tests {
         super::index_and_proof_roots(db);
     }
 
+    #[test]
+    fn test_multiproofs() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::multiproofs(db);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index out of bounds")]
+    fn test_multiproof_illegal_index() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::multiproof_illegal_index(db);
+    }
+
+    #[test]
+    fn test_consistency_proofs() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::consistency_proofs(db);
+    }
+
+    #[test]
+    #[should_panic(expected = "Illegal old length")]
+    fn test_consistency_proof_illegal_length() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::consistency_proof_illegal_length(db);
+    }
+
     #[test]
     #[should_panic]
     fn test_proof_illegal_lower_bound() {
@@ -17,12 +17,12 @@
 pub use self::{key::{HashedKey, ProofMapKey, ProofPath, KEY_SIZE as PROOF_MAP_KEY_SIZE},
                proof::{CheckedMapProof, MapProof, MapProofError}};
 
-use std::{fmt, marker::PhantomData};
+use std::{fmt, marker::PhantomData, ops::RangeBounds};
 
 use self::{key::{BitsRange, ChildKind, LEAF_KEY_PREFIX},
            node::{BranchNode, Node},
-           proof::{create_multiproof, create_proof}};
-use super::{base_index::{BaseIndex, BaseIndexIter},
+           proof::{create_multiproof, create_proof, create_range_proof}};
+use super::{base_index::{bound_ref, map_bound, BaseIndex, BaseIndexIter},
             indexes_metadata::IndexType,
             Fork,
             Snapshot,
@@ -187,6 +187,18 @@ where
         }
     }
 
+    pub(crate) fn from_base(base: BaseIndex<T>) -> Self {
+        ProofMapIndex {
+            base,
+            _k: PhantomData,
+            _v: PhantomData,
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> &Snapshot {
+        self.base.snapshot()
+    }
+
     fn get_root_path(&self) -> Option<ProofPath> {
         self.base
             .iter::<_, ProofPath, _>(&())
@@ -329,6 +341,43 @@ where
         })
     }
 
+    /// Returns the proof of all entries with the keys between `from` and `to` (inclusive).
+    ///
+    /// Keys are compared by their [`ProofPath`]s in the order, in which the corresponding leaves
+    /// are located in the Merkle Patricia tree: bit by bit, starting from the least significant
+    /// bit of the first byte. As a consequence, the keys with a common prefix of whole bytes
+    /// form a continuous range. For [`HashedKey`]s, the hashes of the keys are compared.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofMapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = ProofMapIndex::new("index", &mut fork);
+    /// index.put(&[1; 32], 1u8);
+    /// index.put(&[2; 32], 2u8);
+    ///
+    /// // Prove that there are no keys starting with byte `3`.
+    /// let mut from = [0; 32];
+    /// from[0] = 3;
+    /// let mut to = [0xff; 32];
+    /// to[0] = 3;
+    /// let proof = index.get_range_proof(&from, &to).check().unwrap();
+    /// assert!(proof.entries().is_empty());
+    /// assert_eq!(proof.range(), Some((&from, &to)));
+    /// assert_eq!(proof.merkle_root(), index.merkle_root());
+    /// ```
+    ///
+    /// [`ProofPath`]: struct.ProofPath.html
+    /// [`HashedKey`]: trait.HashedKey.html
+    pub fn get_range_proof(&self, from: &K, to: &K) -> MapProof<K::Output, V> {
+        create_range_proof(from, to, self.get_root_node(), |path| {
+            self.get_node_unchecked(path)
+        })
+    }
+
     /// Returns an iterator over the entries of the map in ascending order. The iterator element
     /// type is `(K::Output, V)`.
     ///
@@ -479,6 +528,41 @@ where
             base_iter: self.base.iter_from(&LEAF_KEY_PREFIX, &ProofPath::new(from)),
         }
     }
+
+    /// Returns an iterator over the entries of the map with the keys within the specified range.
+    /// The iterator element type is `(K::Output, V)`.
+    ///
+    /// Entries are ordered by their [`ProofPath`]s, which coincides with the order
+    /// of the keys unless the keys are hashed (e.g., [`HashedKey`]s).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofMapIndex};
+    /// use exonum::crypto::Hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: ProofMapIndex<_, Hash, u8> = ProofMapIndex::new(name, &snapshot);
+    ///
+    /// let hash = Hash::default();
+    /// for val in index.range(hash..).rev() {
+    ///     println!("{:?}", val);
+    /// }
+    /// ```
+    ///
+    /// [`ProofPath`]: struct.ProofPath.html
+    /// [`HashedKey`]: trait.HashedKey.html
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> ProofMapIndexIter<K, V> {
+        let start = map_bound(range.start_bound(), ProofPath::new);
+        let end = map_bound(range.end_bound(), ProofPath::new);
+        ProofMapIndexIter {
+            base_iter: self.base
+                .range(&LEAF_KEY_PREFIX, bound_ref(&start), bound_ref(&end)),
+            _k: PhantomData,
+        }
+    }
 }
 
 impl<'a, K, V> ProofMapIndex<&'a mut Fork, K, V>
@@ -486,6 +570,10 @@ where
     K: ProofMapKey,
     V: StorageValue,
 {
+    pub(crate) fn fork(&mut self) -> &mut Fork {
+        self.base.fork()
+    }
+
     fn insert_leaf(&mut self, key: &ProofPath, value: V) -> Hash {
         debug_assert!(key.is_leaf());
         let hash = value.hash();
@@ -786,6 +874,18 @@ where
     }
 }
 
+impl<'a, K, V> DoubleEndedIterator for ProofMapIndexIter<'a, K, V>
+where
+    K: ProofMapKey,
+    V: StorageValue,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.base_iter
+            .next_back()
+            .map(|(k, v)| (K::read_key(k.raw_key()), v))
+    }
+}
+
 impl<'a, K> Iterator for ProofMapIndexKeys<'a, K>
 where
     K: ProofMapKey,
@@ -797,6 +897,15 @@ where
     }
 }
 
+impl<'a, K> DoubleEndedIterator for ProofMapIndexKeys<'a, K>
+where
+    K: ProofMapKey,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.base_iter.next_back().map(|(k, _)| K::read_key(k.raw_key()))
+    }
+}
+
 impl<'a, V> Iterator for ProofMapIndexValues<'a, V>
 where
     V: StorageValue,
@@ -808,6 +917,15 @@ where
     }
 }
 
+impl<'a, V> DoubleEndedIterator for ProofMapIndexValues<'a, V>
+where
+    V: StorageValue,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.base_iter.next_back().map(|(_, v)| v)
+    }
+}
+
 impl<T, K, V> fmt::Debug for ProofMapIndex<T, K, V>
 where
     T: AsRef<Snapshot>,
@@ -116,6 +116,14 @@ pub enum MapProofError {
     /// Entries in the proof are not ordered by increasing path.
     #[fail(display = "invalid path ordering")]
     InvalidOrdering(ProofPath, ProofPath),
+
+    /// An entry of a range proof lies outside of the range.
+    #[fail(display = "entry outside of the proven range")]
+    EntryOutOfRange(ProofPath),
+
+    /// A hashed subtree of a range proof may contain keys within the range.
+    #[fail(display = "incomplete range proof")]
+    IncompleteRange(ProofPath),
 }
 
 // Used instead of `(ProofPath, Hash)` only for the purpose of clearer (de)serialization.
@@ -164,6 +172,28 @@ impl<K, V> OptionalEntry<K, V> {
     }
 }
 
+/// Inclusive bounds of the keys in a range proof.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct KeyRange<K> {
+    from: K,
+    to: K,
+}
+
+impl<K: ProofMapKey> KeyRange<K> {
+    fn paths(&self) -> (ProofPath, ProofPath) {
+        (ProofPath::new(&self.from), ProofPath::new(&self.to))
+    }
+}
+
+/// Checks if the subtree with the given path contains no keys in the inclusive range
+/// between `from` and `to`.
+///
+/// Paths are compared in the order of the tree traversal, so the subtree is disjoint
+/// with the range iff it lies entirely before `from` or entirely after `to`.
+fn is_disjoint(path: &ProofPath, from: &ProofPath, to: &ProofPath) -> bool {
+    (path < from && !from.starts_with(path)) || path > to
+}
+
 impl<K, V> From<(K, Option<V>)> for OptionalEntry<K, V> {
     fn from(value: (K, Option<V>)) -> Self {
         match value {
@@ -247,14 +277,26 @@ impl<K, V> Into<(K, Option<V>)> for OptionalEntry<K, V> {
 /// # }
 /// ```
 ///
+/// # Range proofs
+///
+/// A proof created with [`get_range_proof()`] additionally asserts that its entries are
+/// *all* the entries of the index with the keys in the specified range; [`check()`] fails
+/// if the proof does not show this. Range proofs can be used to prove that there are no keys
+/// with a certain prefix: the range should be set from the prefix padded with `0` bytes
+/// to the prefix padded with `0xff` bytes. A range proof is serialized with an additional
+/// `range` field, which is a `{ "from": K, "to": K }` object.
+///
 /// [`get_proof()`]: struct.ProofMapIndex.html#method.get_proof
 /// [`get_multiproof()`]: struct.ProofMapIndex.html#method.get_multiproof
+/// [`get_range_proof()`]: struct.ProofMapIndex.html#method.get_range_proof
 /// [`check()`]: #method.check
 /// [`ProofPath`]: struct.ProofPath.html
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MapProof<K, V> {
     entries: Vec<OptionalEntry<K, V>>,
     proof: Vec<MapProofEntry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    range: Option<KeyRange<K>>,
 }
 
 /// Version of `MapProof` obtained after verification.
@@ -266,6 +308,8 @@ pub struct MapProof<K, V> {
 pub struct CheckedMapProof<K, V> {
     entries: Vec<(K, Option<V>)>,
     hash: Hash,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    range: Option<(K, K)>,
 }
 
 /// Computes the root hash of the Merkle Patricia tree backing the specified entries
@@ -381,6 +425,7 @@ fn collect(entries: &[MapProofEntry]) -> Result<Hash, MapProofError> {
 pub(super) struct MapProofBuilder<K, V> {
     entries: Vec<OptionalEntry<K, V>>,
     proof: Vec<MapProofEntry>,
+    range: Option<KeyRange<K>>,
 }
 
 impl<K, V> MapProofBuilder<K, V> {
@@ -389,9 +434,16 @@ impl<K, V> MapProofBuilder<K, V> {
         MapProofBuilder {
             entries: vec![],
             proof: vec![],
+            range: None,
         }
     }
 
+    /// Marks the proof as a range proof for the keys between `from` and `to` (inclusive).
+    pub fn set_range(mut self, from: K, to: K) -> Self {
+        self.range = Some(KeyRange { from, to });
+        self
+    }
+
     /// Adds an existing entry into the builder.
     pub fn add_entry(mut self, key: K, value: V) -> Self {
         self.entries.push(OptionalEntry::value(key, value));
@@ -435,6 +487,7 @@ impl<K, V> MapProofBuilder<K, V> {
         MapProof {
             entries: self.entries,
             proof: self.proof,
+            range: self.range,
         }
     }
 }
@@ -454,6 +507,12 @@ impl<K, V> MapProof<K, V> {
     pub fn missing_keys_unchecked(&self) -> Vec<&K> {
         self.entries.iter().filter_map(|e| e.as_missing()).collect()
     }
+
+    /// Returns the bounds of the key range if this is a range proof.
+    /// This method does not perform any integrity checks of the proof.
+    pub fn range_unchecked(&self) -> Option<(&K, &K)> {
+        self.range.as_ref().map(|range| (&range.from, &range.to))
+    }
 }
 
 impl<K, V> MapProof<K, V>
@@ -516,6 +575,21 @@ where
             }
         }
 
+        if let Some(ref range) = self.range {
+            let (from, to) = range.paths();
+            for e in &self.proof {
+                if !is_disjoint(&e.path, &from, &to) {
+                    return Err(IncompleteRange(e.path));
+                }
+            }
+            for (key, _) in self.entries.iter().filter_map(|e| e.as_kv()) {
+                let path = ProofPath::new(key);
+                if path < from || path > to {
+                    return Err(EntryOutOfRange(path));
+                }
+            }
+        }
+
         Ok(())
     }
 
@@ -543,7 +617,7 @@ where
     /// [`ProofMapIndex`]: struct.ProofMapIndex.html
     pub fn check(self) -> Result<CheckedMapProof<K, V>, MapProofError> {
         self.precheck()?;
-        let (mut proof, entries) = (self.proof, self.entries);
+        let (mut proof, entries, range) = (self.proof, self.entries, self.range);
 
         proof.extend(entries.iter().filter_map(|e| {
             e.as_kv().map(|(k, v)| MapProofEntry {
@@ -573,6 +647,7 @@ where
         collect(&proof).map(|h| CheckedMapProof {
             entries: entries.into_iter().map(OptionalEntry::into).collect(),
             hash: h,
+            range: range.map(|range| (range.from, range.to)),
         })
     }
 }
@@ -613,6 +688,14 @@ impl<K, V> CheckedMapProof<K, V> {
     pub fn merkle_root(&self) -> Hash {
         self.hash
     }
+
+    /// Returns the bounds of the key range if the proof is a range proof. In this case,
+    /// [`entries()`] are all the entries of the map with the keys within the range.
+    ///
+    /// [`entries()`]: #method.entries
+    pub fn range(&self) -> Option<(&K, &K)> {
+        self.range.as_ref().map(|&(ref from, ref to)| (from, to))
+    }
 }
 
 /// Creates a proof for a single key.
@@ -900,3 +983,92 @@ where
             .create(),
     }
 }
+
+/// Creates a proof for all entries with the keys between `from` and `to` (inclusive).
+pub fn create_range_proof<K, V, F>(
+    from: &K,
+    to: &K,
+    root_node: Option<(ProofPath, Node<V>)>,
+    lookup: F,
+) -> MapProof<K::Output, V>
+where
+    K: ProofMapKey,
+    V: StorageValue,
+    F: Fn(&ProofPath) -> Node<V>,
+{
+    /// Adds the subtree with the given root to the proof, descending into the subtree
+    /// if it may contain keys within the range.
+    fn visit<K, V, F>(
+        builder: MapProofBuilder<K::Output, V>,
+        path: ProofPath,
+        hash: Hash,
+        range: (&ProofPath, &ProofPath),
+        lookup: &F,
+    ) -> MapProofBuilder<K::Output, V>
+    where
+        K: ProofMapKey,
+        V: StorageValue,
+        F: Fn(&ProofPath) -> Node<V>,
+    {
+        if is_disjoint(&path, range.0, range.1) {
+            return builder.add_proof_entry(path, hash);
+        }
+        match lookup(&path) {
+            Node::Leaf(value) => builder.add_entry(K::read_key(path.raw_key()), value),
+            Node::Branch(branch) => visit_children::<K, V, F>(builder, &branch, range, lookup),
+        }
+    }
+
+    fn visit_children<K, V, F>(
+        builder: MapProofBuilder<K::Output, V>,
+        branch: &BranchNode,
+        range: (&ProofPath, &ProofPath),
+        lookup: &F,
+    ) -> MapProofBuilder<K::Output, V>
+    where
+        K: ProofMapKey,
+        V: StorageValue,
+        F: Fn(&ProofPath) -> Node<V>,
+    {
+        let builder = visit::<K, V, F>(
+            builder,
+            branch.child_path(ChildKind::Left),
+            *branch.child_hash(ChildKind::Left),
+            range,
+            lookup,
+        );
+        visit::<K, V, F>(
+            builder,
+            branch.child_path(ChildKind::Right),
+            *branch.child_hash(ChildKind::Right),
+            range,
+            lookup,
+        )
+    }
+
+    let (from, to) = (ProofPath::new(from), ProofPath::new(to));
+    let builder =
+        MapProofBuilder::new().set_range(K::read_key(from.raw_key()), K::read_key(to.raw_key()));
+
+    match root_node {
+        // Both children of the root are always added to the proof, even if the entire tree
+        // is outside of the range.
+        Some((_, Node::Branch(root_branch))) => {
+            visit_children::<K, V, F>(builder, &root_branch, (&from, &to), &lookup).create()
+        }
+
+        Some((root_path, Node::Leaf(root_value))) => {
+            if is_disjoint(&root_path, &from, &to) {
+                builder
+                    .add_proof_entry(root_path, root_value.hash())
+                    .create()
+            } else {
+                builder
+                    .add_entry(K::read_key(root_path.raw_key()), root_value)
+                    .create()
+            }
+        }
+
+        None => builder.create(),
+    }
+}
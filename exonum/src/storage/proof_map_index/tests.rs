@@ -516,6 +516,35 @@ fn test_invalid_map_proofs() {
         DuplicatePath(..) => {}
         e => panic!("expected duplicate path error, got {}", e),
     }
+
+    let proof: MapProof<[u8; 32], Vec<u8>> = MapProofBuilder::new()
+        .set_range([1; 32], [1; 32])
+        .add_proof_entry(ProofPath::new(&[0; 32]).prefix(10), h)
+        .add_proof_entry(ProofPath::new(&[1; 32]), h)
+        .create();
+    match proof.check().unwrap_err() {
+        IncompleteRange(..) => {}
+        e => panic!("expected incomplete range error, got {}", e),
+    }
+
+    let proof: MapProof<[u8; 32], Vec<u8>> = MapProofBuilder::new()
+        .set_range([1; 32], [3; 32])
+        .add_proof_entry(ProofPath::new(&[1; 32]).prefix(3), h)
+        .create();
+    match proof.check().unwrap_err() {
+        IncompleteRange(..) => {}
+        e => panic!("expected incomplete range error, got {}", e),
+    }
+
+    let proof: MapProof<[u8; 32], Vec<u8>> = MapProofBuilder::new()
+        .set_range([1; 32], [1; 32])
+        .add_proof_entry(ProofPath::new(&[0; 32]).prefix(10), h)
+        .add_entry([2; 32], vec![1, 2, 3])
+        .create();
+    match proof.check().unwrap_err() {
+        EntryOutOfRange(..) => {}
+        e => panic!("expected entry out of range error, got {}", e),
+    }
 }
 
 fn build_proof_in_empty_tree(db: Box<Database>) {
@@ -1007,6 +1036,105 @@ fn fuzz_insert_build_multiproofs(db: Box<Database>) {
     }
 }
 
+fn check_range_proof<K, V>(from: K, to: K, table: &ProofMapIndex<&mut Fork, K, V>)
+where
+    K: ProofMapKey<Output = K> + Copy + PartialEq + Debug + Serialize + DeserializeOwned,
+    V: StorageValue + PartialEq + Debug + Serialize + DeserializeOwned,
+{
+    let (from_path, to_path) = (ProofPath::new(&from), ProofPath::new(&to));
+    let mut entries: Vec<(K, V)> = table
+        .iter()
+        .filter(|&(ref key, _)| {
+            let path = ProofPath::new(key);
+            path >= from_path && path <= to_path
+        })
+        .collect();
+    entries.sort_unstable_by(|&(ref x, _), &(ref y, _)| {
+        ProofPath::new(x).partial_cmp(&ProofPath::new(y)).unwrap()
+    });
+
+    let proof = table.get_range_proof(&from, &to);
+    assert_eq!(proof.range_unchecked(), Some((&from, &to)));
+    let serialized_proof = serde_json::to_value(&proof).unwrap();
+    let deserialized_proof: MapProof<K, V> = serde_json::from_value(serialized_proof).unwrap();
+
+    let proof = proof.check().unwrap();
+    assert_eq!(proof.merkle_root(), table.merkle_root());
+    assert_eq!(proof.range(), Some((&from, &to)));
+    assert!(proof.missing_keys().is_empty());
+    assert_eq!(
+        proof.entries(),
+        entries
+            .iter()
+            .map(|&(ref k, ref v)| (k, v))
+            .collect::<Vec<_>>()
+    );
+
+    let deserialized_proof = deserialized_proof.check().unwrap();
+    assert_eq!(deserialized_proof.entries(), proof.entries());
+    assert_eq!(deserialized_proof.range(), proof.range());
+    assert_eq!(deserialized_proof.merkle_root(), proof.merkle_root());
+}
+
+fn build_range_proofs_in_small_trees(db: Box<Database>) {
+    let mut storage = db.fork();
+    let mut table = ProofMapIndex::new(IDX_NAME, &mut storage);
+
+    table.put(&[1; 32], vec![1]);
+    table.remove(&[1; 32]);
+    check_range_proof([0; 32], [255; 32], &table);
+
+    table.put(&[1; 32], vec![1]);
+    check_range_proof([0; 32], [255; 32], &table);
+    check_range_proof([1; 32], [1; 32], &table);
+    check_range_proof([2; 32], [3; 32], &table);
+
+    table.put(&[2; 32], vec![2]);
+    check_range_proof([0; 32], [255; 32], &table);
+    check_range_proof([1; 32], [1; 32], &table);
+    check_range_proof([2; 32], [2; 32], &table);
+    check_range_proof([3; 32], [3; 32], &table);
+    // `[2; 32]` precedes `[1; 32]` in the tree, since the least significant bit is zero.
+    check_range_proof([2; 32], [1; 32], &table);
+    check_range_proof([1; 32], [2; 32], &table);
+}
+
+fn fuzz_insert_build_range_proofs(db: Box<Database>) {
+    let mut rng: XorShiftRng = rand::random();
+    let data = generate_random_data_keys(200, &mut rng);
+
+    let mut storage = db.fork();
+    let mut table = ProofMapIndex::new(IDX_NAME, &mut storage);
+    for &(ref key, ref value) in &data {
+        table.put(key, value.clone());
+    }
+    check_range_proof([0; 32], [255; 32], &table);
+
+    for _ in 0..50 {
+        let mut bounds = sample_iter(&mut rng, data.iter().map(|&(k, _)| k), 2).unwrap();
+        bounds.sort_unstable_by(|x, y| ProofPath::new(x).partial_cmp(&ProofPath::new(y)).unwrap());
+        check_range_proof(bounds[0], bounds[1], &table);
+
+        let (mut from, mut to) = ([0; 32], [0; 32]);
+        rng.fill_bytes(&mut from);
+        rng.fill_bytes(&mut to);
+        if ProofPath::new(&from) > ProofPath::new(&to) {
+            ::std::mem::swap(&mut from, &mut to);
+        }
+        check_range_proof(from, to, &table);
+
+        // Range of the keys with a common first byte.
+        let mut to = from;
+        for byte in to.iter_mut().skip(1) {
+            *byte = 255;
+        }
+        for byte in from.iter_mut().skip(1) {
+            *byte = 0;
+        }
+        check_range_proof(from, to, &table);
+    }
+}
+
 fn fuzz_delete_build_proofs(db: Box<Database>) {
     const SAMPLE_SIZE: usize = 200;
 
@@ -1330,6 +1458,8 @@ macro_rules! common_tests {
         }
         test_on_db!{test_fuzz_insert_build_proofs, fuzz_insert_build_proofs}
         test_on_db!{test_fuzz_insert_build_multiproofs, fuzz_insert_build_multiproofs}
+        test_on_db!{test_build_range_proofs_in_small_trees, build_range_proofs_in_small_trees}
+        test_on_db!{test_fuzz_insert_build_range_proofs, fuzz_insert_build_range_proofs}
         test_on_db!{test_fuzz_delete_build_proofs, fuzz_delete_build_proofs}
         test_on_2dbs!{test_fuzz_delete, fuzz_delete}
         test_on_db!{test_fuzz_insert_after_delete, fuzz_insert_after_delete}
@@ -1350,6 +1480,7 @@ mod memorydb_tests {
     common_tests!{}
 }
 
+#[cfg(feature = "rocksdb")]
 mod rocksdb_tests {
     use std::path::Path;
     use storage::{Database, DbOptions, RocksDB};
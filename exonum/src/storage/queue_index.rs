@@ -0,0 +1,492 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An implementation of a first-in, first-out queue of items.
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use std::{borrow::Cow, cell::Cell, marker::PhantomData, mem};
+
+use super::{base_index::{BaseIndex, BaseIndexIter},
+            indexes_metadata::IndexType,
+            Fork,
+            Snapshot,
+            StorageKey,
+            StorageValue};
+use crypto::{hash, CryptoHash, Hash};
+
+/// Positions of the first item and of the item following the last one.
+#[derive(Debug, Clone, Copy)]
+struct QueueState {
+    head: u64,
+    tail: u64,
+}
+
+impl CryptoHash for QueueState {
+    fn hash(&self) -> Hash {
+        hash(&self.into_bytes())
+    }
+}
+
+impl StorageValue for QueueState {
+    fn into_bytes(self) -> Vec<u8> {
+        let mut buf = vec![0; 2 * mem::size_of::<u64>()];
+        LittleEndian::write_u64(&mut buf[0..8], self.head);
+        LittleEndian::write_u64(&mut buf[8..16], self.tail);
+        buf
+    }
+
+    fn from_bytes(value: Cow<[u8]>) -> Self {
+        QueueState {
+            head: LittleEndian::read_u64(&value[0..8]),
+            tail: LittleEndian::read_u64(&value[8..16]),
+        }
+    }
+}
+
+/// A first-in, first-out queue of items that implement `StorageValue` trait.
+///
+/// `QueueIndex` stores the elements as values using their `u64` positions as keys.
+/// The items are added to the back of the queue and removed from its front in
+/// constant time; the space occupied by the removed items is reclaimed.
+/// `QueueIndex` requires that the elements implement the [`StorageValue`] trait.
+///
+/// [`StorageValue`]: ../trait.StorageValue.html
+#[derive(Debug)]
+pub struct QueueIndex<T, V> {
+    base: BaseIndex<T>,
+    state: Cell<Option<QueueState>>,
+    _v: PhantomData<V>,
+}
+
+/// An iterator over the items of a `QueueIndex`.
+///
+/// This struct is created by the [`iter`] method on [`QueueIndex`].
+/// See its documentation for more.
+///
+/// [`iter`]: struct.QueueIndex.html#method.iter
+/// [`QueueIndex`]: struct.QueueIndex.html
+#[derive(Debug)]
+pub struct QueueIndexIter<'a, V> {
+    base_iter: BaseIndexIter<'a, u64, V>,
+}
+
+impl<T, V> QueueIndex<T, V>
+where
+    T: AsRef<Snapshot>,
+    V: StorageValue,
+{
+    /// Creates a new index representation based on the name and storage view.
+    ///
+    /// Storage view can be specified as [`&Snapshot`] or [`&mut Fork`]. In the first case only
+    /// immutable methods are available. In the second case both immutable and mutable methods are
+    /// available.
+    ///
+    /// [`&Snapshot`]: ../trait.Snapshot.html
+    /// [`&mut Fork`]: ../struct.Fork.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, QueueIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: QueueIndex<_, u8> = QueueIndex::new(name, &snapshot);
+    ///
+    /// let mut fork = db.fork();
+    /// let mut mut_index: QueueIndex<_, u8> = QueueIndex::new(name, &mut fork);
+    /// ```
+    pub fn new<S: AsRef<str>>(index_name: S, view: T) -> Self {
+        QueueIndex {
+            base: BaseIndex::new(index_name, IndexType::Queue, view),
+            state: Cell::new(None),
+            _v: PhantomData,
+        }
+    }
+
+    /// Creates a new index representation based on the name, common prefix of its keys
+    /// and storage view.
+    ///
+    /// Storage view can be specified as [`&Snapshot`] or [`&mut Fork`]. In the first case only
+    /// immutable methods are available. In the second case both immutable and mutable methods are
+    /// available.
+    ///
+    /// [`&Snapshot`]: ../trait.Snapshot.html
+    /// [`&mut Fork`]: ../struct.Fork.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, QueueIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let index_id = vec![01];
+    ///
+    /// let snapshot = db.snapshot();
+    /// let index: QueueIndex<_, u8> = QueueIndex::new_in_family(name, &index_id, &snapshot);
+    ///
+    /// let mut fork = db.fork();
+    /// let mut mut_index: QueueIndex<_, u8> =
+    ///     QueueIndex::new_in_family(name, &index_id, &mut fork);
+    /// ```
+    pub fn new_in_family<S: AsRef<str>, I: StorageKey>(
+        family_name: S,
+        index_id: &I,
+        view: T,
+    ) -> Self {
+        QueueIndex {
+            base: BaseIndex::new_in_family(family_name, index_id, IndexType::Queue, view),
+            state: Cell::new(None),
+            _v: PhantomData,
+        }
+    }
+
+    fn state(&self) -> QueueState {
+        if let Some(state) = self.state.get() {
+            return state;
+        }
+        let state = self.base
+            .get(&())
+            .unwrap_or(QueueState { head: 0, tail: 0 });
+        self.state.set(Some(state));
+        state
+    }
+
+    /// Returns the item at the front of the queue or `None` if the queue is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, QueueIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = QueueIndex::new("name", &mut fork);
+    /// assert_eq!(None, index.peek());
+    ///
+    /// index.push_back(1);
+    /// index.push_back(2);
+    /// assert_eq!(Some(1), index.peek());
+    /// ```
+    pub fn peek(&self) -> Option<V> {
+        let state = self.state();
+        if state.head == state.tail {
+            None
+        } else {
+            self.base.get(&state.head)
+        }
+    }
+
+    /// Returns `true` if the queue has no items.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, QueueIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = QueueIndex::new("name", &mut fork);
+    /// assert!(index.is_empty());
+    ///
+    /// index.push_back(10);
+    /// assert!(!index.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of items in the queue.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, QueueIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = QueueIndex::new("name", &mut fork);
+    /// assert_eq!(0, index.len());
+    ///
+    /// index.push_back(10);
+    /// assert_eq!(1, index.len());
+    /// ```
+    pub fn len(&self) -> u64 {
+        let state = self.state();
+        state.tail - state.head
+    }
+
+    /// Returns an iterator over the queue from its front to its back. The iterator
+    /// element type is `V`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, QueueIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let snapshot = db.snapshot();
+    /// let index: QueueIndex<_, u8> = QueueIndex::new("name", &snapshot);
+    ///
+    /// for val in index.iter() {
+    ///     println!("{}", val);
+    /// }
+    /// ```
+    pub fn iter(&self) -> QueueIndexIter<V> {
+        QueueIndexIter {
+            base_iter: self.base.iter_from(&(), &self.state().head),
+        }
+    }
+}
+
+impl<'a, V> QueueIndex<&'a mut Fork, V>
+where
+    V: StorageValue,
+{
+    fn set_state(&mut self, state: QueueState) {
+        if state.head == state.tail {
+            self.base.remove(&());
+        } else {
+            self.base.put(&(), state);
+        }
+        self.state.set(Some(state));
+    }
+
+    /// Adds an item to the back of the queue.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, QueueIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = QueueIndex::new("name", &mut fork);
+    ///
+    /// index.push_back(1);
+    /// assert!(!index.is_empty());
+    /// ```
+    pub fn push_back(&mut self, value: V) {
+        let mut state = self.state();
+        self.base.put(&state.tail, value);
+        state.tail += 1;
+        self.set_state(state)
+    }
+
+    /// Removes the item from the front of the queue and returns it, or `None`
+    /// if the queue is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, QueueIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = QueueIndex::new("name", &mut fork);
+    /// assert_eq!(None, index.pop_front());
+    ///
+    /// index.push_back(1);
+    /// index.push_back(2);
+    /// assert_eq!(Some(1), index.pop_front());
+    /// assert_eq!(Some(2), index.pop_front());
+    /// assert_eq!(None, index.pop_front());
+    /// ```
+    pub fn pop_front(&mut self) -> Option<V> {
+        let mut state = self.state();
+        if state.head == state.tail {
+            return None;
+        }
+        let value = self.base.get(&state.head);
+        self.base.remove(&state.head);
+        state.head += 1;
+        self.set_state(state);
+        value
+    }
+
+    /// Extends the queue with the contents of an iterator, adding the items to its back.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, QueueIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = QueueIndex::new("name", &mut fork);
+    ///
+    /// index.extend([1, 2, 3].iter().cloned());
+    /// assert_eq!(3, index.len());
+    /// ```
+    pub fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = V>,
+    {
+        let mut state = self.state();
+        for value in iter {
+            self.base.put(&state.tail, value);
+            state.tail += 1;
+        }
+        self.set_state(state);
+    }
+
+    /// Clears the queue, removing all items.
+    ///
+    /// # Notes
+    ///
+    /// Currently this method is not optimized to delete large set of data. During the execution of
+    /// this method the amount of allocated memory is linearly dependent on the number of elements
+    /// in the index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, QueueIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = QueueIndex::new("name", &mut fork);
+    ///
+    /// index.push_back(1);
+    /// assert!(!index.is_empty());
+    ///
+    /// index.clear();
+    /// assert!(index.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.state.set(Some(QueueState { head: 0, tail: 0 }));
+        self.base.clear()
+    }
+}
+
+impl<'a, T, V> ::std::iter::IntoIterator for &'a QueueIndex<T, V>
+where
+    T: AsRef<Snapshot>,
+    V: StorageValue,
+{
+    type Item = V;
+    type IntoIter = QueueIndexIter<'a, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, V> Iterator for QueueIndexIter<'a, V>
+where
+    V: StorageValue,
+{
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.base_iter.next().map(|(.., v)| v)
+    }
+}
+
+impl<'a, V> DoubleEndedIterator for QueueIndexIter<'a, V>
+where
+    V: StorageValue,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.base_iter.next_back().map(|(.., v)| v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Database, MemoryDB};
+    use super::*;
+
+    const INDEX_NAME: &str = "test_index_name";
+
+    #[test]
+    fn fifo_order() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let mut index = QueueIndex::new(INDEX_NAME, &mut fork);
+
+        index.extend(vec![1_u32, 2, 3]);
+        index.push_back(4);
+        assert_eq!(index.len(), 4);
+        assert_eq!(index.peek(), Some(1));
+        assert_eq!(index.iter().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+        assert_eq!(index.iter().rev().collect::<Vec<_>>(), vec![4, 3, 2, 1]);
+
+        assert_eq!(index.pop_front(), Some(1));
+        assert_eq!(index.pop_front(), Some(2));
+        index.push_back(5);
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.peek(), Some(3));
+        assert_eq!(index.iter().collect::<Vec<_>>(), vec![3, 4, 5]);
+
+        assert_eq!(index.pop_front(), Some(3));
+        assert_eq!(index.pop_front(), Some(4));
+        assert_eq!(index.pop_front(), Some(5));
+        assert_eq!(index.pop_front(), None);
+        assert!(index.is_empty());
+        assert_eq!(index.peek(), None);
+        assert_eq!(index.iter().next(), None);
+    }
+
+    #[test]
+    fn space_is_reclaimed() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        {
+            let mut index = QueueIndex::new(INDEX_NAME, &mut fork);
+            for i in 0_u64..100 {
+                index.push_back(i);
+                if i % 2 == 1 {
+                    index.pop_front();
+                }
+            }
+            assert_eq!(index.len(), 50);
+            while index.pop_front().is_some() {}
+        }
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        let mut iter = snapshot.iter(INDEX_NAME, &[]);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn state_is_persisted() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        {
+            let mut index = QueueIndex::new(INDEX_NAME, &mut fork);
+            index.extend(vec![1_u8, 2, 3]);
+            index.pop_front();
+        }
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        let index: QueueIndex<_, u8> = QueueIndex::new(INDEX_NAME, &snapshot);
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.peek(), Some(2));
+        assert_eq!(index.iter().collect::<Vec<_>>(), vec![2, 3]);
+
+        let mut fork = db.fork();
+        let mut index: QueueIndex<_, u8> = QueueIndex::new_in_family("family", &1_u8, &mut fork);
+        assert!(index.is_empty());
+        index.push_back(1);
+        index.clear();
+        assert!(index.is_empty());
+        assert_eq!(index.pop_front(), None);
+    }
+}
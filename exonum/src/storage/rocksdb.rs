@@ -19,11 +19,35 @@
 pub use rocksdb::{BlockBasedOptions as RocksBlockOptions, WriteOptions as RocksDBWriteOptions};
 
 use exonum_profiler::ProfilerSpan;
-use rocksdb::{self, utils::get_cf_names, DBIterator, Options as RocksDbOptions, WriteBatch};
+use rocksdb::{self,
+              backup::{BackupEngine, BackupEngineOptions, RestoreOptions},
+              checkpoint::Checkpoint,
+              utils::get_cf_names,
+              ColumnFamilyDescriptor,
+              DBCompressionType,
+              DBIterator,
+              Options as RocksDbOptions,
+              WriteBatch};
 
-use std::{error::Error, fmt, iter::Peekable, mem, path::Path, sync::Arc};
+use std::{collections::Bound::{self, Excluded, Included, Unbounded},
+          error::Error,
+          fmt,
+          fs,
+          iter::Peekable,
+          mem,
+          path::{Path, PathBuf},
+          sync::Arc};
 
-use storage::{self, db::Change, Database, DbOptions, Iter, Iterator, Patch, Snapshot};
+use storage::{self,
+              db::{is_within_upper_bound, Change},
+              CompressionType,
+              Database,
+              DbOptions,
+              IndexOptions,
+              Iter,
+              Iterator,
+              Patch,
+              Snapshot};
 
 impl From<rocksdb::Error> for storage::Error {
     fn from(err: rocksdb::Error) -> storage::Error {
@@ -34,6 +58,22 @@ impl From<rocksdb::Error> for storage::Error {
 /// Database implementation on the top of `RocksDB` backend.
 pub struct RocksDB {
     db: Arc<rocksdb::DB>,
+    path: PathBuf,
+    options: DbOptions,
+}
+
+impl From<CompressionType> for DBCompressionType {
+    fn from(compression_type: CompressionType) -> Self {
+        match compression_type {
+            CompressionType::None => DBCompressionType::None,
+            CompressionType::Snappy => DBCompressionType::Snappy,
+            CompressionType::Zlib => DBCompressionType::Zlib,
+            CompressionType::Bz2 => DBCompressionType::Bz2,
+            CompressionType::Lz4 => DBCompressionType::Lz4,
+            CompressionType::Lz4hc => DBCompressionType::Lz4hc,
+            CompressionType::Zstd => DBCompressionType::Zstd,
+        }
+    }
 }
 
 impl DbOptions {
@@ -41,8 +81,59 @@ impl DbOptions {
         let mut defaults = RocksDbOptions::default();
         defaults.create_if_missing(self.create_if_missing);
         defaults.set_max_open_files(self.max_open_files.unwrap_or(-1));
+        if let Some(size) = self.max_total_wal_size {
+            defaults.set_max_total_wal_size(size);
+        }
+        if let Some(compactions) = self.max_background_compactions {
+            defaults.increase_parallelism(compactions);
+            defaults.set_max_background_compactions(compactions);
+        }
+        self.apply_index_options(&mut defaults, &IndexOptions::default());
         defaults
     }
+
+    /// Returns the options of the column family with the specified name.
+    fn to_rocksdb_cf(&self, name: &str) -> RocksDbOptions {
+        let mut options = self.to_rocksdb();
+        if let Some(index_options) = self.indexes.get(name) {
+            self.apply_index_options(&mut options, index_options);
+        }
+        options
+    }
+
+    fn apply_index_options(&self, options: &mut RocksDbOptions, overrides: &IndexOptions) {
+        if let Some(compression_type) = overrides.compression_type.or(self.compression_type) {
+            options.set_compression_type(compression_type.into());
+        }
+        if let Some(size) = overrides.write_buffer_size.or(self.write_buffer_size) {
+            options.set_write_buffer_size(size);
+        }
+
+        let block_cache_size = overrides
+            .block_cache_size
+            .or(self.block_cache_size_per_cf);
+        let bloom_filter_bits = overrides.bloom_filter_bits.or(self.bloom_filter_bits);
+        if block_cache_size.is_some() || bloom_filter_bits.is_some() {
+            let mut block_options = RocksBlockOptions::default();
+            if let Some(size) = block_cache_size {
+                block_options.set_lru_cache(size);
+            }
+            if let Some(bits) = bloom_filter_bits {
+                block_options.set_bloom_filter(bits, false);
+            }
+            options.set_block_based_table_factory(&block_options);
+        }
+    }
+
+    fn write_options(&self, sync: bool) -> RocksDBWriteOptions {
+        let mut w_opts = RocksDBWriteOptions::default();
+        if sync {
+            w_opts.set_sync(true);
+        } else if self.disable_wal {
+            w_opts.disable_wal(true);
+        }
+        w_opts
+    }
 }
 
 /// A snapshot of a `RocksDB`.
@@ -61,25 +152,110 @@ struct RocksDBIterator {
 impl RocksDB {
     /// Open a database stored in the specified path with the specified options.
     pub fn open<P: AsRef<Path>>(path: P, options: &DbOptions) -> storage::Result<RocksDB> {
+        let path = path.as_ref().to_path_buf();
         let db = {
             if let Ok(names) = get_cf_names(&path) {
-                let cf_names = names.iter().map(|name| name.as_str()).collect::<Vec<_>>();
-                rocksdb::DB::open_cf(&options.to_rocksdb(), path, cf_names.as_ref())?
+                // Existing column families are opened with their own options, so that
+                // the overrides of individual indexes survive restarts.
+                let descriptors = names
+                    .iter()
+                    .map(|name| {
+                        ColumnFamilyDescriptor::new(name.as_str(), options.to_rocksdb_cf(name))
+                    })
+                    .collect();
+                rocksdb::DB::open_cf_descriptors(&options.to_rocksdb(), &path, descriptors)?
             } else {
-                rocksdb::DB::open(&options.to_rocksdb(), path)?
+                rocksdb::DB::open(&options.to_rocksdb(), &path)?
             }
         };
-        Ok(RocksDB { db: Arc::new(db) })
+        Ok(RocksDB {
+            db: Arc::new(db),
+            path,
+            options: options.clone(),
+        })
+    }
+
+    /// Returns the names of the column families stored in the database.
+    pub(crate) fn column_families(&self) -> storage::Result<Vec<String>> {
+        get_cf_names(&self.path)
+            .map_err(|_| storage::Error::new("Unable to list column families of the database"))
+    }
+
+    /// Creates a new backup of the database in the backup directory at the specified path.
+    ///
+    /// Backups are managed by the `RocksDB` backup engine: the directory keeps all backups
+    /// created in it, and the files not changed since the previous backup are shared
+    /// rather than copied again. The database may keep merging changes while the backup
+    /// is being created. If the write-ahead log is disabled, the changes which have not
+    /// been flushed to the disk yet are not included into the backup.
+    ///
+    /// Use [`restore_backup`] to restore the database from the latest backup.
+    ///
+    /// [`restore_backup`]: #method.restore_backup
+    pub fn create_backup<P: AsRef<Path>>(&self, backup_path: P) -> storage::Result<()> {
+        let _p = ProfilerSpan::new("RocksDB::create_backup");
+        let mut engine = BackupEngine::open(&BackupEngineOptions::default(), backup_path)?;
+        engine.create_new_backup(&self.db)?;
+        Ok(())
+    }
+
+    /// Restores the database at `path` from the latest backup in the backup directory
+    /// created by [`create_backup`].
+    ///
+    /// The database is restored into a new directory, so `path` must not exist. If
+    /// the restoration fails, the partially restored database is removed.
+    ///
+    /// [`create_backup`]: #method.create_backup
+    pub fn restore_backup<P: AsRef<Path>, Q: AsRef<Path>>(
+        backup_path: P,
+        path: Q,
+    ) -> storage::Result<()> {
+        let path = path.as_ref();
+        if path.exists() {
+            return Err(storage::Error::new(format!(
+                "Unable to restore the database at {}: path already exists",
+                path.display()
+            )));
+        }
+
+        let result = BackupEngine::open(&BackupEngineOptions::default(), backup_path)
+            .and_then(|mut engine| {
+                engine.restore_from_latest_backup(path, path, &RestoreOptions::default())
+            });
+        if result.is_err() {
+            let _ = fs::remove_dir_all(path);
+        }
+        result.map_err(Into::into)
+    }
+
+    fn do_create_checkpoint(&self, path: &Path) -> storage::Result<()> {
+        let _p = ProfilerSpan::new("RocksDB::create_checkpoint");
+        if path.exists() {
+            return Err(storage::Error::new(format!(
+                "Unable to create a checkpoint at {}: path already exists",
+                path.display()
+            )));
+        }
+
+        // The checkpoint hard-links the immutable table files of the database, so it takes
+        // little time and space if the checkpoint is on the same file system.
+        let result =
+            Checkpoint::new(&self.db).and_then(|checkpoint| checkpoint.create_checkpoint(path));
+        if result.is_err() {
+            let _ = fs::remove_dir_all(path);
+        }
+        result.map_err(Into::into)
     }
 
     fn do_merge(&self, patch: Patch, w_opts: &RocksDBWriteOptions) -> storage::Result<()> {
         let _p = ProfilerSpan::new("RocksDB::merge");
+        storage::metrics::record_merge(&patch);
         let mut batch = WriteBatch::default();
         for (cf_name, changes) in patch {
             let cf = match self.db.cf_handle(&cf_name) {
                 Some(cf) => cf,
                 None => self.db
-                    .create_cf(&cf_name, &DbOptions::default().to_rocksdb())
+                    .create_cf(&cf_name, &self.options.to_rocksdb_cf(&cf_name))
                     .unwrap(),
             };
             for (key, change) in changes {
@@ -103,17 +279,24 @@ impl Database for RocksDB {
     }
 
     fn merge(&self, patch: Patch) -> storage::Result<()> {
-        let w_opts = RocksDBWriteOptions::default();
+        let w_opts = self.options.write_options(false);
         self.do_merge(patch, &w_opts)
     }
 
     fn merge_sync(&self, patch: Patch) -> storage::Result<()> {
-        let mut w_opts = RocksDBWriteOptions::default();
-        w_opts.set_sync(true);
+        let w_opts = self.options.write_options(true);
         self.do_merge(patch, &w_opts)
     }
+
+    fn create_checkpoint(&self, path: &Path) -> storage::Result<()> {
+        self.do_create_checkpoint(path)
+    }
 }
 
+// RocksDB snapshots are immutable and can be read from several threads concurrently.
+unsafe impl Send for RocksDBSnapshot {}
+unsafe impl Sync for RocksDBSnapshot {}
+
 impl Snapshot for RocksDBSnapshot {
     fn get(&self, name: &str, key: &[u8]) -> Option<Vec<u8>> {
         let _p = ProfilerSpan::new("RocksDBSnapshot::get");
@@ -142,6 +325,38 @@ impl Snapshot for RocksDBSnapshot {
             value: None,
         })
     }
+
+    fn iter_rev<'a>(&'a self, name: &str, to: Bound<&[u8]>) -> Iter<'a> {
+        use rocksdb::{Direction, IteratorMode};
+        let _p = ProfilerSpan::new("RocksDBSnapshot::iter_rev");
+        let mut iter = match self._db.cf_handle(name) {
+            Some(cf) => {
+                let mode = match to {
+                    Included(key) | Excluded(key) => IteratorMode::From(key, Direction::Reverse),
+                    Unbounded => IteratorMode::End,
+                };
+                self.snapshot.iterator_cf(cf, mode).unwrap().peekable()
+            }
+            None => self.snapshot.iterator(IteratorMode::End).peekable(),
+        };
+        // Depending on the seek semantics, the iterator may be positioned at the least key
+        // greater than or equal to the bound, so such keys are skipped.
+        loop {
+            let exceeds_bound = match iter.peek() {
+                Some(&(ref key, _)) => !is_within_upper_bound(key, to),
+                None => false,
+            };
+            if !exceeds_bound {
+                break;
+            }
+            iter.next();
+        }
+        Box::new(RocksDBIterator {
+            iter,
+            key: None,
+            value: None,
+        })
+    }
 }
 
 impl Iterator for RocksDBIterator {
@@ -0,0 +1,613 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Map indexes with automatically maintained secondary indexes.
+//!
+//! A secondary index is declared with a key extractor, i.e., a closure which returns
+//! the secondary key for an entry of the map. [`IndexedMapIndex`] and [`IndexedProofMapIndex`]
+//! update all declared secondary indexes within the same `Fork` when an entry is put into
+//! or removed from the map, and allow to look up the entries by a secondary key.
+//!
+//! The secondary index named `index` of the map named `map` is stored as a family
+//! of [`KeySetIndex`]es named `map.index`. The family contains the keys of the map entries
+//! for each secondary key, so the entries with the same secondary key are iterated
+//! in the ascending order of their primary keys.
+//!
+//! # Examples
+//!
+//! ```
+//! use exonum::storage::{MemoryDB, Database, IndexedMapIndex};
+//!
+//! let db = MemoryDB::new();
+//! let mut fork = db.fork();
+//! let mut users = IndexedMapIndex::new("users", &mut fork)
+//!     .with_index("by_city", |_id: &u64, address: &String| {
+//!         address.split(',').next().unwrap().to_owned()
+//!     });
+//!
+//! users.put(&1, "Berlin, Unter den Linden".to_owned());
+//! users.put(&2, "Paris, Rue de Rivoli".to_owned());
+//! users.put(&3, "Berlin, Kurfuerstendamm".to_owned());
+//! assert_eq!(users.keys_by("by_city", "Berlin"), vec![1, 3]);
+//!
+//! users.put(&1, "Paris, Champs-Elysees".to_owned());
+//! users.remove(&2);
+//! assert_eq!(users.keys_by("by_city", "Berlin"), vec![3]);
+//! assert_eq!(
+//!     users.iter_by("by_city", "Paris").collect::<Vec<_>>(),
+//!     vec![(1, "Paris, Champs-Elysees".to_owned())]
+//! );
+//! ```
+//!
+//! [`IndexedMapIndex`]: struct.IndexedMapIndex.html
+//! [`IndexedProofMapIndex`]: struct.IndexedProofMapIndex.html
+//! [`KeySetIndex`]: ../key_set_index/struct.KeySetIndex.html
+
+use std::{borrow::Borrow, fmt, vec};
+
+use super::{proof_map_index::ProofMapKey,
+            Fork,
+            KeySetIndex,
+            MapIndex,
+            ProofMapIndex,
+            Snapshot,
+            StorageKey,
+            StorageValue};
+use crypto::{hash, Hash};
+
+type KeyExtractor<K, V> = Box<Fn(&K, &V) -> Hash>;
+
+/// Secondary indexes of a map, identified by their names.
+struct SecondaryIndexes<K, V> {
+    map_name: String,
+    indexes: Vec<(String, KeyExtractor<K, V>)>,
+}
+
+/// An iterator over the entries of a map with the specified secondary key.
+///
+/// This struct is created by the `iter_by` method on [`IndexedMapIndex`] or
+/// [`IndexedProofMapIndex`]. See their documentation for more.
+///
+/// [`IndexedMapIndex`]: struct.IndexedMapIndex.html
+/// [`IndexedProofMapIndex`]: struct.IndexedProofMapIndex.html
+pub struct SecondaryIndexIter<'a, K: StorageKey + 'a, V: 'a> {
+    keys: vec::IntoIter<K::Owned>,
+    get: Box<Fn(&K) -> Option<V> + 'a>,
+}
+
+/// A `MapIndex` with automatically maintained secondary indexes.
+///
+/// See the [module documentation](index.html) for more details.
+#[derive(Debug)]
+pub struct IndexedMapIndex<T, K, V> {
+    map: MapIndex<T, K, V>,
+    secondary: SecondaryIndexes<K, V>,
+}
+
+/// A `ProofMapIndex` with automatically maintained secondary indexes.
+///
+/// Only the primary map is Merkelized; the secondary indexes do not affect its
+/// `merkle_root` and are not covered by the proofs.
+///
+/// See the [module documentation](index.html) for more details.
+#[derive(Debug)]
+pub struct IndexedProofMapIndex<T, K, V> {
+    map: ProofMapIndex<T, K, V>,
+    secondary: SecondaryIndexes<K, V>,
+}
+
+fn key_bytes<K: StorageKey + ?Sized>(key: &K) -> Vec<u8> {
+    let mut buf = vec![0; key.size()];
+    key.write(&mut buf);
+    buf
+}
+
+impl<K, V> SecondaryIndexes<K, V>
+where
+    K: StorageKey,
+{
+    fn new(map_name: &str) -> Self {
+        SecondaryIndexes {
+            map_name: map_name.to_owned(),
+            indexes: Vec::new(),
+        }
+    }
+
+    fn add<S, F>(&mut self, index_name: &str, extractor: F)
+    where
+        S: StorageKey + 'static,
+        F: Fn(&K, &V) -> S + 'static,
+        K: 'static,
+        V: 'static,
+    {
+        assert!(
+            !self.is_defined(index_name),
+            "secondary index `{}` is already defined",
+            index_name
+        );
+        let extractor = move |key: &K, value: &V| hash(&key_bytes(&extractor(key, value)));
+        self.indexes
+            .push((index_name.to_owned(), Box::new(extractor)));
+    }
+
+    fn is_defined(&self, index_name: &str) -> bool {
+        self.indexes.iter().any(|&(ref name, _)| name == index_name)
+    }
+
+    fn family_name(&self, index_name: &str) -> String {
+        format!("{}.{}", self.map_name, index_name)
+    }
+
+    fn insert(&self, fork: &mut Fork, key: &K, value: &V) {
+        let primary_key = key_bytes(key);
+        for &(ref name, ref extractor) in &self.indexes {
+            let mut index: KeySetIndex<_, Vec<u8>> = KeySetIndex::new_in_family(
+                self.family_name(name),
+                &extractor(key, value),
+                &mut *fork,
+            );
+            index.insert(primary_key.clone());
+        }
+    }
+
+    fn remove(&self, fork: &mut Fork, key: &K, value: &V) {
+        let primary_key = key_bytes(key);
+        for &(ref name, ref extractor) in &self.indexes {
+            let mut index: KeySetIndex<_, Vec<u8>> = KeySetIndex::new_in_family(
+                self.family_name(name),
+                &extractor(key, value),
+                &mut *fork,
+            );
+            index.remove(&primary_key);
+        }
+    }
+
+    fn keys<S>(&self, view: &Snapshot, index_name: &str, secondary_key: &S) -> Vec<K::Owned>
+    where
+        S: StorageKey + ?Sized,
+    {
+        assert!(
+            self.is_defined(index_name),
+            "no secondary index named `{}`",
+            index_name
+        );
+        let index: KeySetIndex<_, Vec<u8>> = KeySetIndex::new_in_family(
+            self.family_name(index_name),
+            &hash(&key_bytes(secondary_key)),
+            view,
+        );
+        index.iter().map(|key| K::read(&key)).collect()
+    }
+}
+
+impl<K, V> fmt::Debug for SecondaryIndexes<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<_> = self.indexes.iter().map(|&(ref name, _)| name).collect();
+        f.debug_struct("SecondaryIndexes")
+            .field("map_name", &self.map_name)
+            .field("indexes", &names)
+            .finish()
+    }
+}
+
+impl<T, K, V> IndexedMapIndex<T, K, V>
+where
+    T: AsRef<Snapshot>,
+    K: StorageKey,
+    V: StorageValue,
+{
+    /// Creates a new index representation based on the name and storage view.
+    /// The index has no secondary indexes until they are declared with [`with_index`].
+    ///
+    /// Storage view can be specified as [`&Snapshot`] or [`&mut Fork`]. In the first case only
+    /// immutable methods are available. In the second case both immutable and mutable methods are
+    /// available.
+    ///
+    /// [`with_index`]: #method.with_index
+    /// [`&Snapshot`]: ../trait.Snapshot.html
+    /// [`&mut Fork`]: ../struct.Fork.html
+    pub fn new<S: AsRef<str>>(index_name: S, view: T) -> Self {
+        IndexedMapIndex {
+            secondary: SecondaryIndexes::new(index_name.as_ref()),
+            map: MapIndex::new(index_name, view),
+        }
+    }
+
+    /// Declares a secondary index with the specified name and key extractor.
+    ///
+    /// The secondary indexes are not stored in the database, so they should be declared
+    /// each time the index is created; usually this is done in the schema of a service.
+    /// The existing entries of the map are not indexed by a newly declared index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the secondary index with the same name is already declared.
+    pub fn with_index<S, F>(mut self, index_name: &str, extractor: F) -> Self
+    where
+        S: StorageKey + 'static,
+        F: Fn(&K, &V) -> S + 'static,
+        K: 'static,
+        V: 'static,
+    {
+        self.secondary.add(index_name, extractor);
+        self
+    }
+
+    /// Returns the primary map, which can be used for reading and iterating over its entries.
+    pub fn primary(&self) -> &MapIndex<T, K, V> {
+        &self.map
+    }
+
+    /// Returns a value corresponding to the key.
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.map.get(key)
+    }
+
+    /// Returns `true` if the map contains a value for the specified key.
+    pub fn contains(&self, key: &K) -> bool {
+        self.map.contains(key)
+    }
+
+    /// Returns the keys of the entries with the specified secondary key in the ascending order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the secondary index with the specified name is not declared.
+    pub fn keys_by<S>(&self, index_name: &str, secondary_key: &S) -> Vec<K::Owned>
+    where
+        S: StorageKey + ?Sized,
+    {
+        self.secondary
+            .keys(self.map.snapshot(), index_name, secondary_key)
+    }
+
+    /// Returns an iterator over the entries with the specified secondary key in the ascending
+    /// order of keys. The iterator element type is `(K::Owned, V)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the secondary index with the specified name is not declared.
+    pub fn iter_by<S>(&self, index_name: &str, secondary_key: &S) -> SecondaryIndexIter<K, V>
+    where
+        S: StorageKey + ?Sized,
+    {
+        let map = &self.map;
+        SecondaryIndexIter {
+            keys: self.keys_by(index_name, secondary_key).into_iter(),
+            get: Box::new(move |key: &K| map.get(key)),
+        }
+    }
+}
+
+impl<'a, K, V> IndexedMapIndex<&'a mut Fork, K, V>
+where
+    K: StorageKey,
+    V: StorageValue,
+{
+    /// Inserts the key-value pair into the map and updates the secondary indexes.
+    pub fn put(&mut self, key: &K, value: V) {
+        let old_value = self.map.get(key);
+        {
+            let fork = self.map.fork();
+            if let Some(ref old_value) = old_value {
+                self.secondary.remove(fork, key, old_value);
+            }
+            self.secondary.insert(fork, key, &value);
+        }
+        self.map.put(key, value);
+    }
+
+    /// Removes the key from the map and updates the secondary indexes.
+    pub fn remove(&mut self, key: &K) {
+        if let Some(old_value) = self.map.get(key) {
+            self.secondary.remove(self.map.fork(), key, &old_value);
+            self.map.remove(key);
+        }
+    }
+
+    /// Clears the map and the secondary indexes.
+    ///
+    /// # Notes
+    ///
+    /// Currently this method is not optimized to delete large set of data. During the execution of
+    /// this method the amount of allocated memory is linearly dependent on the number of elements
+    /// in the index.
+    pub fn clear(&mut self) {
+        let entries: Vec<(K::Owned, V)> = self.map.iter().collect();
+        for &(ref key, ref value) in &entries {
+            self.secondary
+                .remove(self.map.fork(), key.borrow(), value);
+        }
+        self.map.clear();
+    }
+}
+
+impl<T, K, V> IndexedProofMapIndex<T, K, V>
+where
+    T: AsRef<Snapshot>,
+    K: ProofMapKey<Output = K> + StorageKey,
+    V: StorageValue,
+{
+    /// Creates a new index representation based on the name and storage view.
+    /// The index has no secondary indexes until they are declared with [`with_index`].
+    ///
+    /// Storage view can be specified as [`&Snapshot`] or [`&mut Fork`]. In the first case only
+    /// immutable methods are available. In the second case both immutable and mutable methods are
+    /// available.
+    ///
+    /// [`with_index`]: #method.with_index
+    /// [`&Snapshot`]: ../trait.Snapshot.html
+    /// [`&mut Fork`]: ../struct.Fork.html
+    pub fn new<S: AsRef<str>>(index_name: S, view: T) -> Self {
+        IndexedProofMapIndex {
+            secondary: SecondaryIndexes::new(index_name.as_ref()),
+            map: ProofMapIndex::new(index_name, view),
+        }
+    }
+
+    /// Declares a secondary index with the specified name and key extractor.
+    ///
+    /// The secondary indexes are not stored in the database, so they should be declared
+    /// each time the index is created; usually this is done in the schema of a service.
+    /// The existing entries of the map are not indexed by a newly declared index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the secondary index with the same name is already declared.
+    pub fn with_index<S, F>(mut self, index_name: &str, extractor: F) -> Self
+    where
+        S: StorageKey + 'static,
+        F: Fn(&K, &V) -> S + 'static,
+        K: 'static,
+        V: 'static,
+    {
+        self.secondary.add(index_name, extractor);
+        self
+    }
+
+    /// Returns the primary map, which can be used for reading, iterating over its entries
+    /// and building proofs.
+    pub fn primary(&self) -> &ProofMapIndex<T, K, V> {
+        &self.map
+    }
+
+    /// Returns a value corresponding to the key.
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.map.get(key)
+    }
+
+    /// Returns `true` if the map contains a value for the specified key.
+    pub fn contains(&self, key: &K) -> bool {
+        self.map.contains(key)
+    }
+
+    /// Returns the root hash of the primary map.
+    pub fn merkle_root(&self) -> Hash {
+        self.map.merkle_root()
+    }
+
+    /// Returns the keys of the entries with the specified secondary key in the ascending order
+    /// of their binary representation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the secondary index with the specified name is not declared.
+    pub fn keys_by<S>(&self, index_name: &str, secondary_key: &S) -> Vec<K::Owned>
+    where
+        S: StorageKey + ?Sized,
+    {
+        self.secondary
+            .keys(self.map.snapshot(), index_name, secondary_key)
+    }
+
+    /// Returns an iterator over the entries with the specified secondary key in the ascending
+    /// order of the binary representation of keys. The iterator element type is
+    /// `(K::Owned, V)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the secondary index with the specified name is not declared.
+    pub fn iter_by<S>(&self, index_name: &str, secondary_key: &S) -> SecondaryIndexIter<K, V>
+    where
+        S: StorageKey + ?Sized,
+    {
+        let map = &self.map;
+        SecondaryIndexIter {
+            keys: self.keys_by(index_name, secondary_key).into_iter(),
+            get: Box::new(move |key: &K| map.get(key)),
+        }
+    }
+}
+
+impl<'a, K, V> IndexedProofMapIndex<&'a mut Fork, K, V>
+where
+    K: ProofMapKey<Output = K> + StorageKey,
+    V: StorageValue,
+{
+    /// Inserts the key-value pair into the map and updates the secondary indexes.
+    pub fn put(&mut self, key: &K, value: V) {
+        let old_value = self.map.get(key);
+        {
+            let fork = self.map.fork();
+            if let Some(ref old_value) = old_value {
+                self.secondary.remove(fork, key, old_value);
+            }
+            self.secondary.insert(fork, key, &value);
+        }
+        self.map.put(key, value);
+    }
+
+    /// Removes the key from the map and updates the secondary indexes.
+    pub fn remove(&mut self, key: &K) {
+        if let Some(old_value) = self.map.get(key) {
+            self.secondary.remove(self.map.fork(), key, &old_value);
+            self.map.remove(key);
+        }
+    }
+
+    /// Clears the map and the secondary indexes.
+    ///
+    /// # Notes
+    ///
+    /// Currently this method is not optimized to delete large set of data. During the execution of
+    /// this method the amount of allocated memory is linearly dependent on the number of elements
+    /// in the index.
+    pub fn clear(&mut self) {
+        let entries: Vec<(K, V)> = self.map.iter().collect();
+        for &(ref key, ref value) in &entries {
+            self.secondary.remove(self.map.fork(), key, value);
+        }
+        self.map.clear();
+    }
+}
+
+impl<'a, K, V> Iterator for SecondaryIndexIter<'a, K, V>
+where
+    K: StorageKey,
+{
+    type Item = (K::Owned, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for key in &mut self.keys {
+            let value = (self.get)(key.borrow());
+            if let Some(value) = value {
+                return Some((key, value));
+            }
+        }
+        None
+    }
+}
+
+impl<'a, K, V> fmt::Debug for SecondaryIndexIter<'a, K, V>
+where
+    K: StorageKey,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SecondaryIndexIter")
+            .field("remaining_keys", &self.keys.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Database, MemoryDB};
+    use super::*;
+    use crypto::{hash, Hash};
+
+    fn users<T: AsRef<Snapshot>>(view: T) -> IndexedMapIndex<T, u64, String> {
+        IndexedMapIndex::new("users", view)
+            .with_index("by_name", |_: &u64, name: &String| name.clone())
+            .with_index("by_len", |_: &u64, name: &String| name.len() as u64)
+    }
+
+    #[test]
+    fn indexes_are_updated() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        {
+            let mut index = users(&mut fork);
+            index.put(&1, "alice".to_owned());
+            index.put(&2, "bob".to_owned());
+            index.put(&3, "carol".to_owned());
+            index.put(&4, "bob".to_owned());
+
+            assert_eq!(index.keys_by("by_name", "bob"), vec![2, 4]);
+            assert_eq!(index.keys_by("by_len", &5_u64), vec![1, 3]);
+            assert!(index.keys_by("by_name", "dave").is_empty());
+
+            index.put(&2, "dave".to_owned());
+            assert_eq!(index.keys_by("by_name", "bob"), vec![4]);
+            assert_eq!(index.keys_by("by_name", "dave"), vec![2]);
+            assert_eq!(index.keys_by("by_len", &3_u64), vec![4]);
+            assert_eq!(index.keys_by("by_len", &4_u64), vec![2]);
+
+            index.remove(&1);
+            index.remove(&10);
+            assert_eq!(index.keys_by("by_len", &5_u64), vec![3]);
+            assert_eq!(index.get(&1), None);
+        }
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        let index = users(&snapshot);
+        assert_eq!(
+            index.iter_by("by_len", &5_u64).collect::<Vec<_>>(),
+            vec![(3, "carol".to_owned())]
+        );
+        assert_eq!(index.primary().keys().collect::<Vec<_>>(), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn clear_removes_secondary_keys() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let mut index = users(&mut fork);
+        index.put(&1, "alice".to_owned());
+        index.put(&2, "alice".to_owned());
+
+        index.clear();
+        assert!(index.keys_by("by_name", "alice").is_empty());
+        assert_eq!(index.iter_by("by_len", &5_u64).next(), None);
+
+        index.put(&3, "alice".to_owned());
+        assert_eq!(index.keys_by("by_name", "alice"), vec![3]);
+    }
+
+    #[test]
+    fn proof_map_indexes() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let mut index = IndexedProofMapIndex::new("balances", &mut fork)
+            .with_index("by_balance", |_: &Hash, balance: &u64| *balance);
+
+        let (alice, bob) = (hash(&[1]), hash(&[2]));
+        index.put(&alice, 10);
+        index.put(&bob, 10);
+        let mut keys = vec![alice, bob];
+        keys.sort();
+        assert_eq!(index.keys_by("by_balance", &10_u64), keys);
+
+        index.put(&alice, 20);
+        assert_eq!(index.keys_by("by_balance", &10_u64), vec![bob]);
+        assert_eq!(
+            index.iter_by("by_balance", &20_u64).collect::<Vec<_>>(),
+            vec![(alice, 20)]
+        );
+
+        let root = index.merkle_root();
+        let proof = index.primary().get_proof(alice).check().unwrap();
+        assert_eq!(proof.merkle_root(), root);
+
+        index.clear();
+        assert!(index.keys_by("by_balance", &10_u64).is_empty());
+        assert_eq!(index.merkle_root(), Hash::zero());
+    }
+
+    #[test]
+    #[should_panic(expected = "no secondary index named `by_age`")]
+    fn unknown_index() {
+        let db = MemoryDB::new();
+        let snapshot = db.snapshot();
+        users(&snapshot).keys_by("by_age", &1_u64);
+    }
+
+    #[test]
+    #[should_panic(expected = "secondary index `by_name` is already defined")]
+    fn duplicate_index() {
+        let db = MemoryDB::new();
+        let snapshot = db.snapshot();
+        users(&snapshot).with_index("by_name", |key: &u64, _: &String| *key);
+    }
+}
@@ -19,9 +19,9 @@
 
 use byteorder::{BigEndian, ByteOrder};
 
-use std::{borrow::Cow, cell::Cell, marker::PhantomData};
+use std::{borrow::Cow, cell::Cell, marker::PhantomData, ops::RangeBounds};
 
-use super::{base_index::{BaseIndex, BaseIndexIter},
+use super::{base_index::{list_start_bound, BaseIndex, BaseIndexIter},
             indexes_metadata::IndexType,
             Fork,
             Snapshot,
@@ -382,6 +382,34 @@ where
             base_iter: self.base.iter_from(&(), &from),
         }
     }
+
+    /// Returns an iterator over the list items at the positions within the specified range.
+    /// The iterator element type is (u64, V).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, SparseListIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = SparseListIndex::new("name", &mut fork);
+    ///
+    /// index.extend([1, 2, 3, 4, 5].iter().cloned());
+    /// index.remove(3);
+    ///
+    /// let items: Vec<(u64, i32)> = index.range(1..5).rev().collect();
+    /// assert_eq!(items, vec![(4, 5), (2, 3), (1, 2)]);
+    /// ```
+    pub fn range<R: RangeBounds<u64>>(&self, range: R) -> SparseListIndexIter<V> {
+        SparseListIndexIter {
+            base_iter: self.base.range(
+                &(),
+                list_start_bound(range.start_bound()),
+                range.end_bound(),
+            ),
+        }
+    }
 }
 
 impl<'a, V> SparseListIndex<&'a mut Fork, V>
@@ -600,6 +628,15 @@ where
     }
 }
 
+impl<'a, V> DoubleEndedIterator for SparseListIndexIter<'a, V>
+where
+    V: StorageValue,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.base_iter.next_back()
+    }
+}
+
 impl<'a> Iterator for SparseListIndexKeys<'a> {
     type Item = u64;
 
@@ -608,6 +645,12 @@ impl<'a> Iterator for SparseListIndexKeys<'a> {
     }
 }
 
+impl<'a> DoubleEndedIterator for SparseListIndexKeys<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.base_iter.next_back().map(|(k, ..)| k)
+    }
+}
+
 impl<'a, V> Iterator for SparseListIndexValues<'a, V>
 where
     V: StorageValue,
@@ -619,6 +662,15 @@ where
     }
 }
 
+impl<'a, V> DoubleEndedIterator for SparseListIndexValues<'a, V>
+where
+    V: StorageValue,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.base_iter.next_back().map(|(.., v)| v)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::SparseListIndex;
@@ -760,6 +812,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "rocksdb")]
     mod rocksdb_tests {
         use std::path::Path;
         use storage::{Database, DbOptions, RocksDB};
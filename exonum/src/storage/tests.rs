@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::Bound::{self, Excluded, Included, Unbounded};
+
 use super::{Database, Fork, Snapshot};
 
 const IDX_NAME: &'static str = "idx_name";
@@ -179,13 +181,161 @@ fn changelog<T: Database>(db: T) {
     assert_eq!(fork.get(IDX_NAME, &[4]), None);
 }
 
+fn savepoints<T: Database>(db: T) {
+    let mut fork = db.fork();
+    fork.put(IDX_NAME, vec![1], vec![1]);
+    db.merge(fork.into_patch()).unwrap();
+
+    let mut fork = db.fork();
+    fork.put(IDX_NAME, vec![8], vec![8]);
+    fork.checkpoint();
+    fork.put(IDX_NAME, vec![2], vec![2]);
+
+    fork.savepoint("outer");
+    fork.put(IDX_NAME, vec![1], vec![10]);
+    fork.savepoint("inner");
+    fork.remove(IDX_NAME, vec![1]);
+    fork.put(IDX_NAME, vec![3], vec![3]);
+    assert_eq!(fork.get(IDX_NAME, &[1]), None);
+
+    fork.rollback_to_savepoint("inner");
+    assert_eq!(fork.get(IDX_NAME, &[1]), Some(vec![10]));
+    assert_eq!(fork.get(IDX_NAME, &[3]), None);
+
+    fork.savepoint("inner");
+    fork.put(IDX_NAME, vec![4], vec![4]);
+    // Rolling back the outer savepoint discards the nested one as well.
+    fork.rollback_to_savepoint("outer");
+    assert_eq!(fork.get(IDX_NAME, &[1]), Some(vec![1]));
+    assert_eq!(fork.get(IDX_NAME, &[2]), Some(vec![2]));
+    assert_eq!(fork.get(IDX_NAME, &[4]), None);
+
+    fork.savepoint("released");
+    fork.put(IDX_NAME, vec![5], vec![5]);
+    fork.remove_by_prefix(IDX_NAME, None);
+    fork.release_savepoint("released");
+    assert_eq!(fork.get(IDX_NAME, &[1]), None);
+    assert_eq!(fork.get(IDX_NAME, &[8]), None);
+
+    // Released changes are still rolled back with the checkpoint.
+    fork.rollback();
+    assert_eq!(fork.get(IDX_NAME, &[1]), Some(vec![1]));
+    assert_eq!(fork.get(IDX_NAME, &[2]), None);
+    assert_eq!(fork.get(IDX_NAME, &[5]), None);
+    assert_eq!(fork.get(IDX_NAME, &[8]), Some(vec![8]));
+
+    // Savepoints can be used without a checkpoint.
+    fork.savepoint("standalone");
+    fork.put(IDX_NAME, vec![6], vec![6]);
+    fork.savepoint("nested");
+    fork.put(IDX_NAME, vec![7], vec![7]);
+    fork.release_savepoint("standalone");
+    fork.checkpoint();
+    fork.commit();
+    db.merge(fork.into_patch()).unwrap();
+
+    let snapshot = db.snapshot();
+    assert_eq!(snapshot.get(IDX_NAME, &[6]), Some(vec![6]));
+    assert_eq!(snapshot.get(IDX_NAME, &[7]), Some(vec![7]));
+    assert_eq!(snapshot.get(IDX_NAME, &[8]), Some(vec![8]));
+}
+
+fn remove_by_prefix<T: Database>(db: T) {
+    let mut fork = db.fork();
+    fork.put(IDX_NAME, vec![1], vec![1]);
+    fork.put(IDX_NAME, vec![2, 1], vec![21]);
+    fork.put(IDX_NAME, vec![2, 2], vec![22]);
+    fork.put(IDX_NAME, vec![3], vec![3]);
+    db.merge(fork.into_patch()).unwrap();
+
+    let mut fork = db.fork();
+    fork.remove_by_prefix(IDX_NAME, Some(&vec![2]));
+    db.merge(fork.into_patch()).unwrap();
+
+    let snapshot = db.snapshot();
+    assert_eq!(snapshot.get(IDX_NAME, &[1]), Some(vec![1]));
+    assert_eq!(snapshot.get(IDX_NAME, &[2, 1]), None);
+    assert_eq!(snapshot.get(IDX_NAME, &[2, 2]), None);
+    assert_eq!(snapshot.get(IDX_NAME, &[3]), Some(vec![3]));
+}
+
+fn fork_iter_rev<T: Database>(db: T) {
+    fn collect_rev(snapshot: &Snapshot, to: Bound<&[u8]>) -> Vec<u8> {
+        let mut keys = Vec::new();
+        let mut iter = snapshot.iter_rev(IDX_NAME, to);
+        while let Some((k, v)) = iter.next() {
+            assert_eq!(k, v);
+            keys.push(k[0]);
+        }
+        keys
+    }
+
+    let mut fork = db.fork();
+    fork.put(IDX_NAME, vec![10], vec![10]);
+    fork.put(IDX_NAME, vec![20], vec![20]);
+    fork.put(IDX_NAME, vec![30], vec![30]);
+    fork.put("other", vec![40], vec![40]);
+    db.merge(fork.into_patch()).unwrap();
+
+    let snapshot = db.snapshot();
+    assert_eq!(collect_rev(&*snapshot, Unbounded), vec![30, 20, 10]);
+    assert_eq!(collect_rev(&*snapshot, Included(&[20][..])), vec![20, 10]);
+    assert_eq!(collect_rev(&*snapshot, Excluded(&[20][..])), vec![10]);
+    assert_eq!(collect_rev(&*snapshot, Included(&[25][..])), vec![20, 10]);
+    assert_eq!(collect_rev(&*snapshot, Excluded(&[10][..])), Vec::<u8>::new());
+
+    let mut fork = db.fork();
+    fork.put(IDX_NAME, vec![5], vec![5]);
+    fork.put(IDX_NAME, vec![25], vec![25]);
+    fork.put(IDX_NAME, vec![35], vec![35]);
+    fork.remove(IDX_NAME, vec![20]);
+    assert_eq!(collect_rev(&fork, Unbounded), vec![35, 30, 25, 10, 5]);
+    assert_eq!(collect_rev(&fork, Included(&[30][..])), vec![30, 25, 10, 5]);
+    assert_eq!(collect_rev(&fork, Excluded(&[25][..])), vec![10, 5]);
+    assert_eq!(collect_rev(&fork, Included(&[20][..])), vec![10, 5]);
+
+    db.merge(fork.into_patch()).unwrap();
+    let snapshot = db.snapshot();
+    assert_eq!(collect_rev(&*snapshot, Unbounded), vec![35, 30, 25, 10, 5]);
+}
+
 mod memorydb_tests {
-    use super::super::MemoryDB;
+    use tempdir::TempDir;
+
+    use super::super::{Database, ListIndex, MemoryDB, ProofMapIndex, Snapshot};
+    #[cfg(feature = "rocksdb")]
+    use super::super::{DbOptions, RocksDB};
+    use crypto::Hash;
 
     fn memorydb_database() -> MemoryDB {
         MemoryDB::new()
     }
 
+    fn fill_database(db: &Database) {
+        let mut fork = db.fork();
+        {
+            let mut map = ProofMapIndex::new("map", &mut fork);
+            for i in 0_u8..20 {
+                map.put(&Hash::new([i; 32]), u64::from(i));
+            }
+        }
+        {
+            let mut list = ListIndex::new_in_family("list", &1_u8, &mut fork);
+            list.extend(vec![1_u64, 2, 3]);
+        }
+        db.merge(fork.into_patch()).unwrap();
+    }
+
+    fn assert_same_contents(expected: &Snapshot, actual: &Snapshot) {
+        let expected_map: ProofMapIndex<_, Hash, u64> = ProofMapIndex::new("map", expected);
+        let actual_map: ProofMapIndex<_, Hash, u64> = ProofMapIndex::new("map", actual);
+        assert_eq!(actual_map.merkle_root(), expected_map.merkle_root());
+        assert_eq!(actual_map.iter().count(), 20);
+
+        let list: ListIndex<_, u64> = ListIndex::new_in_family("list", &1_u8, actual);
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
     #[test]
     fn test_memory_fork_iter() {
         super::fork_iter(memorydb_database());
@@ -195,13 +345,79 @@ mod memorydb_tests {
     fn test_memory_changelog() {
         super::changelog(memorydb_database());
     }
+
+    #[test]
+    fn test_memory_remove_by_prefix() {
+        super::remove_by_prefix(memorydb_database());
+    }
+
+    #[test]
+    fn test_memory_savepoints() {
+        super::savepoints(memorydb_database());
+    }
+
+    #[test]
+    #[should_panic(expected = "no active savepoint named `unknown`")]
+    fn test_memory_rollback_to_unknown_savepoint() {
+        let db = memorydb_database();
+        let mut fork = db.fork();
+        fork.savepoint("known");
+        fork.rollback_to_savepoint("unknown");
+    }
+
+    #[test]
+    #[should_panic(expected = "call checkpoint before rollback or commit")]
+    fn test_memory_checkpoint_within_savepoint() {
+        let db = memorydb_database();
+        let mut fork = db.fork();
+        fork.savepoint("savepoint");
+        fork.checkpoint();
+    }
+
+    #[test]
+    fn test_memory_fork_iter_rev() {
+        super::fork_iter_rev(memorydb_database());
+    }
+
+    #[test]
+    fn test_memory_dump_and_load() {
+        let dir = TempDir::new("exonum_memorydb1").unwrap();
+        let path = dir.path().join("dump");
+        let db = memorydb_database();
+        fill_database(&db);
+
+        db.dump_to(&path).unwrap();
+        let loaded = MemoryDB::load_from(&path).unwrap();
+        assert_same_contents(&db.snapshot(), &loaded.snapshot());
+
+        assert!(MemoryDB::load_from(dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "rocksdb")]
+    fn test_memory_rocksdb_conversion() {
+        let dir = TempDir::new("exonum_memorydb2").unwrap();
+        let db = memorydb_database();
+        fill_database(&db);
+
+        let rocks_db = db.to_rocksdb(dir.path(), &DbOptions::default()).unwrap();
+        assert_same_contents(&db.snapshot(), &rocks_db.snapshot());
+        drop(rocks_db);
+
+        let rocks_db = RocksDB::open(dir.path(), &DbOptions::default()).unwrap();
+        let converted = MemoryDB::from_rocksdb(&rocks_db).unwrap();
+        assert_same_contents(&db.snapshot(), &converted.snapshot());
+    }
 }
 
+#[cfg(feature = "rocksdb")]
 mod rocksdb_tests {
-    use super::super::{DbOptions, RocksDB};
+    use super::super::{CompressionType, Database, DbOptions, IndexOptions, RocksDB};
     use std::path::Path;
     use tempdir::TempDir;
 
+    use super::IDX_NAME;
+
     fn rocksdb_database(path: &Path) -> RocksDB {
         let options = DbOptions::default();
         RocksDB::open(path, &options).unwrap()
@@ -220,4 +436,505 @@ mod rocksdb_tests {
         let path = dir.path();
         super::changelog(rocksdb_database(path));
     }
+
+    #[test]
+    fn test_rocksdb_remove_by_prefix() {
+        let dir = TempDir::new("exonum_rocksdb4").unwrap();
+        let path = dir.path();
+        super::remove_by_prefix(rocksdb_database(path));
+    }
+
+    #[test]
+    fn test_rocksdb_savepoints() {
+        let dir = TempDir::new("exonum_rocksdb6").unwrap();
+        let path = dir.path();
+        super::savepoints(rocksdb_database(path));
+    }
+
+    #[test]
+    fn test_rocksdb_fork_iter_rev() {
+        let dir = TempDir::new("exonum_rocksdb5").unwrap();
+        let path = dir.path();
+        super::fork_iter_rev(rocksdb_database(path));
+    }
+
+    #[test]
+    fn test_rocksdb_checkpoint() {
+        let dir = TempDir::new("exonum_rocksdb3").unwrap();
+        let db = rocksdb_database(&dir.path().join("db"));
+
+        let mut fork = db.fork();
+        fork.put(IDX_NAME, vec![1], vec![1]);
+        fork.put("other_idx", vec![2], vec![2]);
+        db.merge(fork.into_patch()).unwrap();
+
+        let checkpoint_path = dir.path().join("checkpoint");
+        db.create_checkpoint(&checkpoint_path).unwrap();
+        assert!(db.create_checkpoint(&checkpoint_path).is_err());
+
+        // Changes made after the checkpoint was created should not affect it.
+        let mut fork = db.fork();
+        fork.put(IDX_NAME, vec![3], vec![3]);
+        db.merge(fork.into_patch()).unwrap();
+
+        let checkpoint = rocksdb_database(&checkpoint_path);
+        let snapshot = checkpoint.snapshot();
+        assert_eq!(snapshot.get(IDX_NAME, &[1]), Some(vec![1]));
+        assert_eq!(snapshot.get("other_idx", &[2]), Some(vec![2]));
+        assert_eq!(snapshot.get(IDX_NAME, &[3]), None);
+    }
+
+    #[test]
+    fn test_rocksdb_backup() {
+        let dir = TempDir::new("exonum_rocksdb8").unwrap();
+        let db = rocksdb_database(&dir.path().join("db"));
+        let backup_path = dir.path().join("backup");
+
+        let mut fork = db.fork();
+        fork.put(IDX_NAME, vec![1], vec![1]);
+        db.merge_sync(fork.into_patch()).unwrap();
+        db.create_backup(&backup_path).unwrap();
+
+        let mut fork = db.fork();
+        fork.put("other_idx", vec![2], vec![2]);
+        db.merge_sync(fork.into_patch()).unwrap();
+        db.create_backup(&backup_path).unwrap();
+
+        // The database is restored from the latest backup into a new directory only.
+        let restored_path = dir.path().join("restored");
+        RocksDB::restore_backup(&backup_path, &restored_path).unwrap();
+        assert!(RocksDB::restore_backup(&backup_path, &restored_path).is_err());
+        assert!(RocksDB::restore_backup(dir.path().join("missing"), dir.path().join("x")).is_err());
+        assert!(!dir.path().join("x").exists());
+
+        let restored = rocksdb_database(&restored_path);
+        let snapshot = restored.snapshot();
+        assert_eq!(snapshot.get(IDX_NAME, &[1]), Some(vec![1]));
+        assert_eq!(snapshot.get("other_idx", &[2]), Some(vec![2]));
+    }
+
+    #[test]
+    fn test_rocksdb_tuning_options() {
+        let dir = TempDir::new("exonum_rocksdb7").unwrap();
+        let mut options = DbOptions {
+            compression_type: Some(CompressionType::None),
+            block_cache_size_per_cf: Some(1 << 20),
+            write_buffer_size: Some(1 << 20),
+            max_total_wal_size: Some(1 << 24),
+            disable_wal: true,
+            max_background_compactions: Some(2),
+            ..DbOptions::default()
+        };
+        options.indexes.insert(
+            IDX_NAME.to_owned(),
+            IndexOptions {
+                bloom_filter_bits: Some(10),
+                ..IndexOptions::default()
+            },
+        );
+
+        {
+            let db = RocksDB::open(dir.path(), &options).unwrap();
+            let mut fork = db.fork();
+            fork.put(IDX_NAME, vec![1], vec![1]);
+            fork.put("other_idx", vec![2], vec![2]);
+            db.merge(fork.into_patch()).unwrap();
+
+            let mut fork = db.fork();
+            fork.put(IDX_NAME, vec![3], vec![3]);
+            db.merge_sync(fork.into_patch()).unwrap();
+        }
+
+        let db = RocksDB::open(dir.path(), &options).unwrap();
+        let snapshot = db.snapshot();
+        assert_eq!(snapshot.get(IDX_NAME, &[1]), Some(vec![1]));
+        assert_eq!(snapshot.get("other_idx", &[2]), Some(vec![2]));
+        assert_eq!(snapshot.get(IDX_NAME, &[3]), Some(vec![3]));
+        assert_eq!(snapshot.get(IDX_NAME, &[4]), None);
+    }
+}
+
+mod logdb_tests {
+    use super::super::{Database, DbOptions, LogDB};
+    use std::{fs::{self, OpenOptions}, io::Write, path::Path};
+    use tempdir::TempDir;
+
+    use super::IDX_NAME;
+
+    fn logdb_database(path: &Path) -> LogDB {
+        let options = DbOptions::default();
+        LogDB::open(path, &options).unwrap()
+    }
+
+    #[test]
+    fn test_logdb_fork_iter() {
+        let dir = TempDir::new("exonum_logdb1").unwrap();
+        super::fork_iter(logdb_database(dir.path()));
+    }
+
+    #[test]
+    fn test_logdb_changelog() {
+        let dir = TempDir::new("exonum_logdb2").unwrap();
+        super::changelog(logdb_database(dir.path()));
+    }
+
+    #[test]
+    fn test_logdb_remove_by_prefix() {
+        let dir = TempDir::new("exonum_logdb7").unwrap();
+        super::remove_by_prefix(logdb_database(dir.path()));
+    }
+
+    #[test]
+    fn test_logdb_savepoints() {
+        let dir = TempDir::new("exonum_logdb9").unwrap();
+        super::savepoints(logdb_database(dir.path()));
+    }
+
+    #[test]
+    fn test_logdb_fork_iter_rev() {
+        let dir = TempDir::new("exonum_logdb8").unwrap();
+        super::fork_iter_rev(logdb_database(dir.path()));
+    }
+
+    #[test]
+    fn test_logdb_snapshot_isolation() {
+        let dir = TempDir::new("exonum_logdb3").unwrap();
+        let db = logdb_database(dir.path());
+
+        let mut fork = db.fork();
+        fork.put(IDX_NAME, vec![1], vec![1]);
+        fork.put(IDX_NAME, vec![2], vec![2]);
+        db.merge(fork.into_patch()).unwrap();
+        let snapshot = db.snapshot();
+
+        let mut fork = db.fork();
+        fork.put(IDX_NAME, vec![1], vec![10]);
+        fork.remove(IDX_NAME, vec![2]);
+        fork.put(IDX_NAME, vec![3], vec![3]);
+        db.merge(fork.into_patch()).unwrap();
+
+        assert_eq!(snapshot.get(IDX_NAME, &[1]), Some(vec![1]));
+        assert_eq!(snapshot.get(IDX_NAME, &[2]), Some(vec![2]));
+        assert!(!snapshot.contains(IDX_NAME, &[3]));
+        let mut iter = snapshot.iter(IDX_NAME, &[]);
+        assert_eq!(iter.next(), Some((&[1][..], &[1][..])));
+        assert_eq!(iter.next(), Some((&[2][..], &[2][..])));
+        assert_eq!(iter.next(), None);
+
+        let snapshot = db.snapshot();
+        assert_eq!(snapshot.get(IDX_NAME, &[1]), Some(vec![10]));
+        assert!(!snapshot.contains(IDX_NAME, &[2]));
+        assert_eq!(snapshot.get(IDX_NAME, &[3]), Some(vec![3]));
+    }
+
+    #[test]
+    fn test_logdb_iter_large_table() {
+        let dir = TempDir::new("exonum_logdb4").unwrap();
+        let db = logdb_database(dir.path());
+
+        let mut fork = db.fork();
+        for i in 0..1000_u16 {
+            fork.put(IDX_NAME, vec![(i >> 8) as u8, i as u8], vec![i as u8]);
+        }
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        let mut iter = snapshot.iter(IDX_NAME, &[1]);
+        let mut count = 0;
+        while let Some((key, _)) = iter.next() {
+            assert_eq!(key, &[(count >> 8) as u8 + 1, count as u8][..]);
+            count += 1;
+        }
+        assert_eq!(count, 1000 - 256);
+    }
+
+    #[test]
+    fn test_logdb_reopen() {
+        let dir = TempDir::new("exonum_logdb5").unwrap();
+        {
+            let db = logdb_database(dir.path());
+            let mut fork = db.fork();
+            fork.put(IDX_NAME, vec![1], vec![1]);
+            fork.put(IDX_NAME, vec![2], vec![2]);
+            db.merge(fork.into_patch()).unwrap();
+
+            let mut fork = db.fork();
+            fork.remove(IDX_NAME, vec![1]);
+            fork.put("other_idx", vec![3], vec![3]);
+            db.merge_sync(fork.into_patch()).unwrap();
+        }
+
+        // Simulate a record torn by a crash.
+        {
+            let mut log = OpenOptions::new()
+                .append(true)
+                .open(dir.path().join("data.log"))
+                .unwrap();
+            log.write_all(&[1, 2, 3]).unwrap();
+        }
+
+        let db = logdb_database(dir.path());
+        let snapshot = db.snapshot();
+        assert!(!snapshot.contains(IDX_NAME, &[1]));
+        assert_eq!(snapshot.get(IDX_NAME, &[2]), Some(vec![2]));
+        assert_eq!(snapshot.get("other_idx", &[3]), Some(vec![3]));
+
+        // The database remains writable after the torn record is discarded.
+        let mut fork = db.fork();
+        fork.put(IDX_NAME, vec![4], vec![4]);
+        db.merge(fork.into_patch()).unwrap();
+        db.compact().unwrap();
+        drop(snapshot);
+        drop(db);
+
+        let db = logdb_database(dir.path());
+        let snapshot = db.snapshot();
+        assert_eq!(snapshot.get(IDX_NAME, &[2]), Some(vec![2]));
+        assert_eq!(snapshot.get(IDX_NAME, &[4]), Some(vec![4]));
+        assert_eq!(snapshot.get("other_idx", &[3]), Some(vec![3]));
+    }
+
+    #[test]
+    fn test_logdb_corrupted_record() {
+        let dir = TempDir::new("exonum_logdb10").unwrap();
+        {
+            let db = logdb_database(dir.path());
+            for i in 1..4 {
+                let mut fork = db.fork();
+                fork.put(IDX_NAME, vec![i], vec![i]);
+                db.merge_sync(fork.into_patch()).unwrap();
+            }
+        }
+
+        // Corrupt the payload of the first record.
+        let log_path = dir.path().join("data.log");
+        let mut data = fs::read(&log_path).unwrap();
+        let len = data.len();
+        data[len / 6] ^= 0xff;
+        fs::write(&log_path, &data).unwrap();
+
+        assert!(LogDB::open(dir.path(), &DbOptions::default()).is_err());
+        assert_eq!(fs::read(&log_path).unwrap(), data);
+    }
+
+    #[test]
+    fn test_logdb_checkpoint() {
+        let dir = TempDir::new("exonum_logdb6").unwrap();
+        let db = logdb_database(&dir.path().join("db"));
+
+        let mut fork = db.fork();
+        fork.put(IDX_NAME, vec![1], vec![1]);
+        db.merge(fork.into_patch()).unwrap();
+
+        let checkpoint_path = dir.path().join("checkpoint");
+        db.create_checkpoint(&checkpoint_path).unwrap();
+        assert!(db.create_checkpoint(&checkpoint_path).is_err());
+
+        let checkpoint = logdb_database(&checkpoint_path);
+        assert_eq!(checkpoint.snapshot().get(IDX_NAME, &[1]), Some(vec![1]));
+    }
+}
+
+mod patch_tests {
+    use serde_json;
+
+    use super::super::{Database, MemoryDB, Patch, PatchDiff};
+    use super::IDX_NAME;
+
+    fn sample_patch(db: &MemoryDB) -> Patch {
+        let mut fork = db.fork();
+        fork.put(IDX_NAME, vec![1], vec![10]);
+        fork.put(IDX_NAME, vec![2], vec![20]);
+        fork.remove(IDX_NAME, vec![3]);
+        fork.put("other_idx", vec![], vec![1, 2, 3]);
+        fork.into_patch()
+    }
+
+    fn apply(patch: Patch) -> MemoryDB {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        fork.put(IDX_NAME, vec![3], vec![30]);
+        db.merge(fork.into_patch()).unwrap();
+        db.merge(patch).unwrap();
+        db
+    }
+
+    fn assert_applied(db: &MemoryDB) {
+        let snapshot = db.snapshot();
+        assert_eq!(snapshot.get(IDX_NAME, &[1]), Some(vec![10]));
+        assert_eq!(snapshot.get(IDX_NAME, &[2]), Some(vec![20]));
+        assert_eq!(snapshot.get(IDX_NAME, &[3]), None);
+        assert_eq!(snapshot.get("other_idx", &[]), Some(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn test_patch_binary_roundtrip() {
+        let patch = sample_patch(&MemoryDB::new());
+        let bytes = patch.to_bytes();
+        let restored = Patch::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.len(), patch.len());
+        assert_eq!(restored.to_bytes(), bytes);
+        assert_applied(&apply(restored));
+    }
+
+    #[test]
+    fn test_patch_binary_format() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        fork.put("b", vec![1], vec![2, 3]);
+        fork.remove("a", vec![4]);
+        let bytes = fork.into_patch().to_bytes();
+
+        let mut expected = b"EXNMPTCH".to_vec();
+        expected.extend_from_slice(&[1, 2, 0, 0, 0]);
+        expected.extend_from_slice(&[1, 0, 0, 0, b'a', 1, 0, 0, 0]);
+        expected.extend_from_slice(&[1, 0, 0, 0, 4, 0]);
+        expected.extend_from_slice(&[1, 0, 0, 0, b'b', 1, 0, 0, 0]);
+        expected.extend_from_slice(&[1, 0, 0, 0, 1, 1, 2, 0, 0, 0, 2, 3]);
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn test_patch_binary_errors() {
+        let bytes = sample_patch(&MemoryDB::new()).to_bytes();
+
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] = 0;
+        assert!(Patch::from_bytes(&wrong_magic).is_err());
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[8] = 2;
+        assert!(Patch::from_bytes(&wrong_version).is_err());
+
+        assert!(Patch::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(Patch::from_bytes(&trailing).is_err());
+    }
+
+    #[test]
+    fn test_patch_json_roundtrip() {
+        let patch = sample_patch(&MemoryDB::new());
+        let json = serde_json::to_value(&patch).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "idx_name": { "01": "0a", "02": "14", "03": null },
+                "other_idx": { "": "010203" },
+            })
+        );
+
+        let restored: Patch = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.to_bytes(), patch.to_bytes());
+        assert_applied(&apply(restored));
+
+        let invalid = json!({ "idx_name": { "zz": null } });
+        assert!(serde_json::from_value::<Patch>(invalid).is_err());
+    }
+
+    #[test]
+    fn test_patch_diff() {
+        let db = MemoryDB::new();
+        let patch = sample_patch(&db);
+        db.merge(patch).unwrap();
+
+        let mut fork = db.fork();
+        fork.put(IDX_NAME, vec![1], vec![10]);
+        fork.put(IDX_NAME, vec![2], vec![21]);
+        fork.put(IDX_NAME, vec![4], vec![40]);
+        fork.remove(IDX_NAME, vec![5]);
+        fork.put(IDX_NAME, vec![6], vec![60]);
+        fork.remove(IDX_NAME, vec![6]);
+        fork.remove("other_idx", vec![]);
+        let patch = fork.into_patch();
+
+        let diff = PatchDiff::new(&patch, &db.snapshot());
+        assert_eq!(diff.indexes().len(), 2);
+        assert_eq!(diff.indexes()[0].entries.len(), 2);
+        assert_eq!(
+            diff.to_string(),
+            "index `idx_name`: 1 added, 1 modified, 0 removed\n\
+             ~ 02: 14 -> 15\n\
+             + 04: 28\n\
+             index `other_idx`: 0 added, 0 modified, 1 removed\n\
+             - : 010203\n"
+        );
+
+        db.merge(patch.clone()).unwrap();
+        assert!(PatchDiff::new(&patch, &db.snapshot()).is_empty());
+    }
+}
+
+#[cfg(feature = "conformance")]
+mod conformance_tests {
+    use tempdir::TempDir;
+
+    use std::path::Path;
+
+    use super::super::{CachedDatabase, Database, DbOptions, LogDB, MemoryDB, Patch, Result,
+                       Snapshot};
+    #[cfg(feature = "rocksdb")]
+    use super::super::RocksDB;
+
+    /// A database removing its directory when dropped.
+    struct TempDatabase<D> {
+        db: D,
+        _dir: TempDir,
+    }
+
+    impl<D> TempDatabase<D> {
+        fn new<F: FnOnce(&Path) -> D>(open: F) -> Self {
+            let dir = TempDir::new("exonum_conformance").unwrap();
+            TempDatabase {
+                db: open(dir.path()),
+                _dir: dir,
+            }
+        }
+    }
+
+    impl<D: Database> Database for TempDatabase<D> {
+        fn snapshot(&self) -> Box<Snapshot> {
+            self.db.snapshot()
+        }
+
+        fn merge(&self, patch: Patch) -> Result<()> {
+            self.db.merge(patch)
+        }
+
+        fn merge_sync(&self, patch: Patch) -> Result<()> {
+            self.db.merge_sync(patch)
+        }
+    }
+
+    mod memorydb {
+        use super::MemoryDB;
+
+        database_conformance_tests!(MemoryDB::new);
+    }
+
+    mod cached_memorydb {
+        use super::{CachedDatabase, MemoryDB};
+
+        database_conformance_tests!(|| CachedDatabase::new(MemoryDB::new(), 16));
+    }
+
+    mod logdb {
+        use super::{DbOptions, LogDB, TempDatabase};
+
+        database_conformance_tests!(
+            || TempDatabase::new(|path| LogDB::open(path, &DbOptions::default()).unwrap()),
+            cases = 64
+        );
+    }
+
+    #[cfg(feature = "rocksdb")]
+    mod rocksdb {
+        use super::{DbOptions, RocksDB, TempDatabase};
+
+        database_conformance_tests!(
+            || TempDatabase::new(|path| RocksDB::open(path, &DbOptions::default()).unwrap()),
+            cases = 64
+        );
+    }
 }
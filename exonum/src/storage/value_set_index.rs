@@ -387,6 +387,15 @@ where
     }
 }
 
+impl<'a, V> DoubleEndedIterator for ValueSetIndexIter<'a, V>
+where
+    V: StorageValue,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.base_iter.next_back()
+    }
+}
+
 impl<'a> Iterator for ValueSetIndexHashes<'a> {
     type Item = Hash;
 
@@ -394,3 +403,9 @@ impl<'a> Iterator for ValueSetIndexHashes<'a> {
         self.base_iter.next().map(|(k, ..)| k)
     }
 }
+
+impl<'a> DoubleEndedIterator for ValueSetIndexHashes<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.base_iter.next_back().map(|(k, ..)| k)
+    }
+}
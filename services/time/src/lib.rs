@@ -26,6 +26,8 @@ extern crate chrono;
 #[macro_use]
 extern crate exonum;
 #[macro_use]
+extern crate exonum_derive;
+#[macro_use]
 extern crate failure;
 extern crate iron;
 extern crate router;
@@ -44,13 +46,15 @@ pub mod time_provider;
 /// Node transactions.
 pub mod transactions;
 
+use chrono::{DateTime, Utc};
 use exonum::{api::Api,
-             blockchain::{ApiContext, Service, ServiceContext, Transaction, TransactionSet},
+             blockchain::{ApiContext, Migration, Service, ServiceContext, Transaction,
+                          TransactionSet},
              crypto::Hash,
              encoding::{self, serialize::json::reexport::Value},
              helpers::fabric::{Context, ServiceFactory},
              messages::RawTransaction,
-             storage::{Fork, Snapshot}};
+             storage::{remove_index, Entry, Fork, Snapshot}};
 use iron::Handler;
 use router::Router;
 use schema::TimeSchema;
@@ -114,6 +118,24 @@ impl Service for TimeService {
         Value::Null
     }
 
+    fn schema_version(&self) -> u16 {
+        1
+    }
+
+    fn migrations(&self) -> Vec<Migration> {
+        vec![
+            Migration::new(1, "Store the time in `ProofEntry`", |fork: &mut Fork| {
+                let name = format!("{}.time", SERVICE_NAME);
+                let time: Option<DateTime<Utc>> = Entry::new(name.as_str(), &*fork).get();
+                remove_index(&name, fork);
+                if let Some(time) = time {
+                    TimeSchema::new(fork).time_mut().set(time);
+                }
+                Ok(())
+            }),
+        ]
+    }
+
     /// Creates transaction after commit of the block.
     fn after_commit(&self, context: &ServiceContext) {
         // The transaction must be created by the validator.
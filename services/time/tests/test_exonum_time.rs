@@ -21,13 +21,14 @@ extern crate exonum_time;
 extern crate pretty_assertions;
 
 use chrono::{DateTime, Duration, TimeZone, Utc};
-use exonum::{blockchain::{Schema, Transaction, TransactionErrorType},
+use exonum::{blockchain::{Blockchain, Schema, Transaction, TransactionErrorType},
              crypto::{gen_keypair, CryptoHash, PublicKey},
              helpers::{Height, ValidatorId},
-             storage::Snapshot};
+             storage::{remove_index, Entry, MapIndex, Snapshot}};
 use exonum_testkit::{ApiKind, TestKitApi, TestKitBuilder, TestNode};
 use exonum_time::{api::ValidatorTime, schema::TimeSchema, time_provider::MockTimeProvider,
-                  transactions::Error, transactions::TxTime, TimeService};
+                  transactions::Error, transactions::TxTime, TimeService,
+                  SERVICE_ID};
 
 use std::{collections::HashMap, iter::FromIterator};
 
@@ -59,7 +60,8 @@ fn assert_transaction_result<S: AsRef<Snapshot>, T: Transaction>(
 ) -> Option<String> {
     let result = Schema::new(snapshot)
         .transaction_results()
-        .get(&transaction.hash());
+        .get(&transaction.hash())
+        .map(|outcome| outcome.result().clone());
     match result {
         Some(Err(e)) => {
             assert_eq!(e.error_type(), TransactionErrorType::Code(expected_code));
@@ -279,7 +281,8 @@ fn test_exonum_time_service_with_7_validators() {
         assert_eq!(
             Schema::new(testkit.snapshot())
                 .transaction_results()
-                .get(&tx.hash()),
+                .get(&tx.hash())
+                .map(|outcome| outcome.result().clone()),
             Some(Ok(()))
         );
 
@@ -337,6 +340,70 @@ fn test_mock_provider() {
     );
 }
 
+#[test]
+fn test_time_proof() {
+    let mock_provider = MockTimeProvider::default();
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(TimeService::with_provider(mock_provider.clone()))
+        .create();
+
+    mock_provider.set_time(Utc.timestamp(10, 0));
+    testkit.create_blocks_until(Height(2));
+
+    let snapshot = testkit.snapshot();
+    let schema = Schema::new(&snapshot);
+    let state_hash = *schema.last_block().state_hash();
+    let table_key = Blockchain::service_table_unique_key(SERVICE_ID, 1);
+    let table_proof = schema.get_proof_to_service_table(SERVICE_ID, 1);
+    let time_proof = TimeSchema::new(&snapshot).time().get_proof();
+
+    let time = time_proof
+        .validate_to_state(table_proof, &table_key, state_hash)
+        .unwrap();
+    assert_eq!(time, Some(&mock_provider.time()));
+
+    let wrong_table_proof = schema.get_proof_to_service_table(SERVICE_ID, 0);
+    assert!(
+        time_proof
+            .validate_to_state(wrong_table_proof, &table_key, state_hash)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_time_migration() {
+    let mock_provider = MockTimeProvider::default();
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(TimeService::with_provider(mock_provider.clone()))
+        .create();
+
+    mock_provider.set_time(Utc.timestamp(10, 0));
+    testkit.create_blocks_until(Height(2));
+    let state_hash = TimeSchema::new(testkit.snapshot()).state_hash();
+
+    // Revert the database to the layout of the schema version 0, which stores the time
+    // in an `Entry`.
+    {
+        let blockchain = testkit.blockchain_mut();
+        let mut fork = blockchain.fork();
+        remove_index("exonum_time.time", &mut fork);
+        Entry::new("exonum_time.time", &mut fork).set(mock_provider.time());
+        MapIndex::<_, String, u16>::new("core.service_schema_versions", &mut fork)
+            .remove("exonum_time");
+        blockchain.merge(fork.into_patch()).unwrap();
+        assert!(blockchain.check_schema_versions().is_err());
+    }
+
+    let reports = testkit.blockchain_mut().migrate().unwrap();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].applied, vec![1]);
+    testkit.blockchain().check_schema_versions().unwrap();
+
+    let schema = TimeSchema::new(testkit.snapshot());
+    assert_eq!(schema.time().get(), Some(mock_provider.time()));
+    assert_eq!(schema.state_hash(), state_hash);
+}
+
 #[test]
 fn test_selected_time_less_than_time_in_storage() {
     let mut testkit = TestKitBuilder::validator()
@@ -379,7 +446,8 @@ fn test_selected_time_less_than_time_in_storage() {
         assert_eq!(
             Schema::new(testkit.snapshot())
                 .transaction_results()
-                .get(&tx.hash()),
+                .get(&tx.hash())
+                .map(|outcome| outcome.result().clone()),
             Some(Ok(()))
         );
     }
@@ -430,7 +498,8 @@ fn test_transaction_time_less_than_validator_time_in_storage() {
     assert_eq!(
         Schema::new(testkit.snapshot())
             .transaction_results()
-            .get(&tx0.hash()),
+            .get(&tx0.hash())
+            .map(|outcome| outcome.result().clone()),
         Some(Ok(()))
     );
 